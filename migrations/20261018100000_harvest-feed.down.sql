@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS services.locations_region_id_idx;
+DROP INDEX IF EXISTS services.harvests_active_location_id_idx;
+DROP INDEX IF EXISTS services.harvests_active_cultivar_id_idx;
@@ -0,0 +1,12 @@
+-- Add up migration script here
+
+-- Indexes backing the harvest feed filters and keyset pagination
+
+CREATE INDEX IF NOT EXISTS harvests_active_cultivar_id_idx
+    ON services.harvests (cultivar_id) WHERE finished = false;
+
+CREATE INDEX IF NOT EXISTS harvests_active_location_id_idx
+    ON services.harvests (location_id) WHERE finished = false;
+
+CREATE INDEX IF NOT EXISTS locations_region_id_idx
+    ON services.locations (region_id);
@@ -0,0 +1,16 @@
+-- Add down migration script here
+
+DROP VIEW IF EXISTS services.active_locations;
+
+DROP INDEX IF EXISTS services.locations_lat_lng_idx;
+DROP INDEX IF EXISTS services.locations_earth_idx;
+
+ALTER TABLE services.locations
+    DROP COLUMN IF EXISTS longitude,
+    DROP COLUMN IF EXISTS latitude;
+
+CREATE VIEW services.active_locations AS (
+	SELECT *
+	FROM services.locations location_
+	WHERE location_.deleted = false
+);
@@ -0,0 +1,26 @@
+-- Add up migration script here
+
+-- Geo search over farm locations, distances are computed with earthdistance.
+CREATE EXTENSION IF NOT EXISTS cube;
+CREATE EXTENSION IF NOT EXISTS earthdistance;
+
+-- Location coords are stored as a geo point, { "x": longitude, "y": latitude }
+ALTER TABLE services.locations
+    ADD COLUMN IF NOT EXISTS latitude double precision
+        GENERATED ALWAYS AS ((coords->>'y')::double precision) STORED,
+    ADD COLUMN IF NOT EXISTS longitude double precision
+        GENERATED ALWAYS AS ((coords->>'x')::double precision) STORED;
+
+CREATE INDEX IF NOT EXISTS locations_earth_idx
+    ON services.locations USING gist (ll_to_earth(latitude, longitude))
+    WHERE latitude IS NOT NULL AND longitude IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS locations_lat_lng_idx
+    ON services.locations (latitude, longitude);
+
+-- Recreate the view so it includes the new columns
+CREATE OR REPLACE VIEW services.active_locations AS (
+	SELECT *
+	FROM services.locations location_
+	WHERE location_.deleted = false
+);
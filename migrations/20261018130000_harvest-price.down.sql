@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS services.harvests_active_price_per_unit_idx;
+
+DROP FUNCTION IF EXISTS services.price_per_unit(jsonb);
+DROP FUNCTION IF EXISTS services.price_unit(jsonb);
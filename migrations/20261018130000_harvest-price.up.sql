@@ -0,0 +1,29 @@
+-- Add up migration script here
+
+-- Unit name of a harvest price, `kg`, `crate`, `bundle` or `head`.
+-- Prices are stored as { "amount": 60, "unit": { "Kg": 10 } } or { "amount": 250, "unit": "Crate" }
+CREATE OR REPLACE FUNCTION services.price_unit(price jsonb)
+RETURNS text
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT lower(
+        CASE jsonb_typeof(price->'unit')
+            WHEN 'object' THEN (SELECT unit FROM jsonb_object_keys(price->'unit') unit LIMIT 1)
+            ELSE price->>'unit'
+        END
+    );
+$$;
+
+-- Price amount normalised per single unit, `Kg(n)` prices are normalised per kg.
+CREATE OR REPLACE FUNCTION services.price_per_unit(price jsonb)
+RETURNS numeric
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT CASE jsonb_typeof(price->'unit')
+        WHEN 'object' THEN (price->>'amount')::numeric / NULLIF((price->'unit'->>'Kg')::numeric, 0)
+        ELSE (price->>'amount')::numeric
+    END;
+$$;
+
+CREATE INDEX IF NOT EXISTS harvests_active_price_per_unit_idx
+    ON services.harvests (services.price_per_unit(price)) WHERE finished = false;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+UPDATE services.harvests harvest
+SET price = harvest.price - 'currency';
+
+ALTER TABLE services.countries DROP COLUMN IF EXISTS currency;
@@ -0,0 +1,49 @@
+-- Add up migration script here
+
+-- Price units: Kg(n), Tonne(n), Litre(n), Crate, Bundle, Sack, Tray, Punnet, Dozen and Head.
+-- Crates, bundles, sacks, trays and punnets may declare their weight in kg, `weight_kg`.
+
+-- Price normalised per kg, NULL if the price unit weight is not known
+CREATE OR REPLACE FUNCTION services.price_per_kg(price jsonb)
+RETURNS numeric
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT (price->>'amount')::numeric / NULLIF(
+        CASE services.price_unit(price)
+            WHEN 'kg' THEN (price->'unit'->>'Kg')::numeric
+            WHEN 'tonne' THEN (price->'unit'->>'Tonne')::numeric * 1000
+            WHEN 'crate' THEN (price->>'weight_kg')::numeric
+            WHEN 'bundle' THEN (price->>'weight_kg')::numeric
+            WHEN 'sack' THEN (price->>'weight_kg')::numeric
+            WHEN 'tray' THEN (price->>'weight_kg')::numeric
+            WHEN 'punnet' THEN (price->>'weight_kg')::numeric
+        END,
+        0
+    );
+$$;
+
+-- Price normalised per litre, NULL if the price unit is not a volume
+CREATE OR REPLACE FUNCTION services.price_per_litre(price jsonb)
+RETURNS numeric
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT (price->>'amount')::numeric / NULLIF((price->'unit'->>'Litre')::numeric, 0);
+$$;
+
+-- The expression index has to be rebuilt when its function changes
+DROP INDEX IF EXISTS services.harvests_active_price_per_unit_idx;
+
+-- Price normalised per kg, per litre or otherwise per item
+CREATE OR REPLACE FUNCTION services.price_per_unit(price jsonb)
+RETURNS numeric
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT COALESCE(
+        services.price_per_kg(price),
+        services.price_per_litre(price),
+        (price->>'amount')::numeric / CASE services.price_unit(price) WHEN 'dozen' THEN 12 ELSE 1 END
+    );
+$$;
+
+CREATE INDEX IF NOT EXISTS harvests_active_price_per_unit_idx
+    ON services.harvests (services.price_per_unit(price)) WHERE finished = false;
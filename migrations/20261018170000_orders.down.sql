@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+ALTER TABLE features.direct_messages DROP COLUMN IF EXISTS order_id;
+
+DROP TABLE IF EXISTS services.orders;
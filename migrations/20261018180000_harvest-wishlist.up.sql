@@ -0,0 +1,12 @@
+-- Add up migration script here
+
+-- User harvests `wishlists`
+CREATE TABLE IF NOT EXISTS services.harvests_wishlist(
+    user_id uuid REFERENCES accounts.users (id) ON DELETE CASCADE,
+    harvest_id uuid REFERENCES services.harvests (id) ON DELETE CASCADE,
+    created_at timestamptz NOT NULL,
+    PRIMARY KEY(user_id, harvest_id)
+);
+
+CREATE INDEX IF NOT EXISTS harvests_wishlist_harvest_id_idx
+    ON services.harvests_wishlist (harvest_id);
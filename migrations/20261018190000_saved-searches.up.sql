@@ -0,0 +1,21 @@
+-- Add up migration script here
+
+-- User saved harvest searches, matching new harvests are emailed in a digest.
+-- `filters` holds a `HarvestFilter` and `geo` a `GeoQuery`.
+-- `unsubscribe_token` is kept in plaintext: it is included in every digest
+-- email and only allows turning the search email alerts off.
+CREATE TABLE IF NOT EXISTS features.saved_searches(
+    id uuid PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES accounts.users (id) ON DELETE CASCADE,
+    name text NOT NULL,
+    filters jsonb NOT NULL,
+    geo jsonb NOT NULL,
+    email_alerts boolean NOT NULL DEFAULT true,
+    unsubscribe_token text NOT NULL UNIQUE,
+    last_checked_at timestamptz NOT NULL,
+    created_at timestamptz NOT NULL,
+    updated_at timestamptz
+);
+
+CREATE INDEX IF NOT EXISTS saved_searches_user_id_idx
+    ON features.saved_searches (user_id);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS maintenance.harvest_archive_runs;
+
+DROP SCHEMA IF EXISTS maintenance;
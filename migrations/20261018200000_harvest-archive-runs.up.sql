@@ -0,0 +1,12 @@
+-- Add up migration script here
+
+CREATE SCHEMA IF NOT EXISTS maintenance;
+
+-- Stale harvests archived by each server maintenance run
+CREATE TABLE IF NOT EXISTS maintenance.harvest_archive_runs(
+    id uuid PRIMARY KEY,
+    archived_count integer NOT NULL,
+    farmers_notified integer NOT NULL DEFAULT 0,
+    started_at timestamptz NOT NULL,
+    finished_at timestamptz NOT NULL
+);
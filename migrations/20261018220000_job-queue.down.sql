@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS maintenance.dead_letter_jobs;
+DROP TABLE IF EXISTS maintenance.queued_jobs;
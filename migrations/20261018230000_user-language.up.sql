@@ -0,0 +1,6 @@
+-- Add up migration script here
+
+-- Language the user receives emails in, an ISO-639-1 code.
+ALTER TABLE accounts.users
+    ADD COLUMN IF NOT EXISTS language text NOT NULL DEFAULT 'en'
+        CHECK (language IN ('en', 'af', 'ng', 'de'));
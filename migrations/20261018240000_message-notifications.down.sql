@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS features.message_status_unread_idx;
+ALTER TABLE features.message_status DROP COLUMN IF EXISTS emailed_at;
+DROP TABLE IF EXISTS features.message_notification_settings;
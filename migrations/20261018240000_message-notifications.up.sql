@@ -0,0 +1,30 @@
+-- Add up migration script here
+
+-- User unread direct message email notification settings,
+-- users without settings are emailed at any time.
+-- Quiet hours are in the user local time: `utc_offset` minutes from UTC,
+-- a start after the end spans midnight.
+CREATE TABLE IF NOT EXISTS features.message_notification_settings(
+    user_id uuid PRIMARY KEY REFERENCES accounts.users (id) ON DELETE CASCADE,
+    email_enabled boolean NOT NULL DEFAULT true,
+    quiet_hours_start time,
+    quiet_hours_end time,
+    utc_offset smallint NOT NULL DEFAULT 0 CHECK (utc_offset BETWEEN -720 AND 840),
+    updated_at timestamptz NOT NULL,
+    CHECK ((quiet_hours_start IS NULL) = (quiet_hours_end IS NULL))
+);
+
+-- When the receiver was emailed about the unread message
+ALTER TABLE features.message_status
+    ADD COLUMN IF NOT EXISTS emailed_at timestamptz;
+
+-- Messages used to be stored as read when sent, the ones the receiver
+-- never marked as read are unread and are not emailed about.
+UPDATE features.message_status
+    SET is_read = false,
+        emailed_at = now()
+WHERE is_read AND read_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS message_status_unread_idx
+    ON features.message_status (message_id)
+    WHERE NOT is_read AND emailed_at IS NULL;
@@ -0,0 +1,22 @@
+-- Add up migration script here
+
+-- Server instances sharing the chat feed over LISTEN/NOTIFY,
+-- instances that stop sending heartbeats are removed with their presence.
+CREATE TABLE IF NOT EXISTS features.chat_instances(
+    id uuid PRIMARY KEY,
+    started_at timestamptz NOT NULL,
+    heartbeat_at timestamptz NOT NULL
+);
+
+-- Number of chat connections each user has open on each server instance,
+-- a user is online while they have a connection on any instance.
+CREATE TABLE IF NOT EXISTS features.chat_presence(
+    instance_id uuid NOT NULL REFERENCES features.chat_instances (id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES accounts.users (id) ON DELETE CASCADE,
+    connections integer NOT NULL CHECK (connections > 0),
+    connected_at timestamptz NOT NULL,
+    PRIMARY KEY (instance_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS chat_presence_user_id_idx
+    ON features.chat_presence (user_id);
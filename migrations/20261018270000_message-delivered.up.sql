@@ -0,0 +1,10 @@
+-- Add up migration script here
+
+-- When the receiver's client acknowledged receiving the message
+ALTER TABLE features.message_status
+    ADD COLUMN IF NOT EXISTS delivered_at timestamptz;
+
+-- Messages read before delivery receipts were added were delivered
+UPDATE features.message_status
+    SET delivered_at = read_at
+WHERE is_read AND delivered_at IS NULL;
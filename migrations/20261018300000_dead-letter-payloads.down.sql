@@ -0,0 +1,3 @@
+-- Add down migration script here
+
+-- The removed email messages can not be restored.
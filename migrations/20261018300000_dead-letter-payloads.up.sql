@@ -0,0 +1,6 @@
+-- Add up migration script here
+
+-- Strip the message from dead-lettered emails, see `QueuedJob::dead_letter`.
+UPDATE maintenance.dead_letter_jobs
+    SET payload = payload - 'message'
+WHERE kind = 'send-email';
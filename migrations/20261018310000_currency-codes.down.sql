@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS services.harvests_active_currency_idx;
+
+ALTER TABLE services.countries
+    DROP CONSTRAINT IF EXISTS countries_currency_check,
+    ADD CONSTRAINT countries_currency_check CHECK (currency ~ '^[A-Z]{3}$');
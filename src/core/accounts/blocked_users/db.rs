@@ -0,0 +1,190 @@
+//! Blocked users database impl
+
+use crate::{
+    accounts::user::models::UserIndex,
+    endpoint::{EndpointRejection, EndpointResult},
+    error::ServerResult,
+    server::state::DatabaseConnection,
+    types::{ModelID, Pagination},
+};
+
+use super::{
+    forms::BlockUserInsertData,
+    models::{BlockedUser, BlockedUserList},
+};
+
+impl BlockedUser {
+    /// Fetches the users blocked by the user from the database
+    #[tracing::instrument(name = "Fetch Blocked Users", skip(db))]
+    pub async fn records(
+        user_id: ModelID,
+        pg: Pagination,
+        db: DatabaseConnection,
+    ) -> ServerResult<BlockedUserList> {
+        let (offset, limit) = pg.offset_limit();
+        match sqlx::query!(
+            r#"
+                SELECT user_.id AS user_id,
+                    user_.first_name AS user_first_name,
+                    user_.last_name AS user_last_name,
+                    profile.photo AS user_photo,
+                    block.created_at AS blocked_at
+                FROM accounts.user_blocks block
+                INNER JOIN accounts.users user_
+                    ON block.blocked_user_id = user_.id
+                LEFT JOIN accounts.user_profiles profile
+                    ON user_.id = profile.user_id
+
+                WHERE block.user_id = $1
+                ORDER BY block.created_at DESC
+                LIMIT $2
+                OFFSET $3;
+            "#,
+            user_id.0,
+            limit,
+            offset,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let users = records
+                    .into_iter()
+                    .map(|rec| {
+                        let user = UserIndex::from_row(
+                            rec.user_id.into(),
+                            rec.user_first_name,
+                            rec.user_last_name,
+                            rec.user_photo,
+                        );
+                        Self::from_row(user, rec.blocked_at)
+                    })
+                    .collect();
+
+                Ok(users)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch blocked users: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Inserts a user into the user blocked users,
+    /// blocking an already blocked user does nothing.
+    #[tracing::instrument(name = "Insert Blocked User", skip(db))]
+    pub async fn insert(block: BlockUserInsertData, db: DatabaseConnection) -> ServerResult<()> {
+        match sqlx::query!(
+            r#"
+                INSERT INTO accounts.user_blocks(
+                    user_id,
+                    blocked_user_id,
+                    created_at
+                )
+                VALUES($1, $2, $3)
+                ON CONFLICT (user_id, blocked_user_id) DO NOTHING;
+            "#,
+            block.user_id.0,
+            block.blocked_user_id.0,
+            block.created_at,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("User blocked successfully: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to block user: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Removes a user from the user blocked users,
+    /// returns false if the user was not blocked.
+    #[tracing::instrument(name = "Delete Blocked User", skip(db))]
+    pub async fn delete(
+        user_id: ModelID,
+        blocked_user_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                DELETE FROM accounts.user_blocks block
+                WHERE block.user_id = $1
+                    AND block.blocked_user_id = $2;
+            "#,
+            user_id.0,
+            blocked_user_id.0,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("User unblocked: {:?}", result);
+                Ok(result.rows_affected() > 0)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to unblock user: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Returns true if either of the users blocked the other
+    #[tracing::instrument(name = "Fetch Is Blocked Between", skip(db))]
+    pub async fn is_blocked_between(
+        user_id: ModelID,
+        other_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                SELECT EXISTS(
+                    SELECT 1 FROM accounts.user_blocks block
+                    WHERE (block.user_id = $1 AND block.blocked_user_id = $2)
+                        OR (block.user_id = $2 AND block.blocked_user_id = $1)
+                ) AS "exists!"
+            "#,
+            user_id.0,
+            other_id.0,
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.exists),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch user blocks: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Returns true if the user exists
+///
+/// # Errors
+///
+/// Return an internal server error if the database query fails.
+pub async fn user_exists(user_id: ModelID, db: DatabaseConnection) -> EndpointResult<bool> {
+    match sqlx::query!(
+        r#"
+            SELECT EXISTS(
+                SELECT 1 FROM accounts.users user_
+                WHERE user_.id = $1
+            ) AS "exists!"
+        "#,
+        user_id.0
+    )
+    .fetch_one(&db.pool)
+    .await
+    {
+        Ok(rec) => Ok(rec.exists),
+        Err(err) => {
+            tracing::error!("Database error: {}", err);
+            Err(EndpointRejection::internal_server_error())
+        }
+    }
+}
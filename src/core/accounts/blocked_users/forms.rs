@@ -0,0 +1,82 @@
+//! Blocked users forms impls
+
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Json, Request},
+};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    auth::CurrentUser,
+    endpoint::{validators::ValidateString, EndpointRejection, EndpointResult},
+    server::state::ServerState,
+    types::ModelID,
+};
+
+use super::db::user_exists;
+
+/// Block user form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockUserForm {
+    pub user_id: String,
+}
+
+/// Block user form cleaned data
+#[derive(Debug, Clone)]
+pub struct BlockUserInsertData {
+    pub user_id: ModelID,
+    pub blocked_user_id: ModelID,
+    pub created_at: OffsetDateTime,
+}
+
+impl BlockUserForm {
+    /// Converts `Self` into `BlockUserInsertData`
+    #[must_use]
+    pub fn data(self, user_id: ModelID) -> BlockUserInsertData {
+        BlockUserInsertData {
+            user_id,
+            blocked_user_id: ModelID::from_str_unchecked(self.user_id),
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Validates block user form inputs
+    fn validate(&self) -> EndpointResult<()> {
+        self.user_id.validate_id("Invalid user id")?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for BlockUserForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let user = { CurrentUser::from_parts(&mut parts, state).await? };
+        let Json(block) =
+            Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        // Validate form fields
+        block.validate()?;
+
+        let blocked_user_id = ModelID::from_str_unchecked(&block.user_id);
+        if blocked_user_id == user.id {
+            return Err(EndpointRejection::BadRequest(
+                "You can not block yourself.".into(),
+            ));
+        }
+        if !user_exists(blocked_user_id, state.database()).await? {
+            return Err(EndpointRejection::NotFound("User not found.".into()));
+        }
+
+        Ok(block)
+    }
+}
@@ -0,0 +1,57 @@
+//! Blocked users http handlers impls
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    auth::CurrentUser,
+    endpoint::{EndpointRejection, EndpointResult},
+    server::state::DatabaseConnection,
+    types::{ModelID, Pagination},
+};
+
+use super::{
+    forms::BlockUserForm,
+    models::{BlockedUser, BlockedUserList},
+};
+
+/// Handles the `GET /account/users/me/blocked-users` route.
+#[tracing::instrument(skip(db, user))]
+pub async fn blocked_user_list(
+    user: CurrentUser,
+    pg: Option<Query<Pagination>>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<BlockedUserList>> {
+    let pagination = pg.unwrap_or_default().0;
+    let users = BlockedUser::records(user.id, pagination, db).await?;
+    Ok(Json(users))
+}
+
+/// Handles the `POST /account/users/me/blocked-users` route.
+#[tracing::instrument(skip(db, user, form))]
+pub async fn blocked_user_add(
+    user: CurrentUser,
+    State(db): State<DatabaseConnection>,
+    form: BlockUserForm,
+) -> EndpointResult<StatusCode> {
+    BlockedUser::insert(form.data(user.id), db).await?;
+    Ok(StatusCode::CREATED)
+}
+
+/// Handles the `DELETE /account/users/me/blocked-users/:user_id` route.
+#[tracing::instrument(skip(db, user))]
+pub async fn blocked_user_remove(
+    user: CurrentUser,
+    blocked_user_id: ModelID,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<StatusCode> {
+    if BlockedUser::delete(user.id, blocked_user_id, db).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(EndpointRejection::NotFound(
+            "User not found in your blocked users.".into(),
+        ))
+    }
+}
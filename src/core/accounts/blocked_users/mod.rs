@@ -0,0 +1,6 @@
+//! Blocked users impls
+
+pub mod db;
+pub mod forms;
+pub mod handlers;
+pub mod models;
@@ -0,0 +1,28 @@
+//! Blocked users models impls
+
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::accounts::user::models::UserIndex;
+
+/// A `Vec` of blocked users
+pub type BlockedUserList = Vec<BlockedUser>;
+
+/// A user blocked by the user
+///
+/// Returned by `blocked_user_list` handler.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedUser {
+    #[serde(flatten)]
+    pub user: UserIndex,
+    pub blocked_at: OffsetDateTime,
+}
+
+impl BlockedUser {
+    /// Creates a new `BlockedUser` from the database row
+    #[must_use]
+    pub const fn from_row(user: UserIndex, blocked_at: OffsetDateTime) -> Self {
+        Self { user, blocked_at }
+    }
+}
@@ -16,16 +16,21 @@ pub type AccountDeleteRequests = Vec<ModelID>;
 pub struct AccountDelete;
 
 impl AccountDelete {
-    /// Permanently delete all the accounts the requested for deletion
-    pub async fn permanently_delete_accounts(db: DatabaseConnection) {
+    /// Permanently delete all the accounts the requested for deletion,
+    /// return the number of accounts deleted.
+    ///
+    /// # Errors
+    ///
+    /// Return database error if failed to fetch delete requests
+    pub async fn permanently_delete_accounts(db: DatabaseConnection) -> ServerResult<u64> {
         // Get account delete requests
         let accounts = match Self::records(db.clone()).await {
             Ok(records) => records,
-            Err(_err) => {
+            Err(err) => {
                 tracing::error!(
                     "Account could not be permanently deleted; failed to fetch delete requests."
                 );
-                return;
+                return Err(err);
             }
         };
         // Spawn account delete task for each user_id
@@ -34,8 +39,14 @@ impl AccountDelete {
             let db = db.clone();
             task.spawn(async move { User::delete(user_id, db).await });
         }
-        // Wait for tasks to run to completion; We don't care about the results.
-        while let Some(_res) = task.join_next().await {}
+        // Wait for tasks to run to completion; failed deletes are retried on the next run.
+        let mut deleted = 0;
+        while let Some(res) = task.join_next().await {
+            if matches!(res, Ok(Ok(()))) {
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
     }
 
     /// Fetches account delete request records from the database
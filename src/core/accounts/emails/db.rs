@@ -7,7 +7,7 @@ use crate::{
     endpoint::EndpointRejection,
     error::{ServerError, ServerResult},
     server::state::DatabaseConnection,
-    types::ModelID,
+    types::{Language, ModelID},
 };
 
 use super::{
@@ -44,16 +44,17 @@ impl EmailModel {
         }
     }
 
-    /// Fetches the user `first_name` and `email` from the database
+    /// Fetches the user `first_name`, `email` and `language` from the database
     #[tracing::instrument(skip(db))]
     pub async fn find_user(
         user_id: ModelID,
         db: DatabaseConnection,
-    ) -> ServerResult<(String, String)> {
+    ) -> ServerResult<(String, String, Language)> {
         match sqlx::query!(
             r#"
                 SELECT user_.first_name,
-                    address.email
+                    address.email,
+                    user_.language
                 FROM accounts.users user_
                 LEFT JOIN accounts.emails address
                     ON user_.id = address.user_id
@@ -64,7 +65,7 @@ impl EmailModel {
         .fetch_one(&db.pool)
         .await
         {
-            Ok(rec) => Ok((rec.first_name, rec.email)),
+            Ok(rec) => Ok((rec.first_name, rec.email, Language::from_row(&rec.language))),
             Err(err) => {
                 // Handle database constraint error
                 handle_email_database_error(&err)?;
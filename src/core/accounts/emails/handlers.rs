@@ -8,6 +8,7 @@ use crate::{
     auth::{hash_token, CurrentUser, Token},
     endpoint::{EndpointRejection, EndpointResult},
     mail::Mail,
+    queue::Task,
     server::state::DatabaseConnection,
 };
 
@@ -51,10 +52,11 @@ pub async fn email_update(
     EmailModel::insert_pending_update(user.id, values, db.clone()).await?;
 
     // Send confirmation code to an existing email
-    let (first_name, email_address) = EmailModel::find_user(user.id, db).await?;
+    let (first_name, email_address, language) = EmailModel::find_user(user.id, db.clone()).await?;
 
-    let email = outlook.approve_email_change(&first_name, &email_address, &new_email, &code)?;
-    outlook.send(email).await?;
+    let email =
+        outlook.approve_email_change(&first_name, &email_address, language, &new_email, &code)?;
+    Task::send_email(email)?.enqueue(db).await?;
 
     Ok((
         remove_password_verified_cookie(cookie_jar),
@@ -78,10 +80,10 @@ pub async fn email_change_approve(
 
         EmailModel::insert_new_email_verify_code(user.id, hash, db.clone()).await?;
 
-        let (first_name, _) = EmailModel::find_user(user.id, db).await?;
+        let (first_name, _, language) = EmailModel::find_user(user.id, db.clone()).await?;
 
-        let email = outlook.verify_new_email(&first_name, &new_email, &code)?;
-        outlook.send(email).await?;
+        let email = outlook.verify_new_email(&first_name, &new_email, language, &code)?;
+        Task::send_email(email)?.enqueue(db).await?;
 
         Ok((
             StatusCode::OK,
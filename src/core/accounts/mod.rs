@@ -1,5 +1,6 @@
 //! User account impls
 
+pub mod blocked_users;
 mod delete;
 pub mod emails;
 pub mod passwords;
@@ -11,6 +11,7 @@ use crate::{
     auth::{hash_token, CurrentUser, Token, TokenConfirm},
     endpoint::{EndpointRejection, EndpointResult},
     mail::Mail,
+    queue::Task,
     server::state::DatabaseConnection,
     SERVER_DOMAIN_NAME,
 };
@@ -96,20 +97,20 @@ pub async fn password_forgot(
 ) -> EndpointResult<&'static str> {
     let (plaintext, hash) = Token::default().into_parts();
     let email_address = form.email;
-    let Some((user_id, first_name)) =
+    let Some((user_id, first_name, language)) =
         User::find_by_email(email_address.clone(), db.clone()).await?
     else {
         return Err(EndpointRejection::BadRequest(
             "Sorry, we could not find your account.".into(),
         ));
     };
-    PasswordModel::insert_token(user_id, hash, db).await?;
+    PasswordModel::insert_token(user_id, hash, db.clone()).await?;
 
     // Send password reset email
     let domain = SERVER_DOMAIN_NAME.get().unwrap();
     let link = format!("{domain}/account/reset-password?token={plaintext}");
-    let email = outlook.password_reset(&first_name, &email_address, &link)?;
-    outlook.send(email).await?;
+    let email = outlook.password_reset(&first_name, &email_address, language, &link)?;
+    Task::send_email(email)?.enqueue(db).await?;
 
     Ok("Your password reset link was sent to your email ")
 }
@@ -1,6 +1,10 @@
 //! User profile database impl
 
-use crate::{error::ServerResult, server::state::DatabaseConnection, types::ModelID};
+use crate::{
+    error::ServerResult,
+    server::state::DatabaseConnection,
+    types::{Language, ModelID},
+};
 
 use super::{forms::PersonalInfoUpdateData, models::PersonalInfo};
 
@@ -15,6 +19,7 @@ impl PersonalInfo {
                     user_.last_name AS user_last_name, 
                     user_.gender AS user_gender,
                     user_.date_of_birth AS user_date_of_birth, 
+                    user_.language AS user_language,
                     user_.date_joined AS user_date_joined,
                     address.email AS user_email, 
                     phone.phone AS "user_phone?"
@@ -44,6 +49,7 @@ impl PersonalInfo {
                     // rec.user_government_id,
                     rec.user_email,
                     rec.user_phone,
+                    Language::from_row(&rec.user_language),
                     rec.user_date_joined.date(),
                 );
 
@@ -78,13 +84,15 @@ impl PersonalInfo {
                 SET first_name = COALESCE($1, user_.first_name),
                     last_name = $2,
                     gender = $3,
-                    date_of_birth = $4
-                WHERE user_.id = $5
+                    date_of_birth = $4,
+                    language = COALESCE($5, user_.language)
+                WHERE user_.id = $6
             "#,
             values.first_name,
             values.last_name,
             values.gender,
             values.date_of_birth,
+            values.language.map(Language::code),
             id.0
         )
         .execute(&db.pool)
@@ -13,6 +13,7 @@ use crate::{
         EndpointRejection, EndpointResult,
     },
     server::state::ServerState,
+    types::Language,
 };
 
 /// User personal info update form
@@ -23,6 +24,8 @@ pub struct PersonalInfoUpdateForm {
     pub last_name: Option<String>,
     pub gender: Option<String>,
     pub date_of_birth: Option<Date>,
+    /// Language the user receives emails in, unchanged if not set
+    pub language: Option<Language>,
 }
 
 /// User personal info cleaned data
@@ -32,6 +35,7 @@ pub struct PersonalInfoUpdateData {
     pub last_name: Option<String>,
     pub gender: Option<String>,
     pub date_of_birth: Option<Date>,
+    pub language: Option<Language>,
 }
 
 impl From<PersonalInfoUpdateForm> for PersonalInfoUpdateData {
@@ -41,6 +45,7 @@ impl From<PersonalInfoUpdateForm> for PersonalInfoUpdateData {
             last_name: form.last_name,
             gender: form.gender,
             date_of_birth: form.date_of_birth,
+            language: form.language,
         }
     }
 }
@@ -3,7 +3,7 @@
 use serde::Serialize;
 use time::Date;
 
-use crate::types::ModelID;
+use crate::types::{Language, ModelID};
 
 /// User personal infos model
 ///
@@ -18,6 +18,7 @@ pub struct PersonalInfo {
     pub date_of_birth: Option<Date>,
     pub email: String,
     pub phone: Option<String>,
+    pub language: Language,
     pub date_joined: Date,
 }
 
@@ -33,6 +34,7 @@ impl PersonalInfo {
         date_of_birth: Option<Date>,
         email: String,
         phone: Option<String>,
+        language: Language,
         date_joined: Date,
     ) -> Self {
         Self {
@@ -44,6 +46,7 @@ impl PersonalInfo {
             // government_id,
             email,
             phone,
+            language,
             date_joined,
         }
     }
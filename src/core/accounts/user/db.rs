@@ -12,7 +12,7 @@ use crate::{
     server::state::DatabaseConnection,
     services::produce::harvest::delete_harvest_photos,
     types::ModelID,
-    types::Pagination,
+    types::{Language, Pagination},
 };
 
 use super::{
@@ -83,18 +83,20 @@ impl User {
                     first_name, 
                     last_name, 
                     phc_string, 
+                    language,
                     is_staff,
                     is_superuser,
                     is_farmer, 
                     date_joined, 
                     account_locked
                 )
-                 VALUES($1, $2, $3, $4, $5, $6, false, $7, $8);
+                 VALUES($1, $2, $3, $4, $5, $6, $7, false, $8, $9);
             "#,
             user_id.0,
             user.first_name,
             user.last_name,
             user.phc_string,
+            user.language.code(),
             user.is_staff,
             user.is_superuser,
             user.date_joined,
@@ -129,15 +131,16 @@ impl User {
         }
     }
 
-    /// Fetches `user_id` and `first_name` by email from the database
+    /// Fetches `user_id`, `first_name` and `language` by email from the database
     pub async fn find_by_email(
         email: String,
         db: DatabaseConnection,
-    ) -> ServerResult<Option<(ModelID, String)>> {
+    ) -> ServerResult<Option<(ModelID, String, Language)>> {
         match sqlx::query!(
             r#"
                 SELECT user_.id AS user_id,
-                    user_.first_name
+                    user_.first_name,
+                    user_.language
                 FROM accounts.emails address
                 LEFT JOIN accounts.users user_
                     ON address.user_id = user_.id
@@ -148,7 +151,13 @@ impl User {
         .fetch_optional(&db.pool)
         .await
         {
-            Ok(rec) => Ok(rec.map(|rec| (rec.user_id.into(), rec.first_name))),
+            Ok(rec) => Ok(rec.map(|rec| {
+                (
+                    rec.user_id.into(),
+                    rec.first_name,
+                    Language::from_row(&rec.language),
+                )
+            })),
             Err(err) => {
                 tracing::error!("Database error, failed to fetch user by email: {}", err);
                 Err(err.into())
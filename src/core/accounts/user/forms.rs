@@ -16,7 +16,7 @@ use crate::{
     },
     error::ServerResult,
     server::state::ServerState,
-    types::ModelID,
+    types::{Language, ModelID},
 };
 
 /// User sign-up form
@@ -27,6 +27,8 @@ pub struct SignUpForm {
     pub last_name: Option<String>,
     pub email: String,
     pub password: String,
+    /// Language the user receives emails in, English if not set
+    pub language: Option<Language>,
 }
 
 /// Signup cleaned data
@@ -37,6 +39,7 @@ pub struct SignUpData {
     pub last_name: Option<String>,
     pub email: EmailInsertData,
     pub phc_string: String,
+    pub language: Language,
     pub is_staff: bool,
     pub is_superuser: bool,
     pub date_joined: OffsetDateTime,
@@ -86,6 +89,7 @@ impl SignUpForm {
             last_name: self.last_name,
             email: EmailInsertData::new(self.email, email_token),
             phc_string: hash_password(self.password).await?,
+            language: self.language.unwrap_or_default(),
             is_staff: false,
             is_superuser: false,
             date_joined: OffsetDateTime::now_utc(),
@@ -11,6 +11,7 @@ use crate::{
     auth::{hash_token, AdminUser, CurrentUser, SuperUser, Token, TokenConfirm},
     endpoint::{EndpointRejection, EndpointResult},
     mail::Mail,
+    queue::Task,
     server::state::DatabaseConnection,
     types::Pagination,
     SERVER_DOMAIN_NAME,
@@ -45,14 +46,15 @@ pub async fn signup(
     let values = form.try_data(hash).await?;
     let first_name = values.first_name.clone();
     let email_address = values.email.email.clone();
+    let language = values.language;
 
-    User::insert(values, db).await?;
+    User::insert(values, db.clone()).await?;
 
     // Send confirmation email
     let domain = SERVER_DOMAIN_NAME.get().unwrap();
     let link = format!("{domain}/account/confirm?token={plaintext}");
-    let email = outlook.account_confirm(&first_name, &email_address, &link)?;
-    outlook.send(email).await?;
+    let email = outlook.account_confirm(&first_name, &email_address, language, &link)?;
+    Task::send_email(email)?.enqueue(db).await?;
 
     Ok("Please confirm your email address by clicking the email we just sent you.")
 }
@@ -7,7 +7,7 @@ use crate::{
     accounts::emails::forms::EmailInsertData,
     auth::{hash_password, Token},
     server::state::DatabaseConnection,
-    types::ModelID,
+    types::{Language, ModelID},
 };
 
 /// A `Vec` of users
@@ -69,6 +69,7 @@ pub async fn create_unsecure_superuser(
         last_name: None,
         email,
         phc_string: hash_password(password.trim().to_owned()).await.unwrap(),
+        language: Language::English,
         is_staff: true,
         is_superuser: true,
         date_joined: OffsetDateTime::now_utc(),
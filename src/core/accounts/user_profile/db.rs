@@ -47,6 +47,11 @@ impl UserProfile {
                 harvest.id AS "harvest_id?",
                 harvest.price AS "harvest_price?",
                 harvest.images AS harvest_images,
+                services.harvest_low_stock(
+                    harvest.available_quantity,
+                    harvest.reserved_quantity,
+                    harvest.low_stock_threshold
+                ) AS "harvest_low_stock!",
                 harvest.harvest_date AS "harvest_harvest_date?",
                 cultivar.name AS "cultivar_name?",
                 cultivar_category.name AS "cultivar_category?",
@@ -141,6 +146,7 @@ impl UserProfile {
                                         rec.location_coords,
                                         rec.farm_name.unwrap(),
                                         rec.farm_logo,
+                                        rec.harvest_low_stock,
                                         0.into(), // boost amount not important
                                     )
                                 })
@@ -59,7 +59,7 @@ pub async fn user_photo_upload(
     handler.accept().await?; // Receive photo from the client
     if let Some(file) = uploads.files().await {
         // Save an image to the file system
-        let saved_to = file.save_image(USER_UPLOAD_DIR).await?;
+        let saved_to = file.queue_image(USER_UPLOAD_DIR, db.clone()).await?;
 
         // Save image path to the database
         let (new_photo, old_photo) = UserProfile::insert_photo(user.id, saved_to, db).await?;
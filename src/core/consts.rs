@@ -47,6 +47,50 @@ pub const UNAUTHENTICATED_ENDPOINTS: [&str; 6] = [
 /// An error message for when a user entered a wrong password of username
 pub const INVALID_CREDENTIALS_ERR_MSG: &str = "The username or password you provided is incorrect.";
 
+// ===== DIRECT MESSAGES =====
+
+/// Unread direct messages are emailed to the receiver after this delay
+pub const UNREAD_MESSAGE_EMAIL_DELAY: time::Duration = time::Duration::minutes(15);
+/// Unread direct messages older than this are not emailed
+pub const UNREAD_MESSAGE_EMAIL_MAX_AGE: time::Duration = time::Duration::days(2);
+/// Number of unread messages previewed per sender in the email
+pub const UNREAD_MESSAGE_MAX_PREVIEWS: usize = 3;
+/// Max number of characters of an unread message preview
+pub const UNREAD_MESSAGE_PREVIEW_LEN: usize = 140;
+/// Max number of messages returned per conversation history page
+pub const MESSAGE_HISTORY_MAX_LIMIT: usize = 100;
+/// Max number of attachments sent per direct message
+pub const MESSAGE_MAX_ATTACHMENTS: u8 = 5;
+/// Max number of new conversations a user can start per day,
+/// conversations about an order are not counted.
+pub const MESSAGE_MAX_NEW_CONVERSATIONS: i64 = 20;
+/// Max number of the most recent messages saved with a conversation report
+pub const CONVERSATION_REPORT_MAX_MESSAGES: i64 = 200;
+/// Number of chat messages buffered per connection,
+/// connections falling further behind are asked to resync.
+pub const CHAT_CONNECTION_CAPACITY: usize = 64;
+/// Typing started is forwarded at most once per this interval per receiver,
+/// clients resend it while the user keeps typing.
+pub const CHAT_TYPING_THROTTLE: Duration = Duration::from_secs(3);
+/// Interval the server pings chat connections at,
+/// event stream connections are sent a keep-alive comment instead.
+pub const CHAT_PING_INTERVAL: Duration = Duration::from_secs(25);
+/// Chat connections the client sent nothing on for this long are closed,
+/// pongs included, so only unreachable clients time out.
+pub const CHAT_IDLE_TIMEOUT: Duration = Duration::from_mins(1);
+/// Max number of messages replayed to a resumed chat connection,
+/// connections that missed more are asked to resync.
+pub const CHAT_REPLAY_MAX_MESSAGES: i64 = 100;
+/// Postgres channel chat messages are published on between server instances
+pub const CHAT_FEED_CHANNEL: &str = "chat_feed";
+/// Max size in bytes of a chat message published between server instances,
+/// Postgres rejects notification payloads of 8000 bytes or more.
+pub const CHAT_FEED_MAX_PAYLOAD: usize = 7900;
+/// Interval server instances send chat heartbeats at
+pub const CHAT_INSTANCE_HEARTBEAT: time::Duration = time::Duration::seconds(30);
+/// Server instances without a chat heartbeat for this long are removed
+pub const CHAT_INSTANCE_TIMEOUT: time::Duration = time::Duration::seconds(90);
+
 // ===== FILES =====
 
 /// Image formats that can be uploaded on the server.
@@ -55,11 +99,34 @@ pub const SUPPORTED_UPLOAD_IMAGE_FORMATS: [&str; 4] = ["jpeg", "jpg", "png", "we
 pub const IMAGE_OUTPUT_FORMATS: [ImageFormat; 2] = [ImageFormat::Jpeg, ImageFormat::WebP];
 /// Image maximum size allowed on the server
 pub const IMAGE_MAX_SIZE: usize = 20 * 1024 * 1024; // 20 * 1024 * 1024 = 20mb
+/// Unreferenced uploaded files younger than this are not deleted,
+/// the request that uploaded them may still be in progress.
+pub const ORPHANED_FILES_GRACE_PERIOD: time::Duration = time::Duration::hours(24);
+
+// ===== JOB QUEUE =====
+
+/// Number of background job queue workers
+pub const QUEUE_WORKERS: usize = 4;
+/// Number of times a background job is attempted before it's dead-lettered
+pub const QUEUE_MAX_ATTEMPTS: i32 = 5;
+/// Delay before a failed background job is retried,
+/// the delay doubles on every attempt.
+pub const QUEUE_RETRY_BACKOFF: time::Duration = time::Duration::seconds(30);
+/// Maximum delay before a failed background job is retried
+pub const QUEUE_MAX_RETRY_BACKOFF: time::Duration = time::Duration::hours(1);
+/// Jobs locked for longer than this are considered abandoned by a
+/// crashed worker and are picked up again.
+pub const QUEUE_LOCK_TIMEOUT: time::Duration = time::Duration::minutes(15);
+/// How often idle queue workers check for new jobs
+pub const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 // ===== MAIL =====
 
-/// Microsoft outlook mail smtp STARTTLS server
+/// Microsoft outlook mail smtp STARTTLS server,
+/// the default smtp server if `MAIL_SMTP_HOST` is not set.
 pub const OUTLOOK_SMTP_SERVER: &str = "smtp.office365.com";
+/// Address emails are sent from if neither `MAIL_FROM` nor `MAIL_EMAIL` is set
+pub const DEFAULT_MAIL_FROM: &str = "noreply@reapears.com";
 
 // ===== SERVER =====
 
@@ -90,5 +157,10 @@ pub const CULTIVAR_MAX_IMAGE: u8 = 1;
 /// If the harvest has been on the platform for
 /// less-than these days it will be deleted.
 pub const HARVEST_MAX_AGE_TO_ARCHIVE: i64 = 4; // days
+/// Harvests listed for longer than these days are
+/// archived by the server maintenance.
+pub const HARVEST_MAX_LISTING_DAYS: i64 = 90; // days
 /// Number of images allowed to be uploaded per harvest
 pub const HARVEST_MAX_IMAGE: u8 = 5;
+/// Max number of harvests returned per harvest feed page
+pub const HARVEST_FEED_MAX_LIMIT: usize = 100;
@@ -12,11 +12,16 @@ use tokio::task::{self, JoinSet};
 use crate::{
     endpoint::{EndpointRejection, EndpointResult},
     error::{ServerError, ServerResult},
+    queue::{QueuedImage, Task},
+    server::state::DatabaseConnection,
     types::ModelID,
 };
 
 use super::UploadedFile;
 
+/// Extension of uploaded images waiting for their output formats to be saved
+const UPLOAD_EXTENSION: &str = "upload";
+
 /// `DynamicImage` file decode from `UploadedFile`
 #[derive(Debug, Clone)]
 pub struct UploadedImage(Arc<InnerImage>);
@@ -52,6 +57,25 @@ impl UploadedImage {
         self.0.file_ext.extensions_str()[0]
     }
 
+    /// Opens and decodes the image saved at `path`,
+    /// the image will be saved under the `id` file name.
+    pub async fn open(id: ModelID, path: PathBuf) -> ServerResult<Self> {
+        task::spawn_blocking(move || {
+            let reader = ImageReader::open(&path)?.with_guessed_format()?;
+            let Some(file_ext) = reader.format() else {
+                return Err(ServerError::new(format!("Unknown image format: {path:?}")));
+            };
+            let image = reader.decode()?;
+            Ok(Self(Arc::new(InnerImage {
+                id,
+                file_name: id.to_string(),
+                file_ext,
+                image,
+            })))
+        })
+        .await?
+    }
+
     /// Saves an image in the current format to the `upload dir`.
     pub async fn save<P>(self, upload_dir: P) -> ServerResult<PathBuf>
     where
@@ -115,6 +139,52 @@ impl UploadedFile {
         Ok(paths)
     }
 
+    /// Saves the uploaded image and enqueues saving it in all server image output formats,
+    /// return the paths the image will be saved to.
+    ///
+    /// The image is checked before it's saved, encoding the output formats
+    /// is left to the background job queue workers.
+    pub async fn queue_image(
+        self,
+        upload_dir: &str,
+        db: DatabaseConnection,
+    ) -> EndpointResult<Vec<PathBuf>> {
+        let ext = &self.file_ext;
+        // fails if the image format is not supported
+        if !crate::SUPPORTED_UPLOAD_IMAGE_FORMATS.contains(&ext.as_ref()) {
+            tracing::error!("Unsupported image form: {ext:?}");
+            return Err(EndpointRejection::BadRequest(
+                format!("Unsupported image format: {ext:?}. Supported formats: jpg, png.").into(),
+            ));
+        }
+        // Reads the image header only, the image is decoded by the workers
+        let dimensions = ImageReader::new(Cursor::new(&self.content))
+            .with_guessed_format()
+            .map_err(ServerError::from)?
+            .into_dimensions();
+        if let Err(err) = dimensions {
+            tracing::error!("Image error, failed to read uploaded image: {}", err);
+            return Err(EndpointRejection::BadRequest("Invalid image file".into()));
+        }
+
+        let source = Path::new(upload_dir)
+            .join(self.id.to_string())
+            .with_extension(UPLOAD_EXTENSION);
+        super::save_file(&source, &self.content).await?;
+
+        let task = Task::ImageVariants(QueuedImage {
+            id: self.id,
+            source: source.clone(),
+            upload_dir: PathBuf::from(upload_dir),
+        });
+        if let Err(err) = task.enqueue(db).await {
+            tokio::spawn(async move { super::delete_file(&source).await });
+            return Err(err.into());
+        }
+
+        Ok(super::saved_paths(upload_dir, &self.id.to_string()))
+    }
+
     /// Saves as an image to the file system in it's original format.
     pub async fn save_image_original<P>(self, upload_dir: P) -> EndpointResult<PathBuf>
     where
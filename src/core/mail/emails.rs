@@ -1,10 +1,21 @@
 //! Emails impls
-use minijinja::context;
+
+use std::{collections::HashMap, path::Path};
+
+use minijinja::{context, Value};
+use serde::Serialize;
 
 use super::message::EmailMessage;
-use crate::{error::ServerResult, APP_NAME};
+use crate::{
+    error::{ServerError, ServerResult},
+    settings::EMAIL_TEMPLATES_DIR,
+    types::Language,
+    APP_NAME,
+};
 
 // ====== Email Templates ======
+// English templates are embedded, translations are loaded from
+// `EMAIL_TEMPLATES_DIR` as `{name}.{language}.{html|txt}` on startup.
 
 /// An email to user to confirm their email on signup.
 const ACCOUNT_CONFIRMATION_EMAIL_HTML: &str = include_str!(concat!(
@@ -50,24 +61,128 @@ const VERIFY_NEW_EMAIL_CHANGE_EMAIL_TEXT: &str = include_str!(concat!(
     "/static/templates/emails/verify_new_email.txt"
 ));
 
-// ===== Email Template Names =====
-// Names used to identify templates in the email template container
+/// An email to order buyer and farmer on order updates.
+const ORDER_UPDATE_EMAIL_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/static/templates/emails/order_update.html"
+));
+/// An email to order buyer and farmer on order updates.
+const ORDER_UPDATE_EMAIL_TEXT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/static/templates/emails/order_update.txt"
+));
+
+/// A digest email to user of new harvests matching their saved searches.
+const SAVED_SEARCH_DIGEST_EMAIL_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/static/templates/emails/saved_search_digest.html"
+));
+/// A digest email to user of new harvests matching their saved searches.
+const SAVED_SEARCH_DIGEST_EMAIL_TEXT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/static/templates/emails/saved_search_digest.txt"
+));
+
+/// An email to farmer about their stale harvests archived by the server maintenance.
+const HARVESTS_ARCHIVED_EMAIL_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/static/templates/emails/harvests_archived.html"
+));
+/// An email to farmer about their stale harvests archived by the server maintenance.
+const HARVESTS_ARCHIVED_EMAIL_TEXT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/static/templates/emails/harvests_archived.txt"
+));
+
+/// An email to user about direct messages they have not read.
+const UNREAD_MESSAGES_EMAIL_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/static/templates/emails/unread_messages.html"
+));
+/// An email to user about direct messages they have not read.
+const UNREAD_MESSAGES_EMAIL_TEXT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/static/templates/emails/unread_messages.txt"
+));
+
+/// English catalogue of the email subjects and messages
+const ENGLISH_CATALOGUE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/static/templates/emails/catalogue.json"
+));
+
+/// Email templates names, each template has a html and a txt file
+pub const EMAIL_TEMPLATE_NAMES: [&str; 8] = [
+    "confirm_account",
+    "approve_email_change",
+    "password_reset",
+    "verify_new_email",
+    "order_update",
+    "saved_search_digest",
+    "harvests_archived",
+    "unread_messages",
+];
 
-const NAME_ACCOUNT_CONFIRMATION_EMAIL_HTML: &str = "confirm_account_html";
-const NAME_ACCOUNT_CONFIRMATION_EMAIL_TEXT: &str = "confirm_account_txt";
+/// Email template file formats
+pub(super) const EMAIL_TEMPLATE_FORMATS: [&str; 2] = ["html", "txt"];
 
-const NAME_APPROVE_EMAIL_CHANGE_EMAIL_HTML: &str = "approve_email_change_html";
-const NAME_APPROVE_EMAIL_CHANGE_EMAIL_TEXT: &str = "approve_email_change_txt";
+/// English templates, sent when a template is not translated
+const ENGLISH_TEMPLATES: [(&str, &str); 16] = [
+    ("confirm_account.html", ACCOUNT_CONFIRMATION_EMAIL_HTML),
+    ("confirm_account.txt", ACCOUNT_CONFIRMATION_EMAIL_TEXT),
+    ("approve_email_change.html", APPROVE_EMAIL_CHANGE_EMAIL_HTML),
+    ("approve_email_change.txt", APPROVE_EMAIL_CHANGE_EMAIL_TEXT),
+    ("password_reset.html", PASSWORD_RESET_EMAIL_HTML),
+    ("password_reset.txt", PASSWORD_RESET_EMAIL_TEXT),
+    ("verify_new_email.html", VERIFY_NEW_EMAIL_CHANGE_EMAIL_HTML),
+    ("verify_new_email.txt", VERIFY_NEW_EMAIL_CHANGE_EMAIL_TEXT),
+    ("order_update.html", ORDER_UPDATE_EMAIL_HTML),
+    ("order_update.txt", ORDER_UPDATE_EMAIL_TEXT),
+    ("saved_search_digest.html", SAVED_SEARCH_DIGEST_EMAIL_HTML),
+    ("saved_search_digest.txt", SAVED_SEARCH_DIGEST_EMAIL_TEXT),
+    ("harvests_archived.html", HARVESTS_ARCHIVED_EMAIL_HTML),
+    ("harvests_archived.txt", HARVESTS_ARCHIVED_EMAIL_TEXT),
+    ("unread_messages.html", UNREAD_MESSAGES_EMAIL_HTML),
+    ("unread_messages.txt", UNREAD_MESSAGES_EMAIL_TEXT),
+];
 
-const NAME_PASSWORD_RESET_EMAIL_HTML: &str = "password_reset_html";
-const NAME_PASSWORD_RESET_EMAIL_TEXT: &str = "password_reset_txt";
+/// Returns the template file name in the language,
+/// English templates have no language suffix.
+pub(super) fn template_file_name(name: &str, language: Language, format: &str) -> String {
+    if language.is_english() {
+        format!("{name}.{format}")
+    } else {
+        format!("{name}.{language}.{format}")
+    }
+}
 
-const NAME_VERIFY_NEW_EMAIL_CHANGE_EMAIL_HTML: &str = "verify_new_email_html";
-const NAME_VERIFY_NEW_EMAIL_CHANGE_EMAIL_TEXT: &str = "verify_new_email_txt";
+/// Returns the catalogue file name in the language
+fn catalogue_file_name(language: Language) -> String {
+    if language.is_english() {
+        "catalogue.json".to_owned()
+    } else {
+        format!("catalogue.{language}.json")
+    }
+}
+
+/// Email subjects and messages by key, the values are templates
+type Catalogue = HashMap<String, String>;
+
+/// Reads the translated catalogue from `EMAIL_TEMPLATES_DIR`
+fn read_catalogue(language: Language) -> ServerResult<Option<Catalogue>> {
+    let path = Path::new(EMAIL_TEMPLATES_DIR).join(catalogue_file_name(language));
+    let Ok(catalogue) = std::fs::read_to_string(path) else {
+        return Ok(None);
+    };
+    Ok(Some(serde_json::from_str(&catalogue)?))
+}
 
 /// A container for email templates
 #[derive(Debug, Clone)]
-pub struct EmailTemplates(minijinja::Environment<'static>);
+pub struct EmailTemplates {
+    pub(super) env: minijinja::Environment<'static>,
+    catalogues: HashMap<Language, Catalogue>,
+}
 
 impl Default for EmailTemplates {
     fn default() -> Self {
@@ -77,49 +192,81 @@ impl Default for EmailTemplates {
 
 impl EmailTemplates {
     /// Creates a new email templates container
+    /// and loads the translated templates.
     #[must_use]
     pub fn new() -> Self {
         let mut env = minijinja::Environment::new();
+        for (name, template) in ENGLISH_TEMPLATES {
+            env.add_template(name, template).unwrap();
+        }
+
+        let mut catalogues = HashMap::new();
+        catalogues.insert(
+            Language::English,
+            serde_json::from_str(ENGLISH_CATALOGUE).unwrap(),
+        );
+
+        for language in Language::ALL.into_iter().filter(|lang| !lang.is_english()) {
+            for name in EMAIL_TEMPLATE_NAMES {
+                for format in EMAIL_TEMPLATE_FORMATS {
+                    let file_name = template_file_name(name, language, format);
+                    let path = Path::new(EMAIL_TEMPLATES_DIR).join(&file_name);
+                    let Ok(template) = std::fs::read_to_string(path) else {
+                        continue;
+                    };
+                    if let Err(err) = env.add_template_owned(file_name.clone(), template) {
+                        tracing::error!("Invalid email template `{file_name}`: {}", err);
+                    }
+                }
+            }
+            match read_catalogue(language) {
+                Ok(Some(catalogue)) => {
+                    catalogues.insert(language, catalogue);
+                }
+                Ok(None) => {}
+                Err(err) => tracing::error!("Invalid {} email catalogue: {}", language.name(), err),
+            }
+        }
 
-        env.add_template(
-            NAME_ACCOUNT_CONFIRMATION_EMAIL_HTML,
-            ACCOUNT_CONFIRMATION_EMAIL_HTML,
-        )
-        .unwrap();
-        env.add_template(
-            NAME_ACCOUNT_CONFIRMATION_EMAIL_TEXT,
-            ACCOUNT_CONFIRMATION_EMAIL_TEXT,
-        )
-        .unwrap();
-
-        env.add_template(
-            NAME_APPROVE_EMAIL_CHANGE_EMAIL_HTML,
-            APPROVE_EMAIL_CHANGE_EMAIL_HTML,
-        )
-        .unwrap();
-        env.add_template(
-            NAME_APPROVE_EMAIL_CHANGE_EMAIL_TEXT,
-            APPROVE_EMAIL_CHANGE_EMAIL_TEXT,
-        )
-        .unwrap();
-
-        env.add_template(NAME_PASSWORD_RESET_EMAIL_HTML, PASSWORD_RESET_EMAIL_HTML)
-            .unwrap();
-        env.add_template(NAME_PASSWORD_RESET_EMAIL_TEXT, PASSWORD_RESET_EMAIL_TEXT)
-            .unwrap();
-
-        env.add_template(
-            NAME_VERIFY_NEW_EMAIL_CHANGE_EMAIL_HTML,
-            VERIFY_NEW_EMAIL_CHANGE_EMAIL_HTML,
-        )
-        .unwrap();
-        env.add_template(
-            NAME_VERIFY_NEW_EMAIL_CHANGE_EMAIL_TEXT,
-            VERIFY_NEW_EMAIL_CHANGE_EMAIL_TEXT,
-        )
-        .unwrap();
-
-        Self(env)
+        Self { env, catalogues }
+    }
+
+    /// Renders the template text and html in the language,
+    /// falls back to English if the template is not translated.
+    pub(super) fn render(
+        &self,
+        name: &str,
+        language: Language,
+        ctx: &Value,
+    ) -> ServerResult<(String, String)> {
+        let render = |format| {
+            let template = self
+                .env
+                .get_template(&template_file_name(name, language, format))
+                .or_else(|_| {
+                    self.env
+                        .get_template(&template_file_name(name, Language::English, format))
+                })
+                .map_err(|err| ServerError::new(err.to_string()))?;
+            template
+                .render(ctx)
+                .map_err(|err| ServerError::new(err.to_string()))
+        };
+        Ok((render("txt")?, render("html")?))
+    }
+
+    /// Renders the catalogue message in the language,
+    /// falls back to English if the message is not translated.
+    fn message(&self, key: &str, language: Language, ctx: &Value) -> ServerResult<String> {
+        let message = self
+            .catalogues
+            .get(&language)
+            .and_then(|catalogue| catalogue.get(key))
+            .or_else(|| self.catalogues[&Language::English].get(key))
+            .ok_or_else(|| ServerError::new(format!("Email catalogue key `{key}` not found")))?;
+        self.env
+            .render_str(message, ctx)
+            .map_err(|err| ServerError::new(err.to_string()))
     }
 
     /// Return account confirm email
@@ -128,22 +275,17 @@ impl EmailTemplates {
         server_email: &str,
         first_name: &str,
         user_email: &str,
+        language: Language,
         link: &str,
     ) -> ServerResult<EmailMessage> {
-        let text = self
-            .0
-            .get_template(NAME_ACCOUNT_CONFIRMATION_EMAIL_TEXT)
-            .unwrap()
-            .render(context! { first_name => first_name, email => user_email, link => link })
-            .unwrap();
-        let html = self
-            .0
-            .get_template(NAME_ACCOUNT_CONFIRMATION_EMAIL_HTML)
-            .unwrap()
-            .render(context! { first_name => first_name, email => user_email, link => link })
-            .unwrap();
-
-        let subject = format!("[{APP_NAME}] Please verify your email address.");
+        let ctx = context! {
+            app_name => APP_NAME,
+            first_name => first_name,
+            email => user_email,
+            link => link,
+        };
+        let (text, html) = self.render("confirm_account", language, &ctx)?;
+        let subject = self.message("confirm_account.subject", language, &ctx)?;
 
         EmailMessage::from_server(server_email, user_email, &subject, text, html)
     }
@@ -154,23 +296,18 @@ impl EmailTemplates {
         server_email: &str,
         first_name: &str,
         user_email: &str,
+        language: Language,
         new_email: &str,
         code: &str,
     ) -> ServerResult<EmailMessage> {
-        let text = self
-            .0
-            .get_template(NAME_APPROVE_EMAIL_CHANGE_EMAIL_TEXT)
-            .unwrap()
-            .render(context! { first_name => first_name, new_email => new_email, code => code })
-            .unwrap();
-        let html = self
-            .0
-            .get_template(NAME_APPROVE_EMAIL_CHANGE_EMAIL_HTML)
-            .unwrap()
-            .render(context! { first_name => first_name, new_email => new_email, code => code })
-            .unwrap();
-
-        let subject = format!("[{APP_NAME}] Please approve your {APP_NAME} account email change.");
+        let ctx = context! {
+            app_name => APP_NAME,
+            first_name => first_name,
+            new_email => new_email,
+            code => code,
+        };
+        let (text, html) = self.render("approve_email_change", language, &ctx)?;
+        let subject = self.message("approve_email_change.subject", language, &ctx)?;
 
         EmailMessage::from_server(server_email, user_email, &subject, text, html)
     }
@@ -181,22 +318,16 @@ impl EmailTemplates {
         server_email: &str,
         first_name: &str,
         user_email: &str,
+        language: Language,
         link: &str,
     ) -> ServerResult<EmailMessage> {
-        let text = self
-            .0
-            .get_template(NAME_PASSWORD_RESET_EMAIL_TEXT)
-            .unwrap()
-            .render(context! { first_name => first_name, link => link })
-            .unwrap();
-        let html = self
-            .0
-            .get_template(NAME_PASSWORD_RESET_EMAIL_HTML)
-            .unwrap()
-            .render(context! { first_name => first_name, link => link })
-            .unwrap();
-
-        let subject = format!("[{APP_NAME}] Password reset.");
+        let ctx = context! {
+            app_name => APP_NAME,
+            first_name => first_name,
+            link => link,
+        };
+        let (text, html) = self.render("password_reset", language, &ctx)?;
+        let subject = self.message("password_reset.subject", language, &ctx)?;
 
         EmailMessage::from_server(server_email, user_email, &subject, text, html)
     }
@@ -207,23 +338,227 @@ impl EmailTemplates {
         server_email: &str,
         first_name: &str,
         new_email: &str,
+        language: Language,
         code: &str,
     ) -> ServerResult<EmailMessage> {
-        let text = self
-            .0
-            .get_template(NAME_APPROVE_EMAIL_CHANGE_EMAIL_TEXT)
-            .unwrap()
-            .render(context! { first_name => first_name, new_email => new_email, code => code })
-            .unwrap();
-        let html = self
-            .0
-            .get_template(NAME_APPROVE_EMAIL_CHANGE_EMAIL_HTML)
-            .unwrap()
-            .render(context! { first_name => first_name, new_email => new_email, code => code })
-            .unwrap();
-
-        let subject = format!("[{APP_NAME}] Verify your new {APP_NAME} account email.");
+        let ctx = context! {
+            app_name => APP_NAME,
+            first_name => first_name,
+            new_email => new_email,
+            code => code,
+        };
+        let (text, html) = self.render("verify_new_email", language, &ctx)?;
+        let subject = self.message("verify_new_email.subject", language, &ctx)?;
 
         EmailMessage::from_server(server_email, new_email, &subject, text, html)
     }
+
+    /// Return order update email
+    pub fn order_update(
+        &self,
+        server_email: &str,
+        first_name: &str,
+        user_email: &str,
+        language: Language,
+        order: &OrderUpdate,
+        recipient: OrderRecipient,
+    ) -> ServerResult<EmailMessage> {
+        let mut ctx = context! {
+            app_name => APP_NAME,
+            first_name => first_name,
+            harvest => format!("{} {}", order.quantity, order.cultivar_name),
+            farm => order.farm_name,
+            buyer => order.buyer_first_name,
+        };
+        let headline_key = match recipient {
+            OrderRecipient::Buyer => format!("order_{}.buyer_headline", order.event),
+            OrderRecipient::Farmer => format!("order_{}.farmer_headline", order.event),
+        };
+        let headline = self.message(&headline_key, language, &ctx)?;
+        let subject = self.message(&format!("order_{}.subject", order.event), language, &ctx)?;
+
+        ctx = context! {
+            first_name => first_name,
+            headline => headline,
+            harvest => order.cultivar_name,
+            quantity => order.quantity,
+            pickup_date => order.pickup_date,
+            status => order.status,
+            link => order.link,
+        };
+        let (text, html) = self.render("order_update", language, &ctx)?;
+
+        EmailMessage::from_server(server_email, user_email, &subject, text, html)
+    }
+
+    /// Return saved search digest email
+    pub fn saved_search_digest(
+        &self,
+        server_email: &str,
+        first_name: &str,
+        user_email: &str,
+        language: Language,
+        searches: &[SearchDigest],
+        link: &str,
+    ) -> ServerResult<EmailMessage> {
+        let ctx = context! {
+            app_name => APP_NAME,
+            first_name => first_name,
+            searches => searches,
+            link => link,
+        };
+        let (text, html) = self.render("saved_search_digest", language, &ctx)?;
+        let subject = self.message("saved_search_digest.subject", language, &ctx)?;
+
+        EmailMessage::from_server(server_email, user_email, &subject, text, html)
+    }
+
+    /// Return harvests archived email
+    #[allow(clippy::too_many_arguments)]
+    pub fn harvests_archived(
+        &self,
+        server_email: &str,
+        first_name: &str,
+        user_email: &str,
+        language: Language,
+        harvests: &[ArchivedHarvest],
+        max_days: i64,
+        link: &str,
+    ) -> ServerResult<EmailMessage> {
+        let ctx = context! {
+            app_name => APP_NAME,
+            first_name => first_name,
+            harvests => harvests,
+            max_days => max_days,
+            link => link,
+        };
+        let (text, html) = self.render("harvests_archived", language, &ctx)?;
+        let subject = self.message("harvests_archived.subject", language, &ctx)?;
+
+        EmailMessage::from_server(server_email, user_email, &subject, text, html)
+    }
+
+    /// Return unread direct messages email
+    pub fn unread_messages(
+        &self,
+        server_email: &str,
+        first_name: &str,
+        user_email: &str,
+        language: Language,
+        senders: &[UnreadSender],
+        link: &str,
+    ) -> ServerResult<EmailMessage> {
+        let ctx = context! {
+            app_name => APP_NAME,
+            first_name => first_name,
+            senders => senders,
+            link => link,
+        };
+        let (text, html) = self.render("unread_messages", language, &ctx)?;
+        let subject = self.message("unread_messages.subject", language, &ctx)?;
+
+        EmailMessage::from_server(server_email, user_email, &subject, text, html)
+    }
+}
+
+/// Returns the translated email template and catalogue files missing
+/// in `EMAIL_TEMPLATES_DIR`, and the catalogue keys missing in each language.
+///
+/// # Errors
+///
+/// Return an error if a translated catalogue is invalid
+pub fn missing_translations() -> ServerResult<Vec<(Language, String)>> {
+    let english: Catalogue = serde_json::from_str(ENGLISH_CATALOGUE)?;
+    let mut english_keys: Vec<_> = english.keys().collect();
+    english_keys.sort_unstable();
+
+    let mut missing = Vec::new();
+    for language in Language::ALL.into_iter().filter(|lang| !lang.is_english()) {
+        for name in EMAIL_TEMPLATE_NAMES {
+            for format in EMAIL_TEMPLATE_FORMATS {
+                let file_name = template_file_name(name, language, format);
+                if !Path::new(EMAIL_TEMPLATES_DIR).join(&file_name).exists() {
+                    missing.push((language, file_name));
+                }
+            }
+        }
+        match read_catalogue(language)? {
+            Some(catalogue) => missing.extend(
+                english_keys
+                    .iter()
+                    .filter(|key| !catalogue.contains_key(key.as_str()))
+                    .map(|key| {
+                        (
+                            language,
+                            format!("{}: {key}", catalogue_file_name(language)),
+                        )
+                    }),
+            ),
+            None => missing.push((language, catalogue_file_name(language))),
+        }
+    }
+    Ok(missing)
+}
+
+/// An order change sent in the order update email
+#[derive(Debug, Clone)]
+pub struct OrderUpdate {
+    /// The order event catalogue key, e.g. `placed`
+    pub event: &'static str,
+    pub cultivar_name: String,
+    pub farm_name: String,
+    pub buyer_first_name: String,
+    pub quantity: String,
+    pub pickup_date: String,
+    pub status: String,
+    pub link: String,
+}
+
+/// The order party receiving the order update email
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderRecipient {
+    Buyer,
+    Farmer,
+}
+
+/// An archived harvest listed in the harvests archived email
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedHarvest {
+    pub name: String,
+    pub farm_name: String,
+    pub listed_on: String,
+}
+
+/// A saved search section of the saved search digest email
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchDigest {
+    pub name: String,
+    pub unsubscribe_link: String,
+    pub harvests: Vec<SearchDigestHarvest>,
+}
+
+/// A harvest listed in the saved search digest email
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchDigestHarvest {
+    pub name: String,
+    pub farm_name: String,
+    pub place_name: String,
+    pub price: String,
+    pub link: String,
+}
+
+/// A sender section of the unread messages email
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreadSender {
+    pub name: String,
+    /// Number of unread messages from the sender
+    pub count: usize,
+    pub messages: Vec<MessagePreview>,
+}
+
+/// A message preview listed in the unread messages email
+#[derive(Debug, Clone, Serialize)]
+pub struct MessagePreview {
+    pub content: String,
+    pub sent_at: String,
 }
@@ -0,0 +1,111 @@
+//! Email template preview http handlers impls
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::{
+    auth::AdminUser,
+    endpoint::{EndpointRejection, EndpointResult},
+    types::Language,
+};
+
+use super::{
+    preview::{EmailFormat, EmailTemplateInfo},
+    transport::SentEmail,
+    Mail,
+};
+
+/// Handles the `GET /admin/emails` route.
+#[tracing::instrument(skip(mail))]
+pub async fn email_template_list(
+    _: AdminUser,
+    State(mail): State<Mail>,
+) -> EndpointResult<Json<Vec<EmailTemplateInfo>>> {
+    Ok(Json(mail.templates().templates()))
+}
+
+/// Handles the `GET /admin/emails/:template/preview` route.
+///
+/// Renders the email template from sample values, `format` selects
+/// the html or txt body and `language` the translation. Any other
+/// query value overrides the template variable of the same name.
+#[tracing::instrument(skip(mail, query))]
+pub async fn email_template_preview(
+    _: AdminUser,
+    Path(template): Path<String>,
+    Query(mut query): Query<HashMap<String, String>>,
+    State(mail): State<Mail>,
+) -> EndpointResult<Response> {
+    let format = query
+        .remove("format")
+        .map_or(Ok(EmailFormat::Html), |format| {
+            EmailFormat::try_from(format.as_str())
+        })
+        .map_err(|err| EndpointRejection::BadRequest(err.into()))?;
+    let language = query
+        .remove("language")
+        .map_or(Ok(Language::English), |language| {
+            Language::try_from(language.as_str())
+        })
+        .map_err(|err| EndpointRejection::BadRequest(err.into()))?;
+    // The api key is not a template variable
+    query.remove("api_key");
+
+    let Some(body) = mail
+        .templates()
+        .preview(&template, format, language, query)?
+    else {
+        return Err(EndpointRejection::not_found("Email template"));
+    };
+    match format {
+        EmailFormat::Html => Ok(Html(body).into_response()),
+        EmailFormat::Txt => Ok(body.into_response()),
+    }
+}
+
+/// `GET /admin/emails/mailbox` query parameters.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MailboxQuery {
+    /// filters for emails sent to this address
+    pub to: Option<String>,
+}
+
+/// Handles the `GET /admin/emails/mailbox` route.
+///
+/// Returns the emails captured by the in-memory transport,
+/// so integration tests can assert against the emails sent.
+#[tracing::instrument(skip(mail))]
+pub async fn email_mailbox(
+    _: AdminUser,
+    Query(query): Query<MailboxQuery>,
+    State(mail): State<Mail>,
+) -> EndpointResult<Json<Vec<SentEmail>>> {
+    let Some(mailbox) = mail.mailbox() else {
+        return Err(EndpointRejection::not_found("Mailbox"));
+    };
+    let emails = query
+        .to
+        .map_or_else(|| mailbox.emails(), |address| mailbox.emails_to(&address));
+    Ok(Json(emails))
+}
+
+/// Handles the `DELETE /admin/emails/mailbox` route.
+///
+/// Removes the emails captured by the in-memory transport.
+#[tracing::instrument(skip(mail))]
+pub async fn email_mailbox_clear(
+    _: AdminUser,
+    State(mail): State<Mail>,
+) -> EndpointResult<StatusCode> {
+    let Some(mailbox) = mail.mailbox() else {
+        return Err(EndpointRejection::not_found("Mailbox"));
+    };
+    mailbox.clear();
+    Ok(StatusCode::NO_CONTENT)
+}
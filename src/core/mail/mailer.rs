@@ -2,60 +2,87 @@
 
 use std::sync::Arc;
 
-use lettre::{
-    transport::smtp::{authentication::Credentials, PoolConfig},
-    AsyncSmtpTransport, AsyncTransport, Tokio1Executor as Tokio,
-};
+use lettre::address::{Address, Envelope};
 
-use crate::error::{ServerError, ServerResult};
+use crate::{
+    error::{ServerError, ServerResult},
+    queue::QueuedEmail,
+    types::Language,
+};
 
-use super::{emails::EmailTemplates, message::EmailMessage};
+use super::{
+    emails::{
+        ArchivedHarvest, EmailTemplates, OrderRecipient, OrderUpdate, SearchDigest, UnreadSender,
+    },
+    message::EmailMessage,
+    transport::{MailTransport, MemoryMailbox},
+};
 
-/// SMTP email sender
+/// Email sender
 #[derive(Debug, Clone)]
 pub struct Mail {
-    mail: AsyncSmtpTransport<Tokio>,
+    transport: MailTransport,
     emails: EmailTemplates,
     address: Arc<String>,
 }
 
 impl Mail {
-    /// Creates a remote connection to `smtp_server` using STARTTLS
+    /// Creates a new email sender sending emails from `email` address
     #[must_use]
-    pub fn new(smtp_server: &str, email: &str, password: String) -> Self {
+    pub fn new(transport: MailTransport, email: &str) -> Self {
         let _ = email
-            .parse::<lettre::address::Address>()
+            .parse::<Address>()
             .unwrap_or_else(|err| panic!("Failed to parse email address:{email}. : {err}",));
         let email = email.to_ascii_lowercase();
 
-        let credentials = Credentials::new(email.clone(), password);
-        let pool = PoolConfig::default();
         Self {
-            mail: AsyncSmtpTransport::<Tokio>::starttls_relay(smtp_server)
-                .unwrap()
-                .credentials(credentials)
-                .pool_config(pool)
-                .build(),
+            transport,
             emails: EmailTemplates::new(),
             address: Arc::new(email),
         }
     }
 
-    /// Creates a remote connection to outlook smtp server using STARTTLS
+    /// Returns the captured emails if the sender uses the in-memory transport
+    #[must_use]
+    pub fn mailbox(&self) -> Option<&MemoryMailbox> {
+        match &self.transport {
+            MailTransport::Memory(mailbox) => Some(mailbox),
+            _ => None,
+        }
+    }
+
+    /// Returns the email templates
     #[must_use]
-    pub fn outlook(email: &str, password: String) -> Self {
-        Self::new(crate::OUTLOOK_SMTP_SERVER, email, password)
+    pub const fn templates(&self) -> &EmailTemplates {
+        &self.emails
     }
 
     /// Sends an email
     pub async fn send(&self, email: EmailMessage) -> ServerResult<()> {
-        match self.mail.send(email.message).await {
-            Ok(_response) => Ok(()),
-            Err(err) => {
-                tracing::error!("Sending email error: {}", err);
-                Err(ServerError::internal(Box::new(err)))
-            }
-        }
+        let message = email.message.formatted();
+        self.transport
+            .send_raw(email.message.envelope(), &message)
+            .await
+    }
+
+    /// Sends an email enqueued in the background job queue
+    pub async fn send_queued(&self, email: &QueuedEmail) -> ServerResult<()> {
+        let from = match email.from.as_deref().map(str::parse::<Address>) {
+            Some(Ok(address)) => Some(address),
+            Some(Err(err)) => return Err(ServerError::new(err.to_string())),
+            None => None,
+        };
+        let to = email
+            .to
+            .iter()
+            .map(|address| address.parse::<Address>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| ServerError::new(err.to_string()))?;
+        let envelope = Envelope::new(from, to).map_err(|err| ServerError::new(err.to_string()))?;
+
+        self.transport
+            .send_raw(&envelope, email.message.as_bytes())
+            .await
     }
 
     /// Return account confirm email
@@ -63,10 +90,16 @@ impl Mail {
         &self,
         first_name: &str,
         user_email: &str,
+        language: Language,
         link: &str,
     ) -> ServerResult<EmailMessage> {
-        self.emails
-            .account_confirm(self.address.as_str(), first_name, user_email, link)
+        self.emails.account_confirm(
+            self.address.as_str(),
+            first_name,
+            user_email,
+            language,
+            link,
+        )
     }
 
     /// Return approve email change email
@@ -74,6 +107,7 @@ impl Mail {
         &self,
         first_name: &str,
         user_email: &str,
+        language: Language,
         new_email: &str,
         code: &str,
     ) -> ServerResult<EmailMessage> {
@@ -81,6 +115,7 @@ impl Mail {
             self.address.as_str(),
             first_name,
             user_email,
+            language,
             new_email,
             code,
         )
@@ -91,10 +126,16 @@ impl Mail {
         &self,
         first_name: &str,
         user_email: &str,
+        language: Language,
         link: &str,
     ) -> ServerResult<EmailMessage> {
-        self.emails
-            .password_reset(self.address.as_str(), first_name, user_email, link)
+        self.emails.password_reset(
+            self.address.as_str(),
+            first_name,
+            user_email,
+            language,
+            link,
+        )
     }
 
     /// Return verify new-email email
@@ -102,9 +143,88 @@ impl Mail {
         &self,
         first_name: &str,
         new_email: &str,
+        language: Language,
         code: &str,
     ) -> ServerResult<EmailMessage> {
         self.emails
-            .verify_new_email(self.address.as_str(), first_name, new_email, code)
+            .verify_new_email(self.address.as_str(), first_name, new_email, language, code)
+    }
+
+    /// Return order update email
+    pub fn order_update(
+        &self,
+        first_name: &str,
+        user_email: &str,
+        language: Language,
+        order: &OrderUpdate,
+        recipient: OrderRecipient,
+    ) -> ServerResult<EmailMessage> {
+        self.emails.order_update(
+            self.address.as_str(),
+            first_name,
+            user_email,
+            language,
+            order,
+            recipient,
+        )
+    }
+
+    /// Return saved search digest email
+    pub fn saved_search_digest(
+        &self,
+        first_name: &str,
+        user_email: &str,
+        language: Language,
+        searches: &[SearchDigest],
+        link: &str,
+    ) -> ServerResult<EmailMessage> {
+        self.emails.saved_search_digest(
+            self.address.as_str(),
+            first_name,
+            user_email,
+            language,
+            searches,
+            link,
+        )
+    }
+
+    /// Return harvests archived email
+    pub fn harvests_archived(
+        &self,
+        first_name: &str,
+        user_email: &str,
+        language: Language,
+        harvests: &[ArchivedHarvest],
+        max_days: i64,
+        link: &str,
+    ) -> ServerResult<EmailMessage> {
+        self.emails.harvests_archived(
+            self.address.as_str(),
+            first_name,
+            user_email,
+            language,
+            harvests,
+            max_days,
+            link,
+        )
+    }
+
+    /// Return unread direct messages email
+    pub fn unread_messages(
+        &self,
+        first_name: &str,
+        user_email: &str,
+        language: Language,
+        senders: &[UnreadSender],
+        link: &str,
+    ) -> ServerResult<EmailMessage> {
+        self.emails.unread_messages(
+            self.address.as_str(),
+            first_name,
+            user_email,
+            language,
+            senders,
+            link,
+        )
     }
 }
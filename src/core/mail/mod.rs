@@ -1,7 +1,17 @@
-//! SMTP email sender impls
+//! Email sender impls
 
 mod emails;
+pub mod handlers;
 mod mailer;
 mod message;
+mod preview;
+mod transport;
 
+pub use emails::{
+    missing_translations, ArchivedHarvest, EmailTemplates, MessagePreview, OrderRecipient,
+    OrderUpdate, SearchDigest, SearchDigestHarvest, UnreadSender, EMAIL_TEMPLATE_NAMES,
+};
 pub use mailer::Mail;
+pub use message::EmailMessage;
+pub use preview::{EmailFormat, EmailTemplateInfo};
+pub use transport::{MailTransport, MemoryMailbox, SentEmail, SmtpConfig, SmtpTls};
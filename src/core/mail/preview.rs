@@ -0,0 +1,187 @@
+//! Email template previews impls
+
+use std::collections::{BTreeSet, HashMap};
+
+use minijinja::Value;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{error::ServerResult, types::Language, APP_DOMAIN_NAME, APP_NAME, SERVER_DOMAIN_NAME};
+
+use super::emails::{
+    template_file_name, EmailTemplates, EMAIL_TEMPLATE_FORMATS, EMAIL_TEMPLATE_NAMES,
+};
+
+/// An email template with the variables it expects
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailTemplateInfo {
+    pub name: &'static str,
+    pub variables: Vec<String>,
+    /// Languages the template is translated in, English included
+    pub languages: Vec<Language>,
+}
+
+/// Email preview body format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailFormat {
+    Html,
+    Txt,
+}
+
+impl EmailFormat {
+    /// Returns the template file format
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Txt => "txt",
+        }
+    }
+}
+
+impl TryFrom<&str> for EmailFormat {
+    type Error = String;
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        match format.to_ascii_lowercase().as_str() {
+            "html" => Ok(Self::Html),
+            "txt" => Ok(Self::Txt),
+            _ => Err(format!(
+                "Unsupported email format `{format}`, expected: html or txt"
+            )),
+        }
+    }
+}
+
+impl EmailTemplates {
+    /// Returns the registered email templates with the variables they expect
+    #[must_use]
+    pub fn templates(&self) -> Vec<EmailTemplateInfo> {
+        EMAIL_TEMPLATE_NAMES
+            .into_iter()
+            .map(|name| {
+                let mut variables = BTreeSet::new();
+                for format in EMAIL_TEMPLATE_FORMATS {
+                    let file_name = template_file_name(name, Language::English, format);
+                    if let Ok(template) = self.env.get_template(&file_name) {
+                        variables.extend(template.undeclared_variables(false));
+                    }
+                }
+                let languages = Language::ALL
+                    .into_iter()
+                    .filter(|&language| {
+                        EMAIL_TEMPLATE_FORMATS.into_iter().any(|format| {
+                            let file_name = template_file_name(name, language, format);
+                            self.env.get_template(&file_name).is_ok()
+                        })
+                    })
+                    .collect();
+                EmailTemplateInfo {
+                    name,
+                    variables: variables.into_iter().collect(),
+                    languages,
+                }
+            })
+            .collect()
+    }
+
+    /// Renders the email template from sample context values,
+    /// `overrides` replace the sample values.
+    ///
+    /// Return None if the template does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the template failed to render
+    pub fn preview(
+        &self,
+        name: &str,
+        format: EmailFormat,
+        language: Language,
+        overrides: HashMap<String, String>,
+    ) -> ServerResult<Option<String>> {
+        let Some(mut ctx) = sample_context(name) else {
+            return Ok(None);
+        };
+        for (variable, value) in overrides {
+            ctx.insert(variable, serde_json::Value::String(value));
+        }
+
+        let (text, html) = self.render(name, language, &Value::from_serializable(&ctx))?;
+        match format {
+            EmailFormat::Html => Ok(Some(html)),
+            EmailFormat::Txt => Ok(Some(text)),
+        }
+    }
+}
+
+/// Returns the sample context values of the email template,
+/// return None if the template does not exist.
+fn sample_context(name: &str) -> Option<serde_json::Map<String, serde_json::Value>> {
+    let domain = SERVER_DOMAIN_NAME
+        .get()
+        .map_or(APP_DOMAIN_NAME, String::as_str);
+    let context = match name {
+        "confirm_account" => json!({
+            "email": "ndapewa@example.com",
+            "link": format!("{domain}/account/confirm?token=sample-token"),
+        }),
+        "approve_email_change" | "verify_new_email" => json!({
+            "new_email": "ndapewa.new@example.com",
+            "code": "123456",
+        }),
+        "password_reset" => json!({
+            "link": format!("{domain}/account/reset-password?token=sample-token"),
+        }),
+        "order_update" => json!({
+            "headline": "Your reservation of 5 kg Tomatoes was sent to Oshana Farm.",
+            "harvest": "Tomatoes",
+            "quantity": "5 kg",
+            "pickup_date": "2026-10-20",
+            "status": "pending",
+            "link": format!("{domain}/orders/sample-order"),
+        }),
+        "saved_search_digest" => json!({
+            "searches": [{
+                "name": "Tomatoes near Ondangwa",
+                "unsubscribe_link": format!("{domain}/account/saved-searches/unsubscribe?token=sample-token"),
+                "harvests": [{
+                    "name": "Tomatoes",
+                    "farm_name": "Oshana Farm",
+                    "place_name": "Ondangwa",
+                    "price": "N$ 25.00 / kg",
+                    "link": format!("{domain}/harvests/sample-harvest"),
+                }],
+            }],
+            "link": format!("{domain}/account/users/me/saved-searches"),
+        }),
+        "harvests_archived" => json!({
+            "harvests": [{
+                "name": "Tomatoes",
+                "farm_name": "Oshana Farm",
+                "listed_on": "2026-07-20",
+            }],
+            "max_days": 90,
+            "link": format!("{domain}/harvests"),
+        }),
+        "unread_messages" => json!({
+            "senders": [{
+                "name": "Shikongo Farms",
+                "count": 2,
+                "messages": [
+                    { "content": "Hi, the tomatoes are ready for pickup.", "sent_at": "2026-10-18 08:30" },
+                    { "content": "We are open until 17:00.", "sent_at": "2026-10-18 08:31" },
+                ],
+            }],
+            "link": format!("{domain}/messages"),
+        }),
+        _ => return None,
+    };
+
+    let serde_json::Value::Object(mut context) = context else {
+        return None;
+    };
+    context.insert("app_name".to_owned(), APP_NAME.into());
+    context.insert("first_name".to_owned(), "Ndapewa".into());
+    Some(context)
+}
@@ -0,0 +1,199 @@
+//! Email transport backends impls
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use lettre::{
+    address::Envelope,
+    transport::smtp::{authentication::Credentials, PoolConfig},
+    AsyncSmtpTransport, AsyncTransport, Tokio1Executor as Tokio,
+};
+use serde::Serialize;
+use tokio::fs;
+
+use crate::{
+    error::{ServerError, ServerResult},
+    types::ModelID,
+};
+
+/// Delivers formatted email messages
+#[derive(Debug, Clone)]
+pub enum MailTransport {
+    /// Sends emails to an SMTP server
+    Smtp(AsyncSmtpTransport<Tokio>),
+    /// Writes emails as `.eml` files to a directory
+    File(Arc<PathBuf>),
+    /// Captures emails in memory
+    Memory(MemoryMailbox),
+}
+
+impl MailTransport {
+    /// Creates an SMTP transport
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the SMTP host is invalid
+    pub fn smtp(config: &SmtpConfig) -> ServerResult<Self> {
+        let builder = match config.tls {
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio>::starttls_relay(&config.host),
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio>::relay(&config.host),
+            SmtpTls::None => Ok(AsyncSmtpTransport::<Tokio>::builder_dangerous(&config.host)),
+        };
+        let mut builder = builder
+            .map_err(|err| {
+                ServerError::new(format!("Invalid SMTP host: {}. : {err}", config.host))
+            })?
+            .pool_config(PoolConfig::default());
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let Some((username, password)) = &config.credentials {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Self::Smtp(builder.build()))
+    }
+
+    /// Creates a file transport writing emails to `dir`
+    #[must_use]
+    pub fn file(dir: impl Into<PathBuf>) -> Self {
+        Self::File(Arc::new(dir.into()))
+    }
+
+    /// Creates an in-memory transport
+    #[must_use]
+    pub fn memory() -> Self {
+        Self::Memory(MemoryMailbox::default())
+    }
+
+    /// Delivers the formatted email message
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the email could not be delivered
+    pub async fn send_raw(&self, envelope: &Envelope, message: &[u8]) -> ServerResult<()> {
+        match self {
+            Self::Smtp(smtp) => match smtp.send_raw(envelope, message).await {
+                Ok(_response) => Ok(()),
+                Err(err) => {
+                    tracing::error!("Sending email error: {}", err);
+                    Err(ServerError::internal(Box::new(err)))
+                }
+            },
+            Self::File(dir) => write_email_file(dir, message).await,
+            Self::Memory(mailbox) => {
+                mailbox.push(SentEmail {
+                    from: envelope.from().map(ToString::to_string),
+                    to: envelope.to().iter().map(ToString::to_string).collect(),
+                    message: String::from_utf8_lossy(message).into_owned(),
+                });
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Writes the email message to `{dir}/{id}.eml`
+///
+/// The message is written to a temporary file first,
+/// so readers of the directory never see a partial email.
+async fn write_email_file(dir: &Path, message: &[u8]) -> ServerResult<()> {
+    fs::create_dir_all(dir).await?;
+    let name = format!("{}.eml", ModelID::new());
+    let tmp_path = dir.join(format!(".{name}.tmp"));
+    fs::write(&tmp_path, message).await?;
+    fs::rename(&tmp_path, dir.join(&name)).await?;
+    tracing::debug!("Email written to: {:?}", dir.join(name));
+    Ok(())
+}
+
+/// SMTP connection security
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Upgrades the connection with STARTTLS, port 587 by default
+    StartTls,
+    /// Connects over TLS, port 465 by default
+    Tls,
+    /// Unencrypted connection, port 25 by default. Local relays only.
+    None,
+}
+
+impl std::str::FromStr for SmtpTls {
+    type Err = ServerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "starttls" => Ok(Self::StartTls),
+            "tls" => Ok(Self::Tls),
+            "none" => Ok(Self::None),
+            _ => Err(ServerError::new(format!(
+                "Invalid SMTP TLS mode: {s}, expected: starttls, tls or none"
+            ))),
+        }
+    }
+}
+
+/// SMTP server connection settings
+#[derive(Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    /// The TLS mode default port is used if not set
+    pub port: Option<u16>,
+    pub tls: SmtpTls,
+    /// Username and password
+    pub credentials: Option<(String, String)>,
+}
+
+impl std::fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("tls", &self.tls)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An email captured by the in-memory transport
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SentEmail {
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    /// The formatted email message
+    pub message: String,
+}
+
+/// Emails captured by the in-memory transport
+#[derive(Debug, Clone, Default)]
+pub struct MemoryMailbox(Arc<Mutex<Vec<SentEmail>>>);
+
+impl MemoryMailbox {
+    /// Returns the captured emails
+    #[must_use]
+    pub fn emails(&self) -> Vec<SentEmail> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Returns the captured emails sent to the address
+    #[must_use]
+    pub fn emails_to(&self, address: &str) -> Vec<SentEmail> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|email| email.to.iter().any(|to| to.eq_ignore_ascii_case(address)))
+            .cloned()
+            .collect()
+    }
+
+    /// Removes all the captured emails
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+
+    /// Captures the email
+    fn push(&self, email: SentEmail) {
+        self.0.lock().unwrap().push(email);
+    }
+}
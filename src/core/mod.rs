@@ -7,6 +7,7 @@ pub mod endpoint;
 pub mod error;
 pub mod files;
 pub mod mail;
+pub mod queue;
 pub mod server;
 pub mod settings;
 pub mod types;
@@ -0,0 +1,213 @@
+//! Background job queue database impl
+
+use time::OffsetDateTime;
+
+use crate::{
+    error::ServerResult, server::state::DatabaseConnection, types::ModelID, QUEUE_LOCK_TIMEOUT,
+    QUEUE_MAX_ATTEMPTS,
+};
+
+use super::models::{QueuedJob, Task};
+
+impl Task {
+    /// Inserts the task into the job queue
+    #[tracing::instrument(name = "Enqueue Job", skip(self, db), fields(kind = self.kind()))]
+    pub async fn enqueue(self, db: DatabaseConnection) -> ServerResult<ModelID> {
+        let id = ModelID::new();
+        let payload = serde_json::to_value(&self)?;
+        match sqlx::query!(
+            r#"
+                INSERT INTO maintenance.queued_jobs(
+                    id,
+                    kind,
+                    payload,
+                    max_attempts,
+                    run_at,
+                    created_at
+                )
+                VALUES($1, $2, $3, $4, $5, $5);
+            "#,
+            id.0,
+            self.kind(),
+            payload,
+            QUEUE_MAX_ATTEMPTS,
+            OffsetDateTime::now_utc(),
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Job enqueued successfully: {:?}", result);
+                Ok(id)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to enqueue job: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+impl QueuedJob {
+    /// Locks and returns the next job due to run, return None if the queue is empty
+    ///
+    /// Jobs locked longer than `QUEUE_LOCK_TIMEOUT` were abandoned
+    /// by a crashed worker and are returned again.
+    pub async fn next(db: DatabaseConnection) -> ServerResult<Option<Self>> {
+        let now = OffsetDateTime::now_utc();
+        match sqlx::query!(
+            r#"
+                UPDATE maintenance.queued_jobs job
+                SET locked_at = $1,
+                    attempts = job.attempts + 1
+                WHERE job.id = (
+                    SELECT next_job.id
+                    FROM maintenance.queued_jobs next_job
+                    WHERE next_job.run_at <= $1
+                        AND (next_job.locked_at IS NULL OR next_job.locked_at < $2)
+                    ORDER BY next_job.run_at
+                    LIMIT 1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING job.id,
+                    job.kind,
+                    job.payload,
+                    job.attempts,
+                    job.max_attempts
+            "#,
+            now,
+            now - QUEUE_LOCK_TIMEOUT,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.map(|rec| {
+                Self::from_row(
+                    rec.id.into(),
+                    rec.kind,
+                    rec.payload,
+                    rec.attempts,
+                    rec.max_attempts,
+                )
+            })),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch next queued job: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Deletes the completed job from the queue
+    #[tracing::instrument(name = "Complete Queued Job", skip(self, db), fields(id = %self.id))]
+    pub async fn complete(&self, db: DatabaseConnection) -> ServerResult<()> {
+        match sqlx::query!(
+            r#"
+                DELETE FROM maintenance.queued_jobs job
+                WHERE job.id = $1;
+            "#,
+            self.id.0,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Queued job completed: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to complete queued job: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Unlocks the failed job to be retried at `run_at`
+    #[tracing::instrument(name = "Retry Queued Job", skip(self, db), fields(id = %self.id))]
+    pub async fn retry(
+        &self,
+        error: &str,
+        run_at: OffsetDateTime,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        match sqlx::query!(
+            r#"
+                UPDATE maintenance.queued_jobs job
+                SET locked_at = NULL,
+                    last_error = $1,
+                    run_at = $2
+                WHERE job.id = $3;
+            "#,
+            error,
+            run_at,
+            self.id.0,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Queued job scheduled for retry: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to retry queued job: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Moves the failed job from the queue to the dead letter jobs
+    ///
+    /// The email message is dropped from send email jobs payload,
+    /// messages contain account confirmation and password reset links.
+    #[tracing::instrument(name = "Dead Letter Queued Job", skip(self, db), fields(id = %self.id))]
+    pub async fn dead_letter(&self, error: &str, db: DatabaseConnection) -> ServerResult<()> {
+        match sqlx::query!(
+            r#"
+                WITH failed AS (
+                    DELETE FROM maintenance.queued_jobs job
+                    WHERE job.id = $1
+                    RETURNING job.id,
+                        job.kind,
+                        job.payload,
+                        job.attempts,
+                        job.created_at
+                )
+                INSERT INTO maintenance.dead_letter_jobs(
+                    id,
+                    kind,
+                    payload,
+                    attempts,
+                    last_error,
+                    created_at,
+                    failed_at
+                )
+                SELECT failed.id,
+                    failed.kind,
+                    CASE WHEN failed.kind = 'send-email'
+                        THEN failed.payload - 'message'
+                        ELSE failed.payload
+                    END,
+                    failed.attempts,
+                    $2,
+                    failed.created_at,
+                    $3
+                FROM failed;
+            "#,
+            self.id.0,
+            error,
+            OffsetDateTime::now_utc(),
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Queued job dead-lettered: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to dead-letter queued job: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
@@ -0,0 +1,12 @@
+//! Background job queue impls
+//!
+//! Jobs are stored in the `maintenance.queued_jobs` table and run by a pool of
+//! workers started with the server. Failed jobs are retried with an exponential
+//! backoff and moved to `maintenance.dead_letter_jobs` after `QUEUE_MAX_ATTEMPTS`.
+
+mod db;
+mod models;
+mod worker;
+
+pub use models::{QueuedEmail, QueuedImage, QueuedJob, Task};
+pub use worker::start_workers;
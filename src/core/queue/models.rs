@@ -0,0 +1,109 @@
+//! Background job queue models impls
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{ServerError, ServerResult},
+    mail::EmailMessage,
+    types::ModelID,
+};
+
+/// A background task run by the queue workers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Task {
+    /// Sends an email
+    SendEmail(QueuedEmail),
+    /// Saves an uploaded image in all `IMAGE_OUTPUT_FORMATS`
+    ImageVariants(QueuedImage),
+}
+
+impl Task {
+    /// Returns the kind of the task as stored in the database
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::SendEmail(_) => "send-email",
+            Self::ImageVariants(_) => "image-variants",
+        }
+    }
+
+    /// Creates a new send email task
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the email could not be serialized
+    pub fn send_email(email: EmailMessage) -> ServerResult<Self> {
+        Ok(Self::SendEmail(QueuedEmail::try_from(email)?))
+    }
+}
+
+/// An email formatted and ready to be sent
+///
+/// The message is removed from the payload when the job
+/// is dead-lettered, see `QueuedJob::dead_letter`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedEmail {
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    pub message: String,
+}
+
+impl TryFrom<EmailMessage> for QueuedEmail {
+    type Error = ServerError;
+
+    fn try_from(email: EmailMessage) -> Result<Self, Self::Error> {
+        let envelope = email.message.envelope();
+        let Ok(message) = String::from_utf8(email.message.formatted()) else {
+            return Err(ServerError::new("Failed to format email message"));
+        };
+        Ok(Self {
+            from: envelope.from().map(ToString::to_string),
+            to: envelope.to().iter().map(ToString::to_string).collect(),
+            message,
+        })
+    }
+}
+
+/// An uploaded image waiting to be saved in all `IMAGE_OUTPUT_FORMATS`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedImage {
+    /// The file name of the image variants
+    pub id: ModelID,
+    /// The uploaded image, deleted once the variants are saved
+    pub source: PathBuf,
+    pub upload_dir: PathBuf,
+}
+
+/// The model representing a row in the `queued_jobs` database table.
+#[derive(Debug, Clone)]
+pub struct QueuedJob {
+    pub id: ModelID,
+    pub kind: String,
+    /// None if the payload could not be decoded
+    pub task: Option<Task>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+}
+
+impl QueuedJob {
+    /// Creates a new `QueuedJob` from the database row
+    #[must_use]
+    pub fn from_row(
+        id: ModelID,
+        kind: String,
+        payload: serde_json::Value,
+        attempts: i32,
+        max_attempts: i32,
+    ) -> Self {
+        Self {
+            id,
+            kind,
+            task: serde_json::from_value(payload).ok(),
+            attempts,
+            max_attempts,
+        }
+    }
+}
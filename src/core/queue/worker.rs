@@ -0,0 +1,124 @@
+//! Background job queue workers impls
+
+use std::panic::AssertUnwindSafe;
+
+use futures_util::FutureExt;
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    error::{ServerError, ServerResult},
+    files::UploadedImage,
+    server::state::ServerState,
+    QUEUE_MAX_RETRY_BACKOFF, QUEUE_POLL_INTERVAL, QUEUE_RETRY_BACKOFF, QUEUE_WORKERS,
+};
+
+use super::models::{QueuedImage, QueuedJob, Task};
+
+/// Starts the background job queue workers
+pub fn start_workers(state: &ServerState) {
+    for worker in 0..QUEUE_WORKERS {
+        tokio::spawn(run_worker(worker, state.clone()));
+    }
+}
+
+/// Runs queued jobs until the server shuts down
+async fn run_worker(worker: usize, state: ServerState) {
+    tracing::trace!("Job queue worker {worker} started");
+    loop {
+        match QueuedJob::next(state.database()).await {
+            Ok(Some(job)) => process_job(job, &state).await,
+            Ok(None) => tokio::time::sleep(QUEUE_POLL_INTERVAL).await,
+            Err(err) => {
+                tracing::error!("Job queue worker {worker} failed to fetch job: {}", err);
+                tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Runs the job, failed jobs are retried or dead-lettered
+async fn process_job(job: QueuedJob, state: &ServerState) {
+    let db = state.database();
+    let Some(task) = job.task.clone() else {
+        tracing::error!("Queued job `{}` has an invalid payload", job.kind);
+        if let Err(err) = job.dead_letter("Invalid job payload", db).await {
+            tracing::error!("Failed to dead-letter queued job: {}", err);
+        }
+        return;
+    };
+
+    let error = match AssertUnwindSafe(task.run(state)).catch_unwind().await {
+        Ok(Ok(())) => {
+            if let Err(err) = job.complete(db).await {
+                tracing::error!("Failed to complete queued job: {}", err);
+            }
+            return;
+        }
+        Ok(Err(err)) => err.to_string(),
+        Err(_) => "Job panicked".to_owned(),
+    };
+
+    if job.attempts < job.max_attempts {
+        tracing::warn!(
+            "Queued job `{}` failed, attempt {} of {}: {error}",
+            job.kind,
+            job.attempts,
+            job.max_attempts
+        );
+        let run_at = OffsetDateTime::now_utc() + retry_backoff(job.attempts);
+        if let Err(err) = job.retry(&error, run_at, db).await {
+            tracing::error!("Failed to retry queued job: {}", err);
+        }
+    } else {
+        tracing::error!(
+            "Queued job `{}` failed after {} attempts, dead-lettered: {error}",
+            job.kind,
+            job.attempts
+        );
+        if let Err(err) = job.dead_letter(&error, db).await {
+            tracing::error!("Failed to dead-letter queued job: {}", err);
+        }
+    }
+}
+
+/// Returns the delay before the job is retried,
+/// the delay doubles on every attempt up to `QUEUE_MAX_RETRY_BACKOFF`.
+fn retry_backoff(attempts: i32) -> Duration {
+    let exponent = u32::try_from(attempts.saturating_sub(1)).unwrap_or_default();
+    2_i32
+        .checked_pow(exponent)
+        .and_then(|factor| QUEUE_RETRY_BACKOFF.checked_mul(factor))
+        .map_or(QUEUE_MAX_RETRY_BACKOFF, |backoff| {
+            backoff.min(QUEUE_MAX_RETRY_BACKOFF)
+        })
+}
+
+impl Task {
+    /// Runs the task
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the task failed
+    pub async fn run(self, state: &ServerState) -> ServerResult<()> {
+        match self {
+            Self::SendEmail(email) => state.mail_client().send_queued(&email).await,
+            Self::ImageVariants(image) => save_image_variants(image).await,
+        }
+    }
+}
+
+/// Saves the uploaded image in all `IMAGE_OUTPUT_FORMATS`
+/// and deletes the uploaded image.
+async fn save_image_variants(image: QueuedImage) -> ServerResult<()> {
+    if !image.source.exists() {
+        return Err(ServerError::new(format!(
+            "Uploaded image not found: {}",
+            image.source.display()
+        )));
+    }
+    UploadedImage::open(image.id, image.source.clone())
+        .await?
+        .save_all(image.upload_dir)
+        .await?;
+    crate::files::delete_file(&image.source).await
+}
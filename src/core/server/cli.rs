@@ -20,4 +20,14 @@ pub enum Commands {
         #[arg(short, long)]
         password: String,
     },
+
+    /// Deletes uploaded files not referenced by the database, then exits.
+    DeleteOrphanedFiles {
+        /// Report the files that would be deleted without deleting them.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Reports email templates and catalogue messages missing in any language, then exits.
+    CheckEmailTemplates,
 }
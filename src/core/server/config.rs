@@ -1,10 +1,15 @@
 //! Server configuration impls
 
-use std::{env, fmt, net::SocketAddr};
+use std::{env, fmt, net::SocketAddr, path::PathBuf};
 
 use axum_extra::extract::cookie::Key;
 
-use crate::{APP_DOMAIN_NAME, DEFAULT_SERVER_ADDR, DEFAULT_SERVER_PORT, SERVER_DOMAIN_NAME};
+use crate::{
+    mail::{SmtpConfig, SmtpTls},
+    settings::MAIL_FILE_DIR,
+    APP_DOMAIN_NAME, DEFAULT_MAIL_FROM, DEFAULT_SERVER_ADDR, DEFAULT_SERVER_PORT,
+    OUTLOOK_SMTP_SERVER, SERVER_DOMAIN_NAME,
+};
 
 /// Server config values
 #[derive(Clone)]
@@ -17,10 +22,13 @@ pub struct Config {
     /// The database connection url
     pub database_url: String,
 
-    /// Outlook smtp email
-    pub mail_email: String,
-    /// Outlook smtp password
-    pub mail_password: String,
+    /// Email transport the server sends emails with
+    pub mail_transport: MailTransportConfig,
+    /// Address the server sends emails from
+    pub mail_from: String,
+
+    /// Chat feed backend shared by the server instances
+    pub chat_feed: ChatFeedConfig,
 
     /// Cookie encryption key
     pub cookie_key: Key,
@@ -59,13 +67,101 @@ impl Config {
             database_url: env::var("DATABASE_URL")
                 .expect("DATABASE_URL environment variable not set."),
 
-            mail_email: env::var("MAIL_EMAIL").expect("MAIL_EMAIL environment variable not set."),
+            mail_transport: MailTransportConfig::from_env(),
+
+            mail_from: env::var("MAIL_FROM")
+                .or_else(|_| env::var("MAIL_EMAIL"))
+                .unwrap_or_else(|_| DEFAULT_MAIL_FROM.to_owned()),
 
-            mail_password: env::var("MAIL_PASSWORD")
-                .expect("MAIL_PASSWORD environment variable not set."),
+            chat_feed: ChatFeedConfig::from_env(),
 
             cookie_key: Key::try_from(cookie_key.as_bytes())
                 .expect("Key too short, cookie key must be at least 64 bytes"),
         }
     }
 }
+
+/// Email transport config
+#[derive(Debug, Clone)]
+pub enum MailTransportConfig {
+    /// Sends emails to an SMTP server
+    Smtp(SmtpConfig),
+    /// Writes emails as `.eml` files to the directory
+    File(PathBuf),
+    /// Captures emails in memory
+    Memory,
+}
+
+impl MailTransportConfig {
+    /// Loads email transport configuration from environmental variables.
+    ///
+    /// `MAIL_TRANSPORT` selects the transport: `smtp`, `file` or `memory`.
+    /// If not set, smtp is used and `MAIL_EMAIL` and `MAIL_PASSWORD` must be set,
+    /// emails are only written to `MAIL_DIR` when `file` is selected.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let Ok(transport) = env::var("MAIL_TRANSPORT") else {
+            let _ = env::var("MAIL_EMAIL").expect("MAIL_EMAIL environment variable not set.");
+            let _ = env::var("MAIL_PASSWORD").expect("MAIL_PASSWORD environment variable not set.");
+            return Self::Smtp(smtp_config_from_env());
+        };
+
+        match transport.to_ascii_lowercase().as_str() {
+            "smtp" => Self::Smtp(smtp_config_from_env()),
+            "file" => Self::File(
+                env::var("MAIL_DIR").map_or_else(|_| PathBuf::from(MAIL_FILE_DIR), PathBuf::from),
+            ),
+            "memory" => Self::Memory,
+            _ => panic!("Invalid MAIL_TRANSPORT: {transport}, expected: smtp, file or memory"),
+        }
+    }
+}
+
+/// Chat feed backend config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatFeedConfig {
+    /// Chat messages are only delivered to users connected to this instance
+    Local,
+    /// Chat messages are shared between server instances with Postgres `LISTEN/NOTIFY`
+    Postgres,
+}
+
+impl ChatFeedConfig {
+    /// Loads chat feed configuration from environmental variables.
+    ///
+    /// `CHAT_FEED` selects the backend: `local` or `postgres`,
+    /// servers running more than one instance must use `postgres`.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let feed = env::var("CHAT_FEED").unwrap_or_else(|_| "local".to_owned());
+        match feed.to_ascii_lowercase().as_str() {
+            "local" => Self::Local,
+            "postgres" => Self::Postgres,
+            _ => panic!("Invalid CHAT_FEED: {feed}, expected: local or postgres"),
+        }
+    }
+}
+
+/// Loads SMTP configuration from environmental variables.
+fn smtp_config_from_env() -> SmtpConfig {
+    let port = env::var("MAIL_SMTP_PORT").ok().map(|port| {
+        port.parse()
+            .unwrap_or_else(|err| panic!("Invalid MAIL_SMTP_PORT: {port}. : {err}"))
+    });
+    let tls = env::var("MAIL_SMTP_TLS").map_or(SmtpTls::StartTls, |tls| {
+        tls.parse().unwrap_or_else(|err| panic!("{err}"))
+    });
+    let credentials = env::var("MAIL_PASSWORD").ok().map(|password| {
+        let username = env::var("MAIL_USERNAME")
+            .or_else(|_| env::var("MAIL_EMAIL"))
+            .expect("MAIL_USERNAME or MAIL_EMAIL environment variable not set.");
+        (username, password)
+    });
+
+    SmtpConfig {
+        host: env::var("MAIL_SMTP_HOST").unwrap_or_else(|_| OUTLOOK_SMTP_SERVER.to_owned()),
+        port,
+        tls,
+        credentials,
+    }
+}
@@ -0,0 +1,321 @@
+//! Maintenance job database impl
+
+use sqlx::{pool::PoolConnection, Postgres};
+use time::OffsetDateTime;
+
+use crate::{
+    error::ServerResult,
+    server::state::DatabaseConnection,
+    types::{ModelID, Pagination},
+};
+
+use super::{
+    jobs::Job,
+    models::{JobOutcome, JobRun, JobRunList, JobTrigger},
+};
+
+/// Advisory lock namespace of the maintenance jobs
+const JOB_LOCK_NAMESPACE: &str = "maintenance";
+
+/// A Postgres session advisory lock held while a job runs
+///
+/// The lock is tied to the connection, if the lock is dropped
+/// without being released the connection is closed to release the lock.
+#[derive(Debug)]
+pub struct JobLock {
+    job: Job,
+    conn: Option<PoolConnection<Postgres>>,
+}
+
+impl JobLock {
+    /// Acquires the job lock, return None if the lock is held by another run
+    ///
+    /// # Errors
+    ///
+    /// Return database error
+    #[tracing::instrument(name = "Acquire Job Lock", skip(db))]
+    pub async fn acquire(job: Job, db: DatabaseConnection) -> ServerResult<Option<Self>> {
+        let mut conn = db.pool.acquire().await?;
+        match sqlx::query!(
+            r#"
+                SELECT pg_try_advisory_lock(hashtext($1), hashtext($2)) AS "locked!"
+            "#,
+            JOB_LOCK_NAMESPACE,
+            job.name()
+        )
+        .fetch_one(&mut *conn)
+        .await
+        {
+            Ok(rec) => {
+                tracing::debug!("Job lock acquired: {}", rec.locked);
+                Ok(rec.locked.then_some(Self {
+                    job,
+                    conn: Some(conn),
+                }))
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to acquire job lock: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Releases the job lock
+    pub async fn release(mut self) {
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+        let result = sqlx::query!(
+            r#"
+                SELECT pg_advisory_unlock(hashtext($1), hashtext($2))
+            "#,
+            JOB_LOCK_NAMESPACE,
+            self.job.name()
+        )
+        .fetch_one(&mut *conn)
+        .await;
+
+        if let Err(err) = result {
+            tracing::error!("Database error, failed to release job lock: {}", err);
+            // Closing the connection releases the lock
+            drop(conn.detach());
+        }
+    }
+}
+
+impl Drop for JobLock {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            // Don't return a connection holding the lock to the pool
+            drop(conn.detach());
+        }
+    }
+}
+
+impl JobRun {
+    /// Fetches the job run records from the database
+    #[tracing::instrument(name = "Fetch Job Runs", skip(db))]
+    pub async fn records(
+        job: Job,
+        pg: Pagination,
+        db: DatabaseConnection,
+    ) -> ServerResult<JobRunList> {
+        let (offset, limit) = pg.offset_limit();
+        match sqlx::query!(
+            r#"
+                SELECT run.id,
+                    run.job,
+                    run.trigger,
+                    run.outcome,
+                    run.rows_affected,
+                    run.error,
+                    run.started_at,
+                    run.finished_at
+                FROM maintenance.job_runs run
+                WHERE run.job = $1
+                ORDER BY run.started_at DESC
+                LIMIT $2
+                OFFSET $3;
+            "#,
+            job.name(),
+            limit,
+            offset,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let runs = records
+                    .into_iter()
+                    .map(|rec| {
+                        Self::from_row(
+                            rec.id.into(),
+                            rec.job,
+                            &rec.trigger,
+                            &rec.outcome,
+                            rec.rows_affected,
+                            rec.error,
+                            rec.started_at,
+                            rec.finished_at,
+                        )
+                    })
+                    .collect();
+
+                Ok(runs)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch job runs: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Fetches the last run of each job from the database
+    #[tracing::instrument(name = "Fetch Last Job Runs", skip(db))]
+    pub async fn last_runs(db: DatabaseConnection) -> ServerResult<JobRunList> {
+        match sqlx::query!(
+            r#"
+                SELECT DISTINCT ON (run.job)
+                    run.id,
+                    run.job,
+                    run.trigger,
+                    run.outcome,
+                    run.rows_affected,
+                    run.error,
+                    run.started_at,
+                    run.finished_at
+                FROM maintenance.job_runs run
+                ORDER BY run.job, run.started_at DESC;
+            "#,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let runs = records
+                    .into_iter()
+                    .map(|rec| {
+                        Self::from_row(
+                            rec.id.into(),
+                            rec.job,
+                            &rec.trigger,
+                            &rec.outcome,
+                            rec.rows_affected,
+                            rec.error,
+                            rec.started_at,
+                            rec.finished_at,
+                        )
+                    })
+                    .collect();
+
+                Ok(runs)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch last job runs: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Return true if a run of the job started at or after `since`
+    #[tracing::instrument(name = "Find Job Run", skip(db))]
+    pub async fn started_since(
+        job: Job,
+        since: OffsetDateTime,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                SELECT EXISTS(
+                    SELECT 1 FROM maintenance.job_runs run
+                    WHERE run.job = $1
+                        AND run.trigger = 'schedule'
+                        AND run.started_at >= $2
+                ) AS "exists!"
+            "#,
+            job.name(),
+            since,
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.exists),
+            Err(err) => {
+                tracing::error!("Database error, failed to find job run: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Inserts a running job run into the database
+    #[tracing::instrument(name = "Insert Job Run", skip(db))]
+    pub async fn start(
+        job: Job,
+        trigger: JobTrigger,
+        db: DatabaseConnection,
+    ) -> ServerResult<Self> {
+        let run = Self {
+            id: ModelID::new(),
+            job: job.name().to_owned(),
+            trigger,
+            outcome: JobOutcome::Running,
+            rows_affected: None,
+            error: None,
+            started_at: OffsetDateTime::now_utc(),
+            finished_at: None,
+        };
+        match sqlx::query!(
+            r#"
+                INSERT INTO maintenance.job_runs(
+                    id,
+                    job,
+                    trigger,
+                    outcome,
+                    started_at
+                )
+                VALUES($1, $2, $3, $4, $5);
+            "#,
+            run.id.0,
+            run.job,
+            run.trigger.as_str(),
+            run.outcome.as_str(),
+            run.started_at,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Job run inserted successfully: {:?}", result);
+                Ok(run)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to insert job run: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Records the job run outcome, the rows affected or the error message
+    #[tracing::instrument(name = "Finish Job Run", skip(self, db))]
+    pub async fn finish(
+        &self,
+        result: Result<u64, String>,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        let (outcome, rows_affected, error) = match result {
+            Ok(rows) => (
+                JobOutcome::Succeeded,
+                Some(i64::try_from(rows).unwrap_or(i64::MAX)),
+                None,
+            ),
+            Err(err) => (JobOutcome::Failed, None, Some(err)),
+        };
+        match sqlx::query!(
+            r#"
+                UPDATE maintenance.job_runs run
+                SET outcome = $1,
+                    rows_affected = $2,
+                    error = $3,
+                    finished_at = $4
+                WHERE run.id = $5;
+            "#,
+            outcome.as_str(),
+            rows_affected,
+            error,
+            OffsetDateTime::now_utc(),
+            self.id.0,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Job run finished successfully: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to finish job run: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Maintenance job http handlers impls
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    auth::SuperUser,
+    endpoint::{EndpointRejection, EndpointResult},
+    server::state::{DatabaseConnection, ServerState},
+    types::Pagination,
+};
+
+use super::{
+    db::JobLock,
+    jobs::{run_job, Job},
+    models::{JobInfo, JobRun, JobRunList, JobTrigger},
+};
+
+/// Handles the `GET /maintenance/jobs` route.
+#[tracing::instrument(skip(db))]
+pub async fn job_list(
+    _: SuperUser,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<Vec<JobInfo>>> {
+    let mut last_runs = JobRun::last_runs(db).await?;
+    let jobs = Job::ALL
+        .into_iter()
+        .map(|job| {
+            let last_run = last_runs
+                .iter()
+                .position(|run| run.job == job.name())
+                .map(|index| last_runs.swap_remove(index));
+            JobInfo::new(job, last_run)
+        })
+        .collect();
+    Ok(Json(jobs))
+}
+
+/// Handles the `GET /maintenance/jobs/:job/runs` route.
+#[tracing::instrument(skip(db))]
+pub async fn job_runs(
+    _: SuperUser,
+    Path(job): Path<String>,
+    pg: Option<Query<Pagination>>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<JobRunList>> {
+    let job = find_job(&job)?;
+    let pagination = pg.unwrap_or_default().0;
+    let runs = JobRun::records(job, pagination, db).await?;
+    Ok(Json(runs))
+}
+
+/// Handles the `POST /maintenance/jobs/:job/run` route.
+///
+/// Starts the job immediately in the background and returns the started run.
+#[tracing::instrument(skip(state))]
+pub async fn job_trigger(
+    _: SuperUser,
+    Path(job): Path<String>,
+    State(state): State<ServerState>,
+) -> EndpointResult<(StatusCode, Json<JobRun>)> {
+    let job = find_job(&job)?;
+    let db = state.database();
+    let Some(lock) = JobLock::acquire(job, db.clone()).await? else {
+        return Err(EndpointRejection::Conflict(
+            "The job is already running.".into(),
+        ));
+    };
+    let run = match JobRun::start(job, JobTrigger::Manual, db).await {
+        Ok(run) => run,
+        Err(err) => {
+            lock.release().await;
+            return Err(err.into());
+        }
+    };
+
+    tokio::spawn(run_job(job, run.clone(), lock, state));
+    Ok((StatusCode::ACCEPTED, Json(run)))
+}
+
+/// Returns the job with the name
+fn find_job(name: &str) -> EndpointResult<Job> {
+    Job::from_name(name).ok_or_else(|| EndpointRejection::not_found("Maintenance job"))
+}
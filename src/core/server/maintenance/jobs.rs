@@ -0,0 +1,190 @@
+//! Maintenance jobs runner impls
+
+use std::panic::AssertUnwindSafe;
+
+use futures_util::FutureExt;
+use time::OffsetDateTime;
+
+use crate::{accounts::AccountDelete, error::ServerResult, server::state::ServerState};
+
+use super::{
+    db::JobLock,
+    models::{JobRun, JobTrigger},
+    schedule::Schedule,
+    tasks::{
+        archive_stale_harvests, delete_orphaned_files, send_saved_search_digests,
+        send_unread_message_emails,
+    },
+};
+
+/// Server maintenance jobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    /// Delete user accounts that requested for account deletion
+    DeleteAccounts,
+    /// Archive harvests listed for more than `HARVEST_MAX_LISTING_DAYS` days
+    ArchiveHarvests,
+    /// Email users new harvests matching their saved searches
+    SavedSearchDigests,
+    /// Delete uploaded files left behind by failed requests
+    DeleteOrphanedFiles,
+    /// Email users direct messages they have not read
+    UnreadMessageEmails,
+}
+
+impl Job {
+    /// All the maintenance jobs
+    pub const ALL: [Self; 5] = [
+        Self::DeleteAccounts,
+        Self::ArchiveHarvests,
+        Self::SavedSearchDigests,
+        Self::DeleteOrphanedFiles,
+        Self::UnreadMessageEmails,
+    ];
+
+    /// Returns the name of the job
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::DeleteAccounts => "delete-accounts",
+            Self::ArchiveHarvests => "archive-harvests",
+            Self::SavedSearchDigests => "saved-search-digests",
+            Self::DeleteOrphanedFiles => "delete-orphaned-files",
+            Self::UnreadMessageEmails => "unread-message-emails",
+        }
+    }
+
+    /// Returns the job with the name
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|job| job.name() == name)
+    }
+
+    /// Returns the cron-like schedule expression of the job in UTC
+    #[must_use]
+    pub const fn schedule_expr(self) -> &'static str {
+        match self {
+            Self::DeleteAccounts => "0 4 * * *",
+            Self::ArchiveHarvests => "15 4 * * *",
+            Self::SavedSearchDigests => "0 7 * * *",
+            Self::DeleteOrphanedFiles => "30 4 * * *",
+            Self::UnreadMessageEmails => "*/5 * * * *",
+        }
+    }
+
+    /// Returns the schedule of the job
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job schedule expression is invalid
+    #[must_use]
+    pub fn schedule(self) -> Schedule {
+        Schedule::parse(self.schedule_expr()).unwrap()
+    }
+
+    /// Runs the job, return the number of rows affected
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the job failed
+    pub async fn run(self, state: &ServerState) -> ServerResult<u64> {
+        let db = state.database();
+        match self {
+            Self::DeleteAccounts => AccountDelete::permanently_delete_accounts(db).await,
+            Self::ArchiveHarvests => archive_stale_harvests(db, state.mail_client()).await,
+            Self::SavedSearchDigests => send_saved_search_digests(db, state.mail_client()).await,
+            Self::DeleteOrphanedFiles => {
+                let orphaned = delete_orphaned_files(false, db).await?;
+                Ok(orphaned.paths.len() as u64)
+            }
+            Self::UnreadMessageEmails => send_unread_message_emails(db, state.mail_client()).await,
+        }
+    }
+}
+
+/// Server maintenance jobs runner,
+/// spawns a scheduler for each maintenance job.
+pub async fn server_maintenance(state: ServerState) {
+    for job in Job::ALL {
+        tokio::spawn(schedule_job(job, state.clone()));
+    }
+}
+
+/// Runs the job on its schedule
+///
+/// Every server instance schedules the job, the advisory lock and
+/// the run history make sure only one instance runs each scheduled run.
+async fn schedule_job(job: Job, state: ServerState) {
+    let schedule = job.schedule();
+    loop {
+        let now = OffsetDateTime::now_utc();
+        let Some(run_at) = schedule.next_after(now) else {
+            tracing::error!("Maintenance job `{}` has no next run", job.name());
+            return;
+        };
+        tokio::time::sleep((run_at - now).unsigned_abs()).await;
+
+        let db = state.database();
+        let lock = match JobLock::acquire(job, db.clone()).await {
+            Ok(Some(lock)) => lock,
+            Ok(None) => {
+                tracing::trace!(
+                    "Maintenance job `{}` running on another instance",
+                    job.name()
+                );
+                continue;
+            }
+            Err(err) => {
+                tracing::error!("Maintenance job `{}` lock failed: {}", job.name(), err);
+                continue;
+            }
+        };
+        // Another instance may have finished this run before the lock was acquired
+        match JobRun::started_since(job, run_at, db.clone()).await {
+            Ok(false) => {}
+            Ok(true) => {
+                lock.release().await;
+                continue;
+            }
+            Err(err) => {
+                tracing::error!("Maintenance job `{}` failed: {}", job.name(), err);
+                lock.release().await;
+                continue;
+            }
+        }
+        match JobRun::start(job, JobTrigger::Schedule, db).await {
+            Ok(run) => run_job(job, run, lock, state.clone()).await,
+            Err(err) => {
+                tracing::error!("Maintenance job `{}` failed: {}", job.name(), err);
+                lock.release().await;
+            }
+        }
+    }
+}
+
+/// Runs the job and records its outcome, the job lock is released after the run.
+pub async fn run_job(job: Job, run: JobRun, lock: JobLock, state: ServerState) {
+    tracing::info!("Maintenance job `{}` started", job.name());
+    let result = match AssertUnwindSafe(job.run(&state)).catch_unwind().await {
+        Ok(Ok(rows)) => {
+            tracing::info!("Maintenance job `{}` succeeded: {rows} rows", job.name());
+            Ok(rows)
+        }
+        Ok(Err(err)) => {
+            tracing::error!("Maintenance job `{}` failed: {}", job.name(), err);
+            Err(err.to_string())
+        }
+        Err(_) => {
+            tracing::error!("Maintenance job `{}` panicked", job.name());
+            Err("Job panicked".to_owned())
+        }
+    };
+    if let Err(err) = run.finish(result, state.database()).await {
+        tracing::error!(
+            "Failed to record maintenance job `{}` run: {}",
+            job.name(),
+            err
+        );
+    }
+    lock.release().await;
+}
@@ -1,19 +1,37 @@
 //! Server maintenance routines impls
 
+mod db;
+pub mod handlers;
+mod jobs;
+mod models;
+mod schedule;
 mod tasks;
 
-pub use tasks::server_maintenance;
+pub use jobs::server_maintenance;
+pub use tasks::delete_orphaned_files;
 
 /*
 
+* Jobs
+- each task runs as a named job on a cron-like schedule (UTC), see `Job::schedule_expr`
+- runs are recorded in maintenance.job_runs
+- a postgres advisory lock makes sure only one server instance runs each job
+- superusers can list jobs and trigger a job from `/maintenance/jobs`
+
 * Delete users
 - users are added to account_deletion_requested
 -
 * Archive harvests that have been up for more than 90 days
--
+- harvest images are deleted and farmers are notified by email
+- each run is recorded in maintenance.harvest_archive_runs
+
+* Unread direct messages
+- receivers are emailed unread messages 15 minutes after they were sent, every 5 minutes
+- users can turn the emails off or set quiet hours in their message notification settings
 
 * Delete Files
 - delete files that have been saved but the request did not complete successfully
+- `reapears delete-orphaned-files --dry-run` reports the files without deleting them
 
 
 */
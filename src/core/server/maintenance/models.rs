@@ -0,0 +1,136 @@
+//! Maintenance job models impls
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::types::ModelID;
+
+use super::jobs::Job;
+
+/// A `Vec` of job runs
+pub type JobRunList = Vec<JobRun>;
+
+/// What started a job run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobTrigger {
+    Schedule,
+    Manual,
+}
+
+impl JobTrigger {
+    /// Returns the trigger as stored in the database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Schedule => "schedule",
+            Self::Manual => "manual",
+        }
+    }
+
+    /// Creates a new `JobTrigger` from the database column
+    #[must_use]
+    pub fn from_row(trigger: &str) -> Self {
+        match trigger {
+            "schedule" => Self::Schedule,
+            // Triggers are checked by the database constraint
+            _ => Self::Manual,
+        }
+    }
+}
+
+/// The outcome of a job run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobOutcome {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobOutcome {
+    /// Returns the outcome as stored in the database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Creates a new `JobOutcome` from the database column
+    #[must_use]
+    pub fn from_row(outcome: &str) -> Self {
+        match outcome {
+            "running" => Self::Running,
+            "succeeded" => Self::Succeeded,
+            // Outcomes are checked by the database constraint
+            _ => Self::Failed,
+        }
+    }
+}
+
+/// The model representing a row in the `job_runs` database table.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRun {
+    pub id: ModelID,
+    pub job: String,
+    pub trigger: JobTrigger,
+    pub outcome: JobOutcome,
+    pub rows_affected: Option<i64>,
+    pub error: Option<String>,
+    pub started_at: OffsetDateTime,
+    pub finished_at: Option<OffsetDateTime>,
+}
+
+impl JobRun {
+    /// Creates a new `JobRun` from the database row
+    #[allow(clippy::too_many_arguments)]
+    #[must_use]
+    pub fn from_row(
+        id: ModelID,
+        job: String,
+        trigger: &str,
+        outcome: &str,
+        rows_affected: Option<i64>,
+        error: Option<String>,
+        started_at: OffsetDateTime,
+        finished_at: Option<OffsetDateTime>,
+    ) -> Self {
+        Self {
+            id,
+            job,
+            trigger: JobTrigger::from_row(trigger),
+            outcome: JobOutcome::from_row(outcome),
+            rows_affected,
+            error,
+            started_at,
+            finished_at,
+        }
+    }
+}
+
+/// A maintenance job with its schedule and last run
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobInfo {
+    pub name: &'static str,
+    pub schedule: &'static str,
+    pub next_run_at: Option<OffsetDateTime>,
+    pub last_run: Option<JobRun>,
+}
+
+impl JobInfo {
+    /// Creates a new `JobInfo` for the job
+    #[must_use]
+    pub fn new(job: Job, last_run: Option<JobRun>) -> Self {
+        Self {
+            name: job.name(),
+            schedule: job.schedule_expr(),
+            next_run_at: job.schedule().next_after(OffsetDateTime::now_utc()),
+            last_run,
+        }
+    }
+}
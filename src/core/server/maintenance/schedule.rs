@@ -0,0 +1,144 @@
+//! Maintenance job schedule impls
+
+use time::{Duration, OffsetDateTime, Time};
+
+use crate::error::{ServerError, ServerResult};
+
+/// Number of days searched for the next run of a schedule
+const MAX_SEARCH_DAYS: u16 = 366 * 5;
+
+/// A cron-like job schedule in UTC
+///
+/// The schedule has five fields: `minute hour day-of-month month day-of-week`,
+/// each field accepts `*`, a value `5`, a range `1-5`, a step `*/15` or `1-30/5`
+/// and a comma separated list of these, e.g. `0 4 * * *` runs every day at 4am.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl Schedule {
+    /// Parses a cron-like schedule expression
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the expression is invalid
+    pub fn parse(expr: &str) -> ServerResult<Self> {
+        let fields: Vec<_> = expr.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(ServerError::new(format!(
+                "Invalid schedule `{expr}`, expected five fields"
+            )));
+        };
+
+        // Sunday can be written as 0 or 7
+        let mut weekdays = parse_field(day_of_week, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days_of_month: parse_field(day_of_month, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            days_of_week: weekdays,
+            any_day_of_month: day_of_month == "*",
+            any_day_of_week: day_of_week == "*",
+        })
+    }
+
+    /// Returns the next time the schedule fires after `after`,
+    /// return None if the schedule does not fire within the next five years.
+    #[must_use]
+    pub fn next_after(&self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        let start = after.replace_time(Time::from_hms(after.hour(), after.minute(), 0).ok()?)
+            + Duration::minutes(1);
+
+        let mut date = start.date();
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.matches_date(date) {
+                let from = if date == start.date() {
+                    (start.hour(), start.minute())
+                } else {
+                    (0, 0)
+                };
+                for hour in from.0..24 {
+                    if !contains(self.hours, hour) {
+                        continue;
+                    }
+                    let from_minute = if hour == from.0 { from.1 } else { 0 };
+                    for minute in from_minute..60 {
+                        if contains(self.minutes, minute) {
+                            let time = Time::from_hms(hour, minute, 0).ok()?;
+                            return Some(date.with_time(time).assume_utc());
+                        }
+                    }
+                }
+            }
+            date = date.next_day()?;
+        }
+        None
+    }
+
+    /// Returns true if the schedule fires on the date
+    fn matches_date(&self, date: time::Date) -> bool {
+        if !contains(self.months, u8::from(date.month())) {
+            return false;
+        }
+        let day_of_month = contains(self.days_of_month, date.day());
+        let day_of_week = contains(self.days_of_week, date.weekday().number_days_from_sunday());
+
+        // Like cron, restricted day of month and day of week match either
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+/// Returns true if the value bit is set
+const fn contains(bits: u64, value: u8) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Parses a schedule field into a set of bits
+///
+/// # Errors
+///
+/// Return an error if the field is invalid or out of range
+fn parse_field(field: &str, min: u8, max: u8) -> ServerResult<u64> {
+    let invalid = || ServerError::new(format!("Invalid schedule field `{field}`"));
+    let parse = |value: &str| value.parse::<u8>().map_err(|_| invalid());
+
+    let mut bits = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, parse(step)?),
+            None => (item, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            // A value with a step runs from the value to the end of the field
+            None if item.contains('/') => (parse(range)?, max),
+            None => {
+                let value = parse(range)?;
+                (value, value)
+            }
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(usize::from(step)) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
@@ -1,27 +1,11 @@
-//! Server maintenance impls
+//! Server maintenance tasks impls
 
-use std::time::Duration;
+mod delete_files;
+mod delete_harvests;
+mod saved_search_digest;
+mod unread_messages;
 
-use time::{OffsetDateTime, Time};
-
-use crate::{accounts::AccountDelete, server::state::ServerState};
-
-/// Server maintenance tasks runner
-pub async fn server_maintenance(state: ServerState) {
-    // Set up starting time
-    // * Server maintenance will be kicked off at 4am every day
-    const DAY: u64 = 60 * 60 * 24;
-    let now = OffsetDateTime::now_utc().time();
-    let run_at = Time::from_hms(4, 0, 0).unwrap();
-    let start_at = tokio::time::Instant::now() + (now - run_at).unsigned_abs();
-
-    let mut interval = tokio::time::interval_at(start_at, Duration::from_secs(DAY));
-    // Runner
-    loop {
-        interval.tick().await;
-
-        let db = state.database();
-        // Delete user accounts the requested for account deletion
-        AccountDelete::permanently_delete_accounts(db).await;
-    }
-}
+pub use delete_files::delete_orphaned_files;
+pub use delete_harvests::archive_stale_harvests;
+pub use saved_search_digest::send_saved_search_digests;
+pub use unread_messages::send_unread_message_emails;
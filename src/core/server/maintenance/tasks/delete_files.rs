@@ -1,20 +1,203 @@
-//! kk
+//! Orphaned files cleanup task impls
 
-// use crate::files;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 
-// const FILE_LOG: &str = "";
+use time::OffsetDateTime;
+use tokio::fs;
+use uuid::Uuid;
 
-// /// Delete files that have been saved, 
-// /// but the request failed complete process complete.
-// pub async fn delete_file(){
+use crate::{
+    error::ServerResult, features::direct_message::models::MessageAttachment, files,
+    server::state::DatabaseConnection, settings, ORPHANED_FILES_GRACE_PERIOD,
+};
 
-//     files::delete_files()
+/// Files found on upload directories that are not referenced by the database
+#[derive(Debug, Clone, Default)]
+pub struct OrphanedFiles {
+    /// Number of uploaded files checked
+    pub scanned: usize,
+    /// Unreferenced files older than the grace period
+    pub paths: Vec<PathBuf>,
+}
 
-// }
+/// Deletes uploaded files that are not referenced by the database,
+/// e.g. files saved by a request that failed halfway.
+///
+/// Only files named by a model id and older than `ORPHANED_FILES_GRACE_PERIOD`
+/// are deleted. In `dry_run` mode the files are reported but not deleted.
+///
+/// # Errors
+///
+/// Return database or io error
+pub async fn delete_orphaned_files(
+    dry_run: bool,
+    db: DatabaseConnection,
+) -> ServerResult<OrphanedFiles> {
+    // Message images never sent are no longer referenced once
+    // they are older than the grace period.
+    let uploaded_before = OffsetDateTime::now_utc() - ORPHANED_FILES_GRACE_PERIOD;
+    if !dry_run {
+        let deleted = MessageAttachment::delete_unsent_images(uploaded_before, db.clone()).await?;
+        tracing::debug!("Orphaned file cleanup: {deleted} unsent message images deleted");
+    }
 
-// ///
-// pub async fn append_files(){
+    // Referenced files are fetched before listing the upload directories,
+    // so files uploaded in between are protected by the grace period.
+    let referenced = UploadedFiles::find(uploaded_before, db).await?;
 
-// }
+    let mut orphaned = OrphanedFiles::default();
+    for (upload_dir, referenced) in [
+        (settings::USER_UPLOAD_DIR, &referenced.user_photos),
+        (settings::FARM_LOGO_UPLOAD_DIR, &referenced.farm_logos),
+        (settings::CULTIVAR_UPLOAD_DIR, &referenced.cultivar_images),
+        (settings::HARVEST_UPLOAD_DIR, &referenced.harvest_images),
+        (settings::MESSAGE_UPLOAD_DIR, &referenced.message_images),
+    ] {
+        find_orphaned_files(Path::new(upload_dir), referenced, &mut orphaned).await?;
+    }
 
-// struct File
\ No newline at end of file
+    for path in &orphaned.paths {
+        if dry_run {
+            tracing::warn!("Orphaned file cleanup (dry run), would delete: {path:?}");
+        } else {
+            tracing::warn!("Orphaned file cleanup, deleting: {path:?}");
+        }
+    }
+    if !dry_run {
+        files::delete_files(orphaned.paths.clone()).await?;
+    }
+    tracing::debug!(
+        "Orphaned file cleanup: {} files scanned, {} orphaned files found",
+        orphaned.scanned,
+        orphaned.paths.len()
+    );
+
+    Ok(orphaned)
+}
+
+/// Lists the upload directory files not in `referenced` older than the grace period
+async fn find_orphaned_files(
+    upload_dir: &Path,
+    referenced: &HashSet<String>,
+    orphaned: &mut OrphanedFiles,
+) -> ServerResult<()> {
+    let mut entries = match fs::read_dir(upload_dir).await {
+        Ok(entries) => entries,
+        // Nothing has been uploaded yet
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let now = SystemTime::now();
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let path = entry.path();
+        // Uploads are named by a model id, other files are server assets
+        let Some(stem) = file_stem(&path) else {
+            continue;
+        };
+        if Uuid::try_parse(&stem).is_err() {
+            continue;
+        }
+        orphaned.scanned += 1;
+
+        if referenced.contains(&stem) {
+            continue;
+        }
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        if ORPHANED_FILES_GRACE_PERIOD <= age {
+            orphaned.paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the file name without the extension,
+/// images are saved in all `IMAGE_OUTPUT_FORMATS` under the same name.
+fn file_stem(path: &Path) -> Option<String> {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+}
+
+/// Uploaded file names referenced by the database, without extensions
+#[derive(Debug, Clone, Default)]
+struct UploadedFiles {
+    user_photos: HashSet<String>,
+    farm_logos: HashSet<String>,
+    cultivar_images: HashSet<String>,
+    harvest_images: HashSet<String>,
+    message_images: HashSet<String>,
+}
+
+impl UploadedFiles {
+    /// Fetches uploaded file names referenced by the database,
+    /// message images not sent since `unsent_before` are not referenced.
+    #[tracing::instrument(name = "Fetch Uploaded Files", skip(db))]
+    async fn find(unsent_before: OffsetDateTime, db: DatabaseConnection) -> ServerResult<Self> {
+        match sqlx::query!(
+            r#"
+                SELECT 'user' AS "upload!", profile.photo AS "file_name!"
+                FROM accounts.user_profiles profile
+                WHERE profile.photo IS NOT NULL
+
+                UNION ALL
+                SELECT 'farm_logo', farm.logo
+                FROM services.farms farm
+                WHERE farm.logo IS NOT NULL
+
+                UNION ALL
+                SELECT 'cultivar', cultivar.image
+                FROM services.cultivars cultivar
+                WHERE cultivar.image IS NOT NULL
+
+                UNION ALL
+                SELECT 'harvest', unnest(harvest.images)
+                FROM services.harvests harvest
+                WHERE harvest.images IS NOT NULL
+
+                UNION ALL
+                SELECT 'message', attachment.image
+                FROM features.message_attachments attachment
+                WHERE attachment.image IS NOT NULL
+                    AND (attachment.message_id IS NOT NULL
+                        OR attachment.created_at >= $1);
+            "#,
+            unsent_before,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let mut files = Self::default();
+                for rec in records {
+                    let Some(stem) = file_stem(Path::new(&rec.file_name)) else {
+                        continue;
+                    };
+                    match rec.upload.as_str() {
+                        "user" => files.user_photos.insert(stem),
+                        "farm_logo" => files.farm_logos.insert(stem),
+                        "cultivar" => files.cultivar_images.insert(stem),
+                        "message" => files.message_images.insert(stem),
+                        _ => files.harvest_images.insert(stem),
+                    };
+                }
+                Ok(files)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch uploaded files: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
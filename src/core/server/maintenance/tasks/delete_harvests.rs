@@ -1,44 +1,262 @@
+//! Stale harvests archive task impls
 
+use std::collections::BTreeMap;
 
-pub async fn archive_farm_harvests(
-    farm_id: ModelID,
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    error::{ServerError, ServerResult},
+    mail::{ArchivedHarvest, Mail},
+    queue::Task,
+    server::state::DatabaseConnection,
+    services::produce::{
+        harvest::delete_harvest_photos,
+        order::{models::Order, notify_order_parties, OrderEvent},
+    },
+    types::{Language, ModelID},
+    HARVEST_MAX_LISTING_DAYS, SERVER_DOMAIN_NAME,
+};
+
+/// A harvest archived by the maintenance with its farmer contact
+#[derive(Debug, Clone)]
+struct StaleHarvest {
+    id: ModelID,
+    images: Option<Vec<String>>,
+    farmer_id: Option<ModelID>,
+    farmer_first_name: Option<String>,
+    farmer_email: Option<String>,
+    farmer_language: Language,
+    harvest: ArchivedHarvest,
+}
+
+/// Archives harvests that have been listed on the platform for more than
+/// `HARVEST_MAX_LISTING_DAYS` days, deletes their images and emails the farmers.
+/// The pending and accepted orders of the archived harvests are closed
+/// and their buyers and farmers notified.
+///
+/// Each run is recorded in `maintenance.harvest_archive_runs`,
+/// return the number of harvests archived.
+///
+/// # Errors
+///
+/// Return database error if failed to archive the harvests
+pub async fn archive_stale_harvests(db: DatabaseConnection, mail: Mail) -> ServerResult<u64> {
+    let started_at = OffsetDateTime::now_utc();
+    let mut tx = db.pool.begin().await?; // init transaction
+    let harvests = archive_harvests(started_at, &mut tx).await?;
+    let harvest_ids: Vec<_> = harvests.iter().map(|harvest| harvest.id).collect();
+    let orders = Order::close_for_archived_harvests(&harvest_ids, started_at, &mut tx).await?;
+    tx.commit().await?; // Commit transaction
+
+    let mut images = Vec::new();
+    for harvest in &harvests {
+        images.extend(harvest.images.iter().flatten().cloned());
+    }
+    if let Err(err) = delete_harvest_photos(images.into_iter()).await {
+        tracing::error!("Failed to delete archived harvests images: {}", err);
+    }
+
+    let mut farmers: BTreeMap<ModelID, Vec<&StaleHarvest>> = BTreeMap::new();
+    for harvest in &harvests {
+        // Farms archived with their owner account have no farmer to notify
+        if let Some(farmer_id) = harvest.farmer_id {
+            farmers.entry(farmer_id).or_default().push(harvest);
+        }
+    }
+
+    let mut farmers_notified = 0;
+    for harvests in farmers.values() {
+        match notify_farmer(harvests, &mail, db.clone()).await {
+            Ok(()) => farmers_notified += 1,
+            Err(err) => tracing::error!("Failed to enqueue harvests archived email: {}", err),
+        }
+    }
+
+    for (order_id, status) in orders {
+        notify_order_parties(order_id, OrderEvent::from(status), db.clone(), mail.clone()).await;
+    }
+
+    if let Err(err) = record_archive_run(
+        harvests.len(),
+        farmers_notified,
+        started_at,
+        OffsetDateTime::now_utc(),
+        db,
+    )
+    .await
+    {
+        tracing::error!("Failed to record stale harvests archive run: {}", err);
+    }
+    Ok(harvests.len() as u64)
+}
+
+/// Emails the farmer about their archived harvests
+async fn notify_farmer(
+    harvests: &[&StaleHarvest],
+    mail: &Mail,
+    db: DatabaseConnection,
+) -> ServerResult<()> {
+    let farmer = harvests[0];
+    let (Some(first_name), Some(email)) = (&farmer.farmer_first_name, &farmer.farmer_email) else {
+        return Err(ServerError::new("Farmer email address not found"));
+    };
+
+    let archived: Vec<_> = harvests
+        .iter()
+        .map(|harvest| harvest.harvest.clone())
+        .collect();
+    let domain = SERVER_DOMAIN_NAME.get().unwrap();
+    let link = format!("{domain}/harvests");
+
+    let email = mail.harvests_archived(
+        first_name,
+        email,
+        farmer.farmer_language,
+        &archived,
+        HARVEST_MAX_LISTING_DAYS,
+        &link,
+    )?;
+    Task::send_email(email)?.enqueue(db).await?;
+    Ok(())
+}
+
+/// Archives active harvests listed before `HARVEST_MAX_LISTING_DAYS` days ago,
+/// return the archived harvests with their images before the archive.
+///
+/// # Errors
+///
+/// Return database error
+async fn archive_harvests(
     finished_at: OffsetDateTime,
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-) -> ServerResult<u64> {
-    let max_age = harvest_max_age(finished_at)?;
+) -> ServerResult<Vec<StaleHarvest>> {
+    let listed_before = finished_at
+        .checked_sub(Duration::days(HARVEST_MAX_LISTING_DAYS))
+        .ok_or_else(|| ServerError::new("Failed to calculate harvest max listing age"))?;
+
     match sqlx::query!(
         r#"
+            WITH stale AS (
+                SELECT harvest.id,
+                    harvest.images,
+                    harvest.created_at,
+                    cultivar.name AS cultivar_name,
+                    farm.name AS farm_name,
+                    farmer.id AS farmer_id,
+                    farmer.first_name AS farmer_first_name,
+                    farmer.language AS farmer_language,
+                    email.email AS farmer_email
+                FROM services.harvests harvest
+                LEFT JOIN services.cultivars cultivar
+                    ON harvest.cultivar_id = cultivar.id
+                LEFT JOIN services.locations location_
+                    ON harvest.location_id = location_.id
+                LEFT JOIN services.farms farm
+                    ON location_.farm_id = farm.id
+                LEFT JOIN accounts.users farmer
+                    ON farm.owner_id = farmer.id
+                LEFT JOIN accounts.emails email
+                    ON farmer.id = email.user_id
+
+                WHERE NOT harvest.finished
+                    AND harvest.harvest_date <= $1
+                    AND harvest.created_at <= $2
+                FOR UPDATE OF harvest
+            )
             UPDATE services.harvests harvest
             SET finished = true,
                 images = NULL,
                 finished_at = $1
+            FROM stale
+            WHERE harvest.id = stale.id
+            RETURNING harvest.id,
+                stale.images,
+                stale.created_at,
+                stale.cultivar_name AS "cultivar_name?",
+                stale.farm_name AS "farm_name?",
+                stale.farmer_id AS "farmer_id?",
+                stale.farmer_first_name AS "farmer_first_name?",
+                stale.farmer_email AS "farmer_email?",
+                stale.farmer_language AS "farmer_language?"
+        "#,
+        finished_at.date(),
+        listed_before,
+    )
+    .fetch_all(&mut **tx)
+    .await
+    {
+        Ok(records) => {
+            tracing::debug!("Stale harvests archived: {}", records.len());
+            let harvests = records
+                .into_iter()
+                .map(|rec| StaleHarvest {
+                    id: rec.id.into(),
+                    images: rec.images,
+                    farmer_id: rec.farmer_id.map(Into::into),
+                    farmer_first_name: rec.farmer_first_name,
+                    farmer_email: rec.farmer_email,
+                    farmer_language: rec
+                        .farmer_language
+                        .as_deref()
+                        .map(Language::from_row)
+                        .unwrap_or_default(),
+                    harvest: ArchivedHarvest {
+                        name: rec.cultivar_name.unwrap_or_default(),
+                        farm_name: rec.farm_name.unwrap_or_default(),
+                        listed_on: rec.created_at.date().to_string(),
+                    },
+                })
+                .collect();
+            Ok(harvests)
+        }
+        Err(err) => {
+            tracing::error!("Database error, failed to archive stale harvests: {}", err);
+            Err(err.into())
+        }
+    }
+}
 
-            WHERE harvest.location_id IN (
-                SELECT location_.id
-                FROM services.active_locations location_
-                WHERE location_.farm_id = $2
-            )
-            AND NOT(
-                harvest.available_at > $1 OR
-                harvest.created_at > $3
+/// Records the stale harvests archive run
+///
+/// # Errors
+///
+/// Return database error
+async fn record_archive_run(
+    archived_count: usize,
+    farmers_notified: usize,
+    started_at: OffsetDateTime,
+    finished_at: OffsetDateTime,
+    db: DatabaseConnection,
+) -> ServerResult<()> {
+    match sqlx::query!(
+        r#"
+            INSERT INTO maintenance.harvest_archive_runs(
+                id,
+                archived_count,
+                farmers_notified,
+                started_at,
+                finished_at
             )
+            VALUES($1, $2, $3, $4, $5);
         "#,
-        finished_at.date(),
-        farm_id.0,
-        max_age,
+        ModelID::new().0,
+        i32::try_from(archived_count).unwrap_or(i32::MAX),
+        i32::try_from(farmers_notified).unwrap_or(i32::MAX),
+        started_at,
+        finished_at,
     )
-    .execute(&mut **tx)
+    .execute(&db.pool)
     .await
     {
         Ok(result) => {
-            tracing::trace!(
-                "Farm active harvests archived, but transaction not committed: {:?}",
-                result
-            );
-            Ok(result.rows_affected())
+            tracing::debug!("Stale harvests archive run recorded: {:?}", result);
+            Ok(())
         }
         Err(err) => {
-            tracing::error!("Database error, failed to archive farm harvests");
+            tracing::error!(
+                "Database error, failed to record stale harvests archive run: {}",
+                err
+            );
             Err(err.into())
         }
     }
@@ -0,0 +1,109 @@
+//! Saved search digest task impls
+
+use std::collections::BTreeMap;
+
+use time::OffsetDateTime;
+
+use crate::{
+    error::ServerResult,
+    features::saved_search::models::SavedSearchAlert,
+    mail::{Mail, SearchDigest, SearchDigestHarvest},
+    queue::Task,
+    server::state::DatabaseConnection,
+    services::produce::harvest::models::HarvestIndex,
+    types::ModelID,
+    SERVER_DOMAIN_NAME,
+};
+
+/// Maximum number of harvests listed per saved search in a digest email
+const DIGEST_MAX_HARVESTS: usize = 10;
+
+/// Emails users a digest of harvests listed since their saved searches were
+/// last checked, one email per user.
+///
+/// Searches are only marked as checked once the digest is enqueued,
+/// failed digests are retried on the next run.
+/// Return the number of digest emails enqueued.
+///
+/// # Errors
+///
+/// Return database error if failed to fetch saved searches
+pub async fn send_saved_search_digests(db: DatabaseConnection, mail: Mail) -> ServerResult<u64> {
+    let checked_at = OffsetDateTime::now_utc();
+    let alerts = SavedSearchAlert::records(db.clone()).await?;
+
+    let mut users: BTreeMap<ModelID, Vec<SavedSearchAlert>> = BTreeMap::new();
+    for alert in alerts {
+        users.entry(alert.user_id).or_default().push(alert);
+    }
+
+    let domain = SERVER_DOMAIN_NAME.get().unwrap();
+    let mut sent_count = 0;
+    for searches in users.values() {
+        let mut digest = Vec::new();
+        for search in searches {
+            match search
+                .new_harvests(DIGEST_MAX_HARVESTS, checked_at, db.clone())
+                .await
+            {
+                Ok(harvests) if !harvests.is_empty() => {
+                    digest.push(search_digest(search, harvests, domain));
+                }
+                Ok(_) => {}
+                Err(err) => tracing::error!("Failed to evaluate saved search: {}", err),
+            }
+        }
+
+        let user = &searches[0];
+        if !digest.is_empty() {
+            let link = format!("{domain}/account/users/me/saved-searches");
+            let email = mail.saved_search_digest(
+                &user.first_name,
+                &user.email,
+                user.language,
+                &digest,
+                &link,
+            );
+            let queued = match email.and_then(Task::send_email) {
+                Ok(task) => task.enqueue(db.clone()).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = queued {
+                tracing::error!("Failed to enqueue saved search digest email: {}", err);
+                continue;
+            }
+            sent_count += 1;
+        }
+
+        let ids: Vec<_> = searches.iter().map(|search| search.id).collect();
+        if let Err(err) = SavedSearchAlert::checked(&ids, checked_at, db.clone()).await {
+            tracing::error!("Failed to mark saved searches checked: {}", err);
+        }
+    }
+    Ok(sent_count)
+}
+
+/// Creates the digest email section of the saved search
+fn search_digest(
+    search: &SavedSearchAlert,
+    harvests: Vec<HarvestIndex>,
+    domain: &str,
+) -> SearchDigest {
+    SearchDigest {
+        name: search.name.clone(),
+        unsubscribe_link: format!(
+            "{domain}/account/saved-searches/unsubscribe?token={}",
+            search.unsubscribe_token
+        ),
+        harvests: harvests
+            .into_iter()
+            .map(|harvest| SearchDigestHarvest {
+                link: format!("{domain}/harvests/{}", harvest.id),
+                price: harvest.price.to_string(),
+                name: harvest.name,
+                farm_name: harvest.farm_name,
+                place_name: harvest.place_name,
+            })
+            .collect(),
+    }
+}
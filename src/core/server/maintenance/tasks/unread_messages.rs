@@ -0,0 +1,125 @@
+//! Unread direct messages email task impls
+
+use std::collections::BTreeMap;
+
+use time::OffsetDateTime;
+
+use crate::{
+    error::ServerResult,
+    features::message_notification::models::UnreadMessage,
+    mail::{Mail, MessagePreview, UnreadSender},
+    queue::Task,
+    server::state::DatabaseConnection,
+    types::ModelID,
+    SERVER_DOMAIN_NAME, UNREAD_MESSAGE_EMAIL_DELAY, UNREAD_MESSAGE_EMAIL_MAX_AGE,
+    UNREAD_MESSAGE_MAX_PREVIEWS, UNREAD_MESSAGE_PREVIEW_LEN,
+};
+
+/// Emails users about direct messages they have not read
+/// `UNREAD_MESSAGE_EMAIL_DELAY` after the messages were sent, one email per user.
+///
+/// Users inside their quiet hours are emailed on the first run after
+/// the quiet hours end, messages are only emailed about once.
+/// Return the number of emails enqueued.
+///
+/// # Errors
+///
+/// Return database error if failed to fetch unread messages
+pub async fn send_unread_message_emails(db: DatabaseConnection, mail: Mail) -> ServerResult<u64> {
+    let now = OffsetDateTime::now_utc();
+    let messages = UnreadMessage::records(
+        now - UNREAD_MESSAGE_EMAIL_MAX_AGE,
+        now - UNREAD_MESSAGE_EMAIL_DELAY,
+        db.clone(),
+    )
+    .await?;
+
+    let mut receivers: BTreeMap<ModelID, Vec<UnreadMessage>> = BTreeMap::new();
+    for message in messages {
+        receivers
+            .entry(message.receiver_id)
+            .or_default()
+            .push(message);
+    }
+
+    let domain = SERVER_DOMAIN_NAME.get().unwrap();
+    let link = format!("{domain}/messages");
+    let mut sent_count = 0;
+    for messages in receivers.values() {
+        let receiver = &messages[0];
+        if receiver
+            .quiet_hours
+            .is_some_and(|quiet_hours| quiet_hours.contains(now))
+        {
+            continue;
+        }
+
+        let email = mail.unread_messages(
+            &receiver.receiver_first_name,
+            &receiver.receiver_email,
+            receiver.receiver_language,
+            &unread_senders(messages),
+            &link,
+        );
+        let queued = match email.and_then(Task::send_email) {
+            Ok(task) => task.enqueue(db.clone()).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = queued {
+            tracing::error!("Failed to enqueue unread messages email: {}", err);
+            continue;
+        }
+        sent_count += 1;
+
+        let ids: Vec<_> = messages.iter().map(|message| message.id).collect();
+        if let Err(err) = UnreadMessage::emailed(&ids, now, db.clone()).await {
+            tracing::error!("Failed to mark unread messages emailed: {}", err);
+        }
+    }
+    Ok(sent_count)
+}
+
+/// Groups the receiver unread messages by sender,
+/// senders are listed in the order of their first unread message.
+fn unread_senders(messages: &[UnreadMessage]) -> Vec<UnreadSender> {
+    let mut senders: Vec<(ModelID, UnreadSender)> = Vec::new();
+    for message in messages {
+        if !senders.iter().any(|(id, _)| *id == message.sender_id) {
+            senders.push((
+                message.sender_id,
+                UnreadSender {
+                    name: message.sender_name.clone(),
+                    count: 0,
+                    messages: Vec::new(),
+                },
+            ));
+        }
+        let Some((_, sender)) = senders.iter_mut().find(|(id, _)| *id == message.sender_id) else {
+            continue;
+        };
+        sender.count += 1;
+        if sender.messages.len() < UNREAD_MESSAGE_MAX_PREVIEWS {
+            sender.messages.push(MessagePreview {
+                content: preview(&message.content),
+                sent_at: format!(
+                    "{} {:02}:{:02} UTC",
+                    message.sent_at.date(),
+                    message.sent_at.hour(),
+                    message.sent_at.minute()
+                ),
+            });
+        }
+    }
+    senders.into_iter().map(|(_, sender)| sender).collect()
+}
+
+/// Truncates the message content to `UNREAD_MESSAGE_PREVIEW_LEN` characters
+fn preview(content: &str) -> String {
+    let content = content.trim();
+    if content.chars().count() <= UNREAD_MESSAGE_PREVIEW_LEN {
+        return content.to_owned();
+    }
+    let mut preview: String = content.chars().take(UNREAD_MESSAGE_PREVIEW_LEN).collect();
+    preview.push('…');
+    preview
+}
@@ -19,13 +19,15 @@ use crate::{
     accounts::user::models::create_unsecure_superuser,
     auth::{api_key::ApiToken, ApiAuthentication},
     endpoint::{EndpointRejection, EndpointResult},
+    mail::missing_translations,
+    queue,
     types::ModelID,
     CONCURRENCY_LIMIT, ONE_SECOND, REQUEST_PER_SEC, SENSITIVE_HEADERS, TIMEOUT_SECS,
 };
 
 use cli::{Commands, ConfigCli};
 use config::Config;
-use maintenance::server_maintenance;
+use maintenance::{delete_orphaned_files, server_maintenance};
 use routers::server_routers;
 use state::{DatabaseConnection, ServerState};
 
@@ -41,10 +43,53 @@ pub mod state;
 ///
 /// Panics if failed to start a server
 pub async fn run() {
+    let cli = ConfigCli::parse();
+
+    // Report missing translations without starting the server,
+    // only the template files are read so no config or database is needed
+    if matches!(cli.command, Some(Commands::CheckEmailTemplates)) {
+        check_email_templates();
+        return;
+    }
+
     let config = Config::from_env();
     let addr = config.local_addr;
     let state = ServerState::from_config(config).await;
 
+    // RUN MIGRATIONS
+    let db = state.database();
+    run_migration(db.clone()).await;
+
+    // Run cli subcommands
+    // Create superuser if values given.
+    match cli.command {
+        Some(Commands::WithSuperuser { email, password }) => {
+            let id = create_unsecure_superuser(email, password, db.clone()).await;
+            let (token, key) = ApiToken::new_for_user(id);
+            let _ = token.insert(db.clone()).await.unwrap();
+            println!("API_KEY: {key}");
+        }
+        // Run the cleanup once without starting the server
+        Some(Commands::DeleteOrphanedFiles { dry_run }) => {
+            let orphaned = delete_orphaned_files(dry_run, db).await.unwrap();
+            for path in &orphaned.paths {
+                println!("{}", path.display());
+            }
+            let action = if dry_run {
+                "would be deleted"
+            } else {
+                "deleted"
+            };
+            println!(
+                "{} files scanned, {} orphaned files {action}.",
+                orphaned.scanned,
+                orphaned.paths.len()
+            );
+            return;
+        }
+        Some(Commands::CheckEmailTemplates) | None => {}
+    }
+
     let app = server_routers()
         .layer(
             ServiceBuilder::new()
@@ -70,18 +115,8 @@ pub async fn run() {
         )
         .with_state(state.clone());
 
-    // RUN MIGRATIONS
-    let db = state.database();
-    run_migration(db.clone()).await;
-
-    // Create superuser if values given.
-    let cli = ConfigCli::parse();
-    if let Some(Commands::WithSuperuser { email, password }) = cli.command {
-        let id = create_unsecure_superuser(email, password, db.clone()).await;
-        let (token, key) = ApiToken::new_for_user(id);
-        let _ = token.insert(db.clone()).await.unwrap();
-        println!("API_KEY: {key}");
-    }
+    // RUN BACKGROUND JOB QUEUE WORKERS
+    queue::start_workers(&state);
 
     // RUN MAINTENANCE TASK
     tokio::spawn(server_maintenance(state));
@@ -94,6 +129,23 @@ pub async fn run() {
         .unwrap();
 }
 
+/// Prints the email templates missing a translation,
+/// exits with an error status if any is missing.
+fn check_email_templates() {
+    let missing = missing_translations().unwrap();
+    for (language, file) in &missing {
+        println!("{}: {file}", language.name());
+    }
+    if missing.is_empty() {
+        println!("All email templates are translated.");
+        return;
+    }
+    println!("{} email translations missing.", missing.len());
+    // Fail CI checks on missing translations
+    #[allow(clippy::exit)]
+    std::process::exit(1);
+}
+
 // =====
 
 // RUN MIGRATIONS
@@ -19,12 +19,23 @@
 //! [::]/api/v1/account/users/:user_id/profile                                         GET
 //! [::]/api/v1/account/users/profile                                                  GET, PUT
 //! [::]/api/v1/account/users/profile/photo                                            POST, DELETE
+//! [::]/api/v1/account/users/me/blocked-users                                         GET, POST
+//! [::]/api/v1/account/users/me/blocked-users/:user_id                                DELETE
+//! [::]/api/v1/account/users/chat?after=..                                             GET (websocket)
+//! [::]/api/v1/account/users/chat/events?after=..                                      GET (event stream)
+//! [::]/api/v1/account/users/chat/messages                                             POST
+//! [::]/api/v1/account/users/conversations                                            GET
+//! [::]/api/v1/account/users/conversations/:other_user_id/messages?before=..&limit=..  GET
+//! [::]/api/v1/account/users/conversations/:other_user_id/report                      POST
+//! [::]/api/v1/account/users/conversations/attachments                                POST
+//! [::]/api/v1/account/users/conversations/attachments/:file_name                     GET
 //!
 //! [::]/api/v1/account/settings/personal-info                                         GET, PUT,
 //! [::]/api/v1/account/settings/change-email                                          POST
 //! [::]/api/v1/account/settings/verify-email                                          POST
 //! [::]/api/v1/account/settings/change-password                                       POST
 //! [::]/api/v1/account/settings/verify-password                                       POST
+//! [::]/api/v1/account/settings/message-notifications                                 GET, PUT
 //!
 //! [::]/api/v1/cultivars                                                               GET, POST
 //! [::]/api/v1/cultivars/:cultivar_id                                                  GET, PUT, DELETE
@@ -50,6 +61,15 @@
 //! [::]/api/v1/locations/countries/:country_id/regions                                 GET, POST
 //! [::]/api/v1/locations/countries/regions/region_id                                   PUT DELETE
 //!
+//! [::]/api/v1/maintenance/jobs                                                        GET                              Yes                       Superuser
+//! [::]/api/v1/maintenance/jobs/:job/runs                                              GET                              Yes                       Superuser
+//! [::]/api/v1/maintenance/jobs/:job/run                                               POST                             Yes                       Superuser
+//!
+//! [::]/api/v1/admin/emails                                                            GET                              Yes                       Staff
+//! [::]/api/v1/admin/emails/:template/preview?format=html|txt                          GET                              Yes                       Staff
+//! [::]/api/v1/admin/conversation-reports?status=open|dismissed|actioned              GET                              Yes                       Staff
+//! [::]/api/v1/admin/conversation-reports/:report_id                                   GET, PUT                         Yes                       Staff
+//!
 //!
 //! --------------------------------------------------------------
 //!
@@ -103,6 +123,8 @@ use crate::{
 use super::state::ServerState;
 
 mod accounts;
+mod admin;
+mod maintenance;
 mod services;
 
 #[allow(clippy::declare_interior_mutable_const)]
@@ -128,6 +150,8 @@ fn api_v1_router() -> Router<ServerState> {
     Router::new()
         .merge(services::routers())
         .merge(accounts::routers())
+        .merge(maintenance::routers())
+        .merge(admin::routers())
         .merge(pictures_router())
 }
 
@@ -2,12 +2,13 @@
 
 use axum::{
     extract::DefaultBodyLimit,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 
 use crate::{
     accounts::{
+        blocked_users::handlers::{blocked_user_add, blocked_user_list, blocked_user_remove},
         emails::handlers::{
             email_change_approve, email_exists, email_update, new_email_change_verify,
         },
@@ -26,13 +27,27 @@ use crate::{
     },
     auth::sessions::handlers::{login, logout},
     features::{
-        direct_message::handlers::{direct_message_websocket, user_conversations},
+        conversation_report::handlers::conversation_report,
+        direct_message::handlers::{
+            conversation_list, conversation_messages, direct_message_events, direct_message_send,
+            direct_message_websocket, message_attachment_image, message_attachment_uploads,
+            user_conversations,
+        },
         harvest_subscription::handlers::user_harvest_subscriptions,
+        harvest_wishlist::handlers::{wishlist_add, wishlist_list, wishlist_remove},
+        message_notification::handlers::{
+            message_notification_settings, message_notification_settings_update,
+        },
+        saved_search::handlers::{
+            saved_search_create, saved_search_delete, saved_search_list, saved_search_unsubscribe,
+            saved_search_unsubscribe_confirm, saved_search_update,
+        },
     },
     server::state::ServerState,
 };
 
 /// Accounts routers
+#[allow(clippy::too_many_lines)]
 pub fn routers() -> Router<ServerState> {
     Router::new()
         // Accounts
@@ -57,12 +72,64 @@ pub fn routers() -> Router<ServerState> {
             "/account/users/profile/photo",
             post(user_photo_upload).layer(DefaultBodyLimit::max(crate::IMAGE_MAX_SIZE)),
         )
+        // Wishlist
+        .route(
+            "/account/users/me/wishlist",
+            get(wishlist_list).post(wishlist_add),
+        )
+        .route(
+            "/account/users/me/wishlist/:harvest_id",
+            delete(wishlist_remove),
+        )
+        // Blocked users
+        .route(
+            "/account/users/me/blocked-users",
+            get(blocked_user_list).post(blocked_user_add),
+        )
+        .route(
+            "/account/users/me/blocked-users/:user_id",
+            delete(blocked_user_remove),
+        )
+        // Saved searches
+        .route(
+            "/account/users/me/saved-searches",
+            get(saved_search_list).post(saved_search_create),
+        )
+        .route(
+            "/account/users/me/saved-searches/:search_id",
+            put(saved_search_update).delete(saved_search_delete),
+        )
+        .route(
+            "/account/saved-searches/unsubscribe",
+            get(saved_search_unsubscribe_confirm).post(saved_search_unsubscribe),
+        )
         // DirectMessage
         .route("/account/users/chat", get(direct_message_websocket))
+        .route("/account/users/chat/events", get(direct_message_events))
+        .route("/account/users/chat/messages", post(direct_message_send))
         .route(
             "/account/users/chat/direct_message",
             get(user_conversations),
         )
+        .route("/account/users/conversations", get(conversation_list))
+        .route(
+            "/account/users/conversations/:other_user_id/messages",
+            get(conversation_messages),
+        )
+        .route(
+            "/account/users/conversations/:other_user_id/report",
+            post(conversation_report),
+        )
+        .route(
+            "/account/users/conversations/attachments",
+            post(message_attachment_uploads).layer(DefaultBodyLimit::max(
+                crate::IMAGE_MAX_SIZE * crate::MESSAGE_MAX_ATTACHMENTS as usize,
+            )),
+        )
+        .route(
+            "/account/users/conversations/attachments/:file_name",
+            get(message_attachment_image),
+        )
         // Settings
         .route(
             "/account/settings/personal-info",
@@ -79,6 +146,10 @@ pub fn routers() -> Router<ServerState> {
         )
         .route("/account/settings/change-password", post(password_change))
         .route("/account/settings/verify-password", post(password_verify))
+        .route(
+            "/account/settings/message-notifications",
+            get(message_notification_settings).put(message_notification_settings_update),
+        )
         // .route("/account/settings/phones", put(phone_update))
         .route("/account/settings/add-superuser", post(user_make_superuser))
         .route(
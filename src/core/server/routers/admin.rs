@@ -0,0 +1,32 @@
+//! Admin routers impls
+
+use axum::{routing::get, Router};
+
+use crate::{
+    features::conversation_report::handlers::{
+        conversation_report_detail, conversation_report_list, conversation_report_review,
+    },
+    mail::handlers::{
+        email_mailbox, email_mailbox_clear, email_template_list, email_template_preview,
+    },
+    server::state::ServerState,
+};
+
+/// Admin routers
+pub fn routers() -> Router<ServerState> {
+    Router::new()
+        .route("/admin/emails", get(email_template_list))
+        .route(
+            "/admin/emails/mailbox",
+            get(email_mailbox).delete(email_mailbox_clear),
+        )
+        .route(
+            "/admin/emails/:template/preview",
+            get(email_template_preview),
+        )
+        .route("/admin/conversation-reports", get(conversation_report_list))
+        .route(
+            "/admin/conversation-reports/:report_id",
+            get(conversation_report_detail).put(conversation_report_review),
+        )
+}
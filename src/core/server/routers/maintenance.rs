@@ -0,0 +1,19 @@
+//! Maintenance routers impls
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::server::{
+    maintenance::handlers::{job_list, job_runs, job_trigger},
+    state::ServerState,
+};
+
+/// Maintenance routers
+pub fn routers() -> Router<ServerState> {
+    Router::new()
+        .route("/maintenance/jobs", get(job_list))
+        .route("/maintenance/jobs/:job/runs", get(job_runs))
+        .route("/maintenance/jobs/:job/run", post(job_trigger))
+}
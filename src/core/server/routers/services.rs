@@ -40,17 +40,26 @@ use crate::{
         },
         produce::harvest::handlers::{
             harvest_create, harvest_delete, harvest_detail, harvest_image_delete,
-            harvest_image_uploads, harvest_list, harvest_update,
+            harvest_image_uploads, harvest_list, harvest_stock, harvest_stock_adjust,
+            harvest_update,
         },
         produce::harvest_feed,
+        produce::order::handlers::{
+            order_counter, order_counter_accept, order_create, order_detail, order_list,
+            order_status_update,
+        },
+        search::handlers::search,
     },
 };
 
 /// Services routers
+#[allow(clippy::too_many_lines)]
 pub fn routers() -> Router<ServerState> {
     Router::new()
         //Produce
         .route("/produce", get(harvest_feed))
+        // Search
+        .route("/search", get(search))
         // Cultivar
         .route("/cultivars", get(cultivar_list).post(cultivar_create))
         .route(
@@ -90,6 +99,19 @@ pub fn routers() -> Router<ServerState> {
                 ))
                 .delete(harvest_image_delete),
         )
+        .route(
+            "/harvests/:harvest_id/stock",
+            get(harvest_stock).post(harvest_stock_adjust),
+        )
+        // Orders
+        .route("/orders", get(order_list).post(order_create))
+        .route("/orders/:order_id", get(order_detail))
+        .route("/orders/:order_id/status", put(order_status_update))
+        .route("/orders/:order_id/counter", post(order_counter))
+        .route(
+            "/orders/:order_id/counter/accept",
+            post(order_counter_accept),
+        )
         .route(
             "/harvests/subscription",
             get(harvest_subscription_list).post(harvest_subscription_create),
@@ -6,9 +6,12 @@ use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
 use sqlx::{postgres::PgPoolOptions, PgPool};
 
-use crate::{features::direct_message::ChatFeed, mail::Mail};
+use crate::{
+    features::direct_message::ChatFeed,
+    mail::{Mail, MailTransport},
+};
 
-use super::config::Config;
+use super::config::{ChatFeedConfig, Config, MailTransportConfig};
 
 /// Server's state
 #[derive(Clone)]
@@ -17,7 +20,7 @@ pub struct ServerState(Arc<StateInner>);
 #[derive(Clone)]
 struct StateInner {
     database: DatabaseConnection,
-    outlook_client: Mail,
+    mail_client: Mail,
     chat: ChatFeed,
     cookie_key: Key,
 }
@@ -25,10 +28,15 @@ struct StateInner {
 impl ServerState {
     /// Creates new `ServerState`.
     pub async fn from_config(config: Config) -> Self {
+        let database = DatabaseConnection::new(&config.database_url).await;
+        let chat = match config.chat_feed {
+            ChatFeedConfig::Local => ChatFeed::local(database.clone()),
+            ChatFeedConfig::Postgres => ChatFeed::postgres(database.clone()),
+        };
         Self(Arc::new(StateInner {
-            database: DatabaseConnection::new(&config.database_url).await,
-            outlook_client: Mail::outlook(&config.mail_email, config.mail_password),
-            chat: ChatFeed::new(),
+            database,
+            mail_client: Mail::new(mail_transport(config.mail_transport), &config.mail_from),
+            chat,
             cookie_key: config.cookie_key,
         }))
     }
@@ -43,8 +51,8 @@ impl ServerState {
     /// Clone and returns mail client
     #[must_use]
     #[inline]
-    pub fn outlook_client(&self) -> Mail {
-        self.0.outlook_client.clone()
+    pub fn mail_client(&self) -> Mail {
+        self.0.mail_client.clone()
     }
 
     /// Clone and returns chat feed instance
@@ -69,6 +77,21 @@ impl ServerState {
     }
 }
 
+/// Creates the email transport from the config
+///
+/// # Panics
+///
+/// Panics if the SMTP host is invalid
+fn mail_transport(config: MailTransportConfig) -> MailTransport {
+    match config {
+        MailTransportConfig::Smtp(smtp) => {
+            MailTransport::smtp(&smtp).unwrap_or_else(|err| panic!("{err}"))
+        }
+        MailTransportConfig::File(dir) => MailTransport::file(dir),
+        MailTransportConfig::Memory => MailTransport::memory(),
+    }
+}
+
 impl fmt::Debug for ServerState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ServerState{..}").finish()
@@ -89,7 +112,7 @@ impl FromRef<ServerState> for ChatFeed {
 
 impl FromRef<ServerState> for Mail {
     fn from_ref(state: &ServerState) -> Self {
-        state.outlook_client()
+        state.mail_client()
     }
 }
 
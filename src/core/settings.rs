@@ -33,3 +33,14 @@ pub const CULTIVAR_UPLOAD_DIR: &str =
 /// Harvests image file uploads directory
 pub const HARVEST_UPLOAD_DIR: &str =
     concat!(env!("CARGO_MANIFEST_DIR"), "/static/media/uploads/harvest");
+
+/// Direct message image attachments uploads directory
+pub const MESSAGE_UPLOAD_DIR: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/static/media/uploads/message");
+
+/// Emails written by the file mail transport directory
+pub const MAIL_FILE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/mailbox");
+
+/// Email templates and their translations directory
+pub const EMAIL_TEMPLATES_DIR: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/static/templates/emails");
@@ -0,0 +1,139 @@
+//! Geo search impls
+
+use geo::{Coord, Point, Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::endpoint::{EndpointRejection, EndpointResult};
+
+/// Geo search query parameters.
+///
+/// `near=lat,lng&radius_km=25` limits results to a radius around a point,
+/// `bbox=west,south,east,north` limits results to a bounding box.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GeoQuery {
+    /// The point distances are measured from, `lat,lng`
+    #[serde(default)]
+    pub near: Option<String>,
+    /// Maximum distance in kilometres from the `near` point
+    #[serde(default)]
+    pub radius_km: Option<f64>,
+    /// Bounding box, `west,south,east,north`
+    #[serde(default)]
+    pub bbox: Option<String>,
+    /// Result ordering, `sort=distance` orders results nearest first
+    #[serde(default)]
+    pub sort: Option<String>,
+}
+
+impl GeoQuery {
+    /// Validates and parses the query parameters into a `GeoFilter`
+    pub fn filter(&self) -> EndpointResult<GeoFilter> {
+        let near = self.near.as_deref().map(parse_near).transpose()?;
+        let bbox = self.bbox.as_deref().map(parse_bbox).transpose()?;
+
+        if let Some(radius_km) = self.radius_km {
+            if near.is_none() {
+                return Err(EndpointRejection::BadRequest(
+                    "radius_km requires a near=lat,lng point".into(),
+                ));
+            }
+            if !radius_km.is_finite() || radius_km <= 0.0 {
+                return Err(EndpointRejection::BadRequest(
+                    "radius_km must be a positive number".into(),
+                ));
+            }
+        }
+
+        // Other sort options are endpoint specific, they are not handled here.
+        let sort_by_distance = self.sort.as_deref() == Some("distance");
+        if sort_by_distance && near.is_none() {
+            return Err(EndpointRejection::BadRequest(
+                "Sorting by distance requires a near=lat,lng point".into(),
+            ));
+        }
+
+        Ok(GeoFilter {
+            near,
+            radius_km: self.radius_km,
+            bbox,
+            sort_by_distance,
+        })
+    }
+}
+
+/// Validated geo search filters
+#[derive(Debug, Clone, Default)]
+pub struct GeoFilter {
+    pub near: Option<Point>,
+    pub radius_km: Option<f64>,
+    pub bbox: Option<Rect>,
+    pub sort_by_distance: bool,
+}
+
+impl GeoFilter {
+    /// Latitude of the `near` point
+    #[must_use]
+    pub fn near_lat(&self) -> Option<f64> {
+        self.near.map(Point::y)
+    }
+
+    /// Longitude of the `near` point
+    #[must_use]
+    pub fn near_lng(&self) -> Option<f64> {
+        self.near.map(Point::x)
+    }
+
+    /// Bounding box edges, `(west, south, east, north)`
+    #[must_use]
+    pub fn bbox_edges(&self) -> (Option<f64>, Option<f64>, Option<f64>, Option<f64>) {
+        self.bbox.map_or((None, None, None, None), |rect| {
+            let (min, max) = (rect.min(), rect.max());
+            (Some(min.x), Some(min.y), Some(max.x), Some(max.y))
+        })
+    }
+}
+
+/// Validates coordinates are a valid latitude and longitude
+pub fn validate_coords(point: &Point) -> EndpointResult<()> {
+    if (-90.0..=90.0).contains(&point.y()) && (-180.0..=180.0).contains(&point.x()) {
+        Ok(())
+    } else {
+        tracing::error!("Validation coords error: {:?} out of range", point);
+        Err(EndpointRejection::BadRequest(
+            "Coordinates out of range, latitude must be within -90 and 90 and longitude within -180 and 180".into(),
+        ))
+    }
+}
+
+/// Parses `lat,lng` into a `Point`
+fn parse_near(near: &str) -> EndpointResult<Point> {
+    let err = || EndpointRejection::BadRequest("Invalid near, expected: near=lat,lng".into());
+    let [lat, lng] = parse_numbers::<2>(near).ok_or_else(err)?;
+    let point = Point::new(lng, lat);
+    validate_coords(&point)?;
+    Ok(point)
+}
+
+/// Parses `west,south,east,north` into a `Rect`
+fn parse_bbox(bbox: &str) -> EndpointResult<Rect> {
+    let err = || {
+        EndpointRejection::BadRequest("Invalid bbox, expected: bbox=west,south,east,north".into())
+    };
+    let [west, south, east, north] = parse_numbers::<4>(bbox).ok_or_else(err)?;
+    let (min, max) = (Point::new(west, south), Point::new(east, north));
+    validate_coords(&min)?;
+    validate_coords(&max)?;
+    if west > east || south > north {
+        return Err(err());
+    }
+    Ok(Rect::new(Coord::from(min), Coord::from(max)))
+}
+
+/// Parses exactly `N` comma separated numbers
+fn parse_numbers<const N: usize>(value: &str) -> Option<[f64; N]> {
+    let numbers: Vec<f64> = value
+        .split(',')
+        .map(|n| n.trim().parse::<f64>().ok().filter(|n| n.is_finite()))
+        .collect::<Option<_>>()?;
+    numbers.try_into().ok()
+}
@@ -0,0 +1,80 @@
+//! Language impls
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A language users receive emails in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Language {
+    /// English, the fallback of every other language
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "af")]
+    Afrikaans,
+    /// Oshiwambo (Ndonga)
+    #[serde(rename = "ng")]
+    Oshiwambo,
+    #[serde(rename = "de")]
+    German,
+}
+
+impl Language {
+    /// All supported languages
+    pub const ALL: [Self; 4] = [
+        Self::English,
+        Self::Afrikaans,
+        Self::Oshiwambo,
+        Self::German,
+    ];
+
+    /// ISO-639-1 language code
+    #[must_use]
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Afrikaans => "af",
+            Self::Oshiwambo => "ng",
+            Self::German => "de",
+        }
+    }
+
+    /// Language name
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Afrikaans => "Afrikaans",
+            Self::Oshiwambo => "Oshiwambo",
+            Self::German => "German",
+        }
+    }
+
+    /// Returns true if the language is English
+    #[must_use]
+    pub const fn is_english(self) -> bool {
+        matches!(self, Self::English)
+    }
+
+    /// Creates a new `Language` from the database column,
+    /// unknown codes default to `English`
+    #[must_use]
+    pub fn from_row(code: &str) -> Self {
+        Self::try_from(code).unwrap_or_default()
+    }
+}
+
+impl TryFrom<&str> for Language {
+    type Error = String;
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::String(code.to_ascii_lowercase()))
+            .map_err(|_| format!("Unsupported language `{code}`"))
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
@@ -1,8 +1,11 @@
+pub mod geo;
 mod identifier;
+mod language;
 mod model_id;
 mod pagination;
 pub mod price;
 
 pub use identifier::{ModelIdentifier, ModelIndex};
+pub use language::Language;
 pub use model_id::ModelID;
 pub use pagination::Pagination;
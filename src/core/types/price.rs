@@ -8,13 +8,22 @@ use serde::{Deserialize, Serialize};
 pub struct Price {
     pub amount: Decimal,
     pub unit: Unit,
+    /// Harvest forms fill a missing currency with the currency
+    /// of the harvest location's country
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    /// Farmer declared weight in kg of a single crate, bundle, sack, tray or punnet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight_kg: Option<Decimal>,
 }
 
 impl Default for Price {
     fn default() -> Self {
         Self {
             amount: 0.into(),
-            unit: Unit::Kg(0),
+            unit: Unit::Kg(0.into()),
+            currency: None,
+            weight_kg: None,
         }
     }
 }
@@ -22,8 +31,13 @@ impl Default for Price {
 impl Price {
     /// Creates a new price
     #[must_use]
-    pub const fn new(amount: Decimal, unit: Unit) -> Self {
-        Self { amount, unit }
+    pub const fn new(amount: Decimal, unit: Unit, currency: Currency) -> Self {
+        Self {
+            amount,
+            unit,
+            currency: Some(currency),
+            weight_kg: None,
+        }
     }
 
     /// Creates a new `Price` from the database column
@@ -31,32 +45,287 @@ impl Price {
     pub fn from_row(value: serde_json::Value) -> Self {
         serde_json::from_value(value).unwrap_or_default()
     }
+
+    /// Price normalised per kg,
+    /// `None` if the weight of the unit is not known
+    #[must_use]
+    pub fn price_per_kg(&self) -> Option<Decimal> {
+        let weight = self.unit.weight_kg(self.weight_kg)?;
+        self.amount.checked_div(weight)
+    }
+
+    /// Price normalised per litre,
+    /// `None` if the unit is not a volume
+    #[must_use]
+    pub fn price_per_litre(&self) -> Option<Decimal> {
+        let volume = self.unit.volume_litres()?;
+        self.amount.checked_div(volume)
+    }
 }
 
 impl fmt::Display for Price {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let currency = self.currency.unwrap_or_default();
+        let symbol = currency.symbol();
+        let dp = currency.decimal_places() as usize;
         let amount = self.amount;
         let unit = self.unit.clone();
-        write!(f, "N${amount} {unit}")
+        write!(f, "{symbol}{amount:.dp$} {unit}")?;
+        if let (Some(weight), true) = (self.weight_kg, self.unit.is_container()) {
+            write!(f, " ({}kg)", weight.normalize())?;
+        }
+        Ok(())
     }
 }
 
 /// A unit of the `Price`
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum Unit {
-    Kg(u8),
+    /// Quantity in kilograms
+    Kg(Decimal),
+    /// Quantity in metric tonnes
+    Tonne(Decimal),
+    /// Quantity in litres
+    Litre(Decimal),
     Crate,
     Bundle,
+    Sack,
+    Tray,
+    Punnet,
+    Dozen,
     Head,
 }
 
+impl Unit {
+    /// Returns true if the unit is a container
+    /// with a farmer declared weight
+    #[must_use]
+    pub const fn is_container(&self) -> bool {
+        matches!(
+            self,
+            Self::Crate | Self::Bundle | Self::Sack | Self::Tray | Self::Punnet
+        )
+    }
+
+    /// Weight in kg of the unit quantity,
+    /// containers weigh `declared_weight_kg`
+    #[must_use]
+    pub fn weight_kg(&self, declared_weight_kg: Option<Decimal>) -> Option<Decimal> {
+        match self {
+            Self::Kg(n) => Some(*n),
+            Self::Tonne(n) => n.checked_mul(Decimal::ONE_THOUSAND),
+            _ if self.is_container() => declared_weight_kg,
+            _ => None,
+        }
+    }
+
+    /// Volume in litres of the unit quantity
+    #[must_use]
+    pub const fn volume_litres(&self) -> Option<Decimal> {
+        match self {
+            Self::Litre(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Unit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Crate => write!(f, "crate"),
             Self::Bundle => write!(f, "bundle"),
+            Self::Sack => write!(f, "sack"),
+            Self::Tray => write!(f, "tray"),
+            Self::Punnet => write!(f, "punnet"),
+            Self::Dozen => write!(f, "dozen"),
             Self::Head => write!(f, "head"),
-            Self::Kg(n) => write!(f, "{n}kg"),
+            Self::Kg(n) => write!(f, "{}kg", n.normalize()),
+            Self::Tonne(n) => write!(f, "{}t", n.normalize()),
+            Self::Litre(n) => write!(f, "{}L", n.normalize()),
+        }
+    }
+}
+
+/// A unit harvest stock quantities are counted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuantityUnit {
+    Kg,
+    Tonne,
+    Litre,
+    Crate,
+    Bundle,
+    Sack,
+    Tray,
+    Punnet,
+    Dozen,
+    Head,
+}
+
+impl QuantityUnit {
+    /// Returns the unit name
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Kg => "kg",
+            Self::Tonne => "tonne",
+            Self::Litre => "litre",
+            Self::Crate => "crate",
+            Self::Bundle => "bundle",
+            Self::Sack => "sack",
+            Self::Tray => "tray",
+            Self::Punnet => "punnet",
+            Self::Dozen => "dozen",
+            Self::Head => "head",
         }
     }
+
+    /// Creates a new `QuantityUnit` from the database column
+    #[must_use]
+    pub fn from_row(unit: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(unit.to_owned())).ok()
+    }
+}
+
+impl From<&Unit> for QuantityUnit {
+    fn from(unit: &Unit) -> Self {
+        match unit {
+            Unit::Kg(_) => Self::Kg,
+            Unit::Tonne(_) => Self::Tonne,
+            Unit::Litre(_) => Self::Litre,
+            Unit::Crate => Self::Crate,
+            Unit::Bundle => Self::Bundle,
+            Unit::Sack => Self::Sack,
+            Unit::Tray => Self::Tray,
+            Unit::Punnet => Self::Punnet,
+            Unit::Dozen => Self::Dozen,
+            Unit::Head => Self::Head,
+        }
+    }
+}
+
+impl fmt::Display for QuantityUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// ISO-4217 currency of the `Price`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Currency {
+    /// Namibian dollar
+    #[default]
+    NAD,
+    /// South African rand
+    ZAR,
+    /// Botswana pula
+    BWP,
+    /// Zambian kwacha
+    ZMW,
+    /// Angolan kwanza
+    AOA,
+    /// Mozambican metical
+    MZN,
+    /// Lesotho loti
+    LSL,
+    /// Swazi lilangeni
+    SZL,
+    /// Malawian kwacha
+    MWK,
+    /// Kenyan shilling
+    KES,
+    /// Ugandan shilling
+    UGX,
+    /// Rwandan franc
+    RWF,
+    /// Nigerian naira
+    NGN,
+    /// Ghanaian cedi
+    GHS,
+    /// United States dollar
+    USD,
+    /// Euro
+    EUR,
+    /// Pound sterling
+    GBP,
+}
+
+impl Currency {
+    /// ISO-4217 alphabetic code
+    #[must_use]
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::NAD => "NAD",
+            Self::ZAR => "ZAR",
+            Self::BWP => "BWP",
+            Self::ZMW => "ZMW",
+            Self::AOA => "AOA",
+            Self::MZN => "MZN",
+            Self::LSL => "LSL",
+            Self::SZL => "SZL",
+            Self::MWK => "MWK",
+            Self::KES => "KES",
+            Self::UGX => "UGX",
+            Self::RWF => "RWF",
+            Self::NGN => "NGN",
+            Self::GHS => "GHS",
+            Self::USD => "USD",
+            Self::EUR => "EUR",
+            Self::GBP => "GBP",
+        }
+    }
+
+    /// Symbol amounts are formatted with
+    #[must_use]
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::NAD => "N$",
+            Self::ZAR => "R",
+            Self::BWP => "P",
+            Self::ZMW => "K",
+            Self::AOA => "Kz",
+            Self::MZN => "MT",
+            Self::LSL => "L",
+            Self::SZL => "E",
+            Self::MWK => "MK",
+            Self::KES => "KSh",
+            Self::UGX => "USh",
+            Self::RWF => "FRw",
+            Self::NGN => "₦",
+            Self::GHS => "GH₵",
+            Self::USD => "$",
+            Self::EUR => "€",
+            Self::GBP => "£",
+        }
+    }
+
+    /// Number of decimal places of the currency minor unit
+    #[must_use]
+    pub const fn decimal_places(self) -> u32 {
+        match self {
+            Self::UGX | Self::RWF => 0,
+            _ => 2,
+        }
+    }
+
+    /// Creates a new `Currency` from the database column
+    #[must_use]
+    pub fn from_row(code: &str) -> Self {
+        // Currency codes are checked by the database constraint
+        Self::try_from(code).unwrap_or_default()
+    }
+}
+
+impl TryFrom<&str> for Currency {
+    type Error = String;
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::String(code.to_ascii_uppercase()))
+            .map_err(|_| format!("Unsupported currency `{code}`"))
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
 }
@@ -0,0 +1,332 @@
+//! Conversation report database impl
+
+use crate::{
+    accounts::user::models::UserIndex,
+    error::ServerResult,
+    features::direct_message::{attach_message_attachments, models::DirectMessage},
+    server::state::DatabaseConnection,
+    types::{ModelID, Pagination},
+    CONVERSATION_REPORT_MAX_MESSAGES,
+};
+
+use super::{
+    forms::{ConversationReportInsertData, ReportFilter, ReportReviewUpdateData},
+    models::{ConversationReport, ConversationReportIndex, ConversationReportList, ReportStatus},
+};
+
+impl ConversationReportIndex {
+    /// Fetches conversation reports from the database,
+    /// the oldest reports are returned first.
+    #[tracing::instrument(name = "Fetch Conversation Reports", skip(db))]
+    pub async fn records(
+        filter: ReportFilter,
+        pg: Pagination,
+        db: DatabaseConnection,
+    ) -> ServerResult<ConversationReportList> {
+        let (offset, limit) = pg.offset_limit();
+        match sqlx::query!(
+            r#"
+                SELECT report.id AS report_id,
+                    report.reason AS report_reason,
+                    report.status AS report_status,
+                    report.reviewed_by AS report_reviewed_by,
+                    report.reviewed_at AS report_reviewed_at,
+                    report.created_at AS report_created_at,
+                    reporter.id AS reporter_id,
+                    reporter.first_name AS reporter_first_name,
+                    reporter.last_name AS reporter_last_name,
+                    reporter_profile.photo AS reporter_photo,
+                    reported.id AS reported_id,
+                    reported.first_name AS reported_first_name,
+                    reported.last_name AS reported_last_name,
+                    reported_profile.photo AS reported_photo
+                FROM features.conversation_reports report
+                INNER JOIN accounts.users reporter
+                    ON report.reporter_id = reporter.id
+                LEFT JOIN accounts.user_profiles reporter_profile
+                    ON reporter.id = reporter_profile.user_id
+                INNER JOIN accounts.users reported
+                    ON report.reported_user_id = reported.id
+                LEFT JOIN accounts.user_profiles reported_profile
+                    ON reported.id = reported_profile.user_id
+
+                WHERE ($1::text IS NULL OR report.status = $1)
+                ORDER BY report.created_at
+                LIMIT $2
+                OFFSET $3;
+            "#,
+            filter.status.map(ReportStatus::as_str),
+            limit,
+            offset,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let reports = records
+                    .into_iter()
+                    .map(|rec| {
+                        Self::from_row(
+                            rec.report_id.into(),
+                            UserIndex::from_row(
+                                rec.reporter_id.into(),
+                                rec.reporter_first_name,
+                                rec.reporter_last_name,
+                                rec.reporter_photo,
+                            ),
+                            UserIndex::from_row(
+                                rec.reported_id.into(),
+                                rec.reported_first_name,
+                                rec.reported_last_name,
+                                rec.reported_photo,
+                            ),
+                            rec.report_reason,
+                            &rec.report_status,
+                            rec.report_reviewed_by.map(Into::into),
+                            rec.report_reviewed_at,
+                            rec.report_created_at,
+                        )
+                    })
+                    .collect();
+
+                Ok(reports)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to fetch conversation reports: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
+
+impl ConversationReport {
+    /// Fetches conversation report from the database
+    #[tracing::instrument(name = "Fetch Conversation Report", skip(db))]
+    pub async fn find(report_id: ModelID, db: DatabaseConnection) -> ServerResult<Option<Self>> {
+        match sqlx::query!(
+            r#"
+                SELECT report.id AS report_id,
+                    report.reason AS report_reason,
+                    report.messages AS report_messages,
+                    report.status AS report_status,
+                    report.reviewed_by AS report_reviewed_by,
+                    report.reviewed_at AS report_reviewed_at,
+                    report.created_at AS report_created_at,
+                    reporter.id AS reporter_id,
+                    reporter.first_name AS reporter_first_name,
+                    reporter.last_name AS reporter_last_name,
+                    reporter_profile.photo AS reporter_photo,
+                    reported.id AS reported_id,
+                    reported.first_name AS reported_first_name,
+                    reported.last_name AS reported_last_name,
+                    reported_profile.photo AS reported_photo
+                FROM features.conversation_reports report
+                INNER JOIN accounts.users reporter
+                    ON report.reporter_id = reporter.id
+                LEFT JOIN accounts.user_profiles reporter_profile
+                    ON reporter.id = reporter_profile.user_id
+                INNER JOIN accounts.users reported
+                    ON report.reported_user_id = reported.id
+                LEFT JOIN accounts.user_profiles reported_profile
+                    ON reported.id = reported_profile.user_id
+
+                WHERE report.id = $1;
+            "#,
+            report_id.0,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.map(|rec| {
+                let report = ConversationReportIndex::from_row(
+                    rec.report_id.into(),
+                    UserIndex::from_row(
+                        rec.reporter_id.into(),
+                        rec.reporter_first_name,
+                        rec.reporter_last_name,
+                        rec.reporter_photo,
+                    ),
+                    UserIndex::from_row(
+                        rec.reported_id.into(),
+                        rec.reported_first_name,
+                        rec.reported_last_name,
+                        rec.reported_photo,
+                    ),
+                    rec.report_reason,
+                    &rec.report_status,
+                    rec.report_reviewed_by.map(Into::into),
+                    rec.report_reviewed_at,
+                    rec.report_created_at,
+                );
+                Self::from_row(report, rec.report_messages)
+            })),
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to fetch conversation report: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Inserts conversation report with a snapshot of the most recent
+    /// conversation messages into the database, messages deleted by
+    /// either user are included.
+    ///
+    /// Returns `None` if the users have no messages.
+    #[tracing::instrument(name = "Insert Conversation Report", skip(db, report))]
+    pub async fn insert(
+        report: ConversationReportInsertData,
+        db: DatabaseConnection,
+    ) -> ServerResult<Option<ModelID>> {
+        let messages =
+            conversation_snapshot(report.reporter_id, report.reported_user_id, db.clone()).await?;
+        if messages.is_empty() {
+            return Ok(None);
+        }
+        let messages = serde_json::to_value(messages)?;
+
+        match sqlx::query!(
+            r#"
+                INSERT INTO features.conversation_reports(
+                    id,
+                    reporter_id,
+                    reported_user_id,
+                    reason,
+                    messages,
+                    created_at
+                )
+                VALUES($1, $2, $3, $4, $5, $6);
+            "#,
+            report.id.0,
+            report.reporter_id.0,
+            report.reported_user_id.0,
+            report.reason,
+            messages,
+            report.created_at,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Conversation report inserted successfully: {:?}", result);
+                Ok(Some(report.id))
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to insert conversation report: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Updates conversation report status in the database,
+    /// returns false if the report does not exist.
+    #[tracing::instrument(name = "Update Conversation Report Status", skip(db))]
+    pub async fn review(
+        report_id: ModelID,
+        review: ReportReviewUpdateData,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                UPDATE features.conversation_reports report
+                SET status = $2,
+                    reviewed_by = $3,
+                    reviewed_at = $4
+                WHERE report.id = $1;
+            "#,
+            report_id.0,
+            review.status.as_str(),
+            review.reviewed_by.0,
+            review.reviewed_at,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Conversation report reviewed: {:?}", result);
+                Ok(result.rows_affected() > 0)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to update conversation report: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Fetches the most recent messages sent between the users
+/// from the database, oldest message first.
+async fn conversation_snapshot(
+    reporter_id: ModelID,
+    other_id: ModelID,
+    db: DatabaseConnection,
+) -> ServerResult<Vec<DirectMessage>> {
+    match sqlx::query!(
+        r#"
+            SELECT message.id AS message_id,
+                message.sender_id,
+                message.receiver_id,
+                message.content AS message_content,
+                message.order_id AS message_order_id,
+                message.sent_at AS message_sent_at,
+                status.is_read,
+                status.read_at,
+                status.delivered_at
+            FROM features.direct_messages message
+            INNER JOIN features.message_status status
+                ON message.id = status.message_id
+
+            WHERE (message.sender_id = $1 AND message.receiver_id = $2)
+                OR (message.sender_id = $2 AND message.receiver_id = $1)
+
+            ORDER BY message.sent_at DESC, message.id DESC
+            LIMIT $3;
+        "#,
+        reporter_id.0,
+        other_id.0,
+        CONVERSATION_REPORT_MAX_MESSAGES,
+    )
+    .fetch_all(&db.pool)
+    .await
+    {
+        Ok(records) => {
+            let mut messages: Vec<_> = records
+                .into_iter()
+                .rev()
+                .map(|rec| {
+                    let sender_id: ModelID = rec.sender_id.into();
+                    DirectMessage::from_row(
+                        rec.message_id.into(),
+                        sender_id,
+                        rec.receiver_id.into(),
+                        rec.message_content,
+                        rec.message_order_id.map(Into::into),
+                        rec.message_sent_at,
+                        sender_id == reporter_id,
+                        rec.is_read,
+                        rec.read_at,
+                        rec.delivered_at,
+                    )
+                })
+                .collect();
+            attach_message_attachments(&mut messages, db).await?;
+
+            Ok(messages)
+        }
+        Err(err) => {
+            tracing::error!("Database error, failed to fetch reported messages: {}", err);
+            Err(err.into())
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Conversation report forms impls
+
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Json, Request},
+};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    auth::{AdminUser, CurrentUser},
+    endpoint::{
+        validators::{TransformString, ValidateString},
+        EndpointRejection, EndpointResult,
+    },
+    server::state::ServerState,
+    types::ModelID,
+};
+
+use super::models::ReportStatus;
+
+// ===== Report form impls =====
+
+/// Conversation report form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationReportForm {
+    pub reason: String,
+}
+
+/// Conversation report form cleaned data
+#[derive(Debug, Clone)]
+pub struct ConversationReportInsertData {
+    pub id: ModelID,
+    pub reporter_id: ModelID,
+    pub reported_user_id: ModelID,
+    pub reason: String,
+    pub created_at: OffsetDateTime,
+}
+
+impl ConversationReportForm {
+    /// Converts `Self` into `ConversationReportInsertData`
+    #[must_use]
+    pub fn data(
+        self,
+        reporter_id: ModelID,
+        reported_user_id: ModelID,
+    ) -> ConversationReportInsertData {
+        ConversationReportInsertData {
+            id: ModelID::new(),
+            reporter_id,
+            reported_user_id,
+            reason: self.reason,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Validates conversation report form inputs
+    fn validate(&mut self) -> EndpointResult<()> {
+        // Clean the data
+        self.reason = self.reason.clean();
+
+        self.reason
+            .validate_len(1, 1024, "Reason must be between 1 and 1024 characters")?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for ConversationReportForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let user = { CurrentUser::from_parts(&mut parts, state).await? };
+        let other_user_id = { ModelID::from_request_parts(&mut parts, state).await? };
+        let Json(mut report) =
+            Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        // Validate form fields
+        report.validate()?;
+
+        if other_user_id == user.id {
+            return Err(EndpointRejection::BadRequest(
+                "You can not report yourself.".into(),
+            ));
+        }
+
+        Ok(report)
+    }
+}
+
+// ===== Review form impls =====
+
+/// Conversation report review form, sent by staff
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportReviewForm {
+    pub status: ReportStatus,
+}
+
+/// Conversation report review form cleaned data
+#[derive(Debug, Clone)]
+pub struct ReportReviewUpdateData {
+    pub status: ReportStatus,
+    pub reviewed_by: ModelID,
+    pub reviewed_at: OffsetDateTime,
+}
+
+impl ReportReviewForm {
+    /// Converts `Self` into `ReportReviewUpdateData`
+    #[must_use]
+    pub fn data(self, reviewed_by: ModelID) -> ReportReviewUpdateData {
+        ReportReviewUpdateData {
+            status: self.status,
+            reviewed_by,
+            reviewed_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Validates review form inputs
+    fn validate(&self) -> EndpointResult<()> {
+        if self.status == ReportStatus::Open {
+            return Err(EndpointRejection::BadRequest(
+                "A report cannot be moved back to open.".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for ReportReviewForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let _ = { AdminUser::from_parts(&mut parts, state).await? };
+        let Json(review) =
+            Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        // Validate form fields
+        review.validate()?;
+
+        Ok(review)
+    }
+}
+
+// ===== Filter impls =====
+
+/// `admin/conversation-reports` query parameters.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportFilter {
+    /// filters for report status
+    #[serde(default)]
+    pub status: Option<ReportStatus>,
+}
@@ -0,0 +1,83 @@
+//! Conversation report http handlers impls
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    auth::{AdminUser, CurrentUser},
+    endpoint::{EndpointRejection, EndpointResult},
+    server::state::DatabaseConnection,
+    types::{ModelID, Pagination},
+};
+
+use super::{
+    forms::{ConversationReportForm, ReportFilter, ReportReviewForm},
+    models::{ConversationReport, ConversationReportIndex, ConversationReportList},
+};
+
+/// Handles the `POST /account/users/conversations/:other_user_id/report` route.
+///
+/// Reports the conversation to staff, the most recent messages
+/// are saved with the report.
+#[tracing::instrument(skip(db, user, form))]
+pub async fn conversation_report(
+    user: CurrentUser,
+    Path(other_user_id): Path<ModelID>,
+    State(db): State<DatabaseConnection>,
+    form: ConversationReportForm,
+) -> EndpointResult<(StatusCode, Json<ModelID>)> {
+    ConversationReport::insert(form.data(user.id, other_user_id), db)
+        .await?
+        .map_or_else(
+            || {
+                Err(EndpointRejection::NotFound(
+                    "Conversation not found.".into(),
+                ))
+            },
+            |report_id| Ok((StatusCode::CREATED, Json(report_id))),
+        )
+}
+
+/// Handles the `GET /admin/conversation-reports` route.
+#[tracing::instrument(skip(db))]
+pub async fn conversation_report_list(
+    _: AdminUser,
+    filter: Option<Query<ReportFilter>>,
+    pg: Option<Query<Pagination>>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<ConversationReportList>> {
+    let filter = filter.unwrap_or_default().0;
+    let pagination = pg.unwrap_or_default().0;
+    let reports = ConversationReportIndex::records(filter, pagination, db).await?;
+    Ok(Json(reports))
+}
+
+/// Handles the `GET /admin/conversation-reports/:report_id` route.
+#[tracing::instrument(skip(db))]
+pub async fn conversation_report_detail(
+    _: AdminUser,
+    report_id: ModelID,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<ConversationReport>> {
+    ConversationReport::find(report_id, db).await?.map_or_else(
+        || Err(EndpointRejection::NotFound("Report not found.".into())),
+        |report| Ok(Json(report)),
+    )
+}
+
+/// Handles the `PUT /admin/conversation-reports/:report_id` route.
+#[tracing::instrument(skip(db, user, form))]
+pub async fn conversation_report_review(
+    user: AdminUser,
+    report_id: ModelID,
+    State(db): State<DatabaseConnection>,
+    form: ReportReviewForm,
+) -> EndpointResult<StatusCode> {
+    if ConversationReport::review(report_id, form.data(user.id()), db).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(EndpointRejection::NotFound("Report not found.".into()))
+    }
+}
@@ -0,0 +1,6 @@
+//! Conversation report impls
+
+pub mod db;
+pub mod forms;
+pub mod handlers;
+pub mod models;
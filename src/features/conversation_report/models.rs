@@ -0,0 +1,110 @@
+//! Conversation report models impls
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{accounts::user::models::UserIndex, types::ModelID};
+
+/// A `Vec` of conversation reports
+pub type ConversationReportList = Vec<ConversationReportIndex>;
+
+/// Conversation report status
+///
+/// A report is `open` until staff review it and mark it as
+/// `dismissed` or `actioned`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    Open,
+    Dismissed,
+    Actioned,
+}
+
+impl ReportStatus {
+    /// Returns the status as stored in the database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Dismissed => "dismissed",
+            Self::Actioned => "actioned",
+        }
+    }
+
+    /// Creates a new `ReportStatus` from the database column
+    #[must_use]
+    pub fn from_row(status: &str) -> Self {
+        match status {
+            "open" => Self::Open,
+            "dismissed" => Self::Dismissed,
+            // Statuses are checked by the database constraint
+            _ => Self::Actioned,
+        }
+    }
+}
+
+/// A conversation reported by a user
+///
+/// Returned by `conversation_report_list` handler.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationReportIndex {
+    pub id: ModelID,
+    pub reporter: UserIndex,
+    pub reported_user: UserIndex,
+    pub reason: String,
+    pub status: ReportStatus,
+    /// The staff user that reviewed the report
+    pub reviewed_by: Option<ModelID>,
+    pub reviewed_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+impl ConversationReportIndex {
+    /// Creates a new `ConversationReportIndex` from the database row
+    #[allow(clippy::too_many_arguments)]
+    #[must_use]
+    pub fn from_row(
+        id: ModelID,
+        reporter: UserIndex,
+        reported_user: UserIndex,
+        reason: String,
+        status: &str,
+        reviewed_by: Option<ModelID>,
+        reviewed_at: Option<OffsetDateTime>,
+        created_at: OffsetDateTime,
+    ) -> Self {
+        Self {
+            id,
+            reporter,
+            reported_user,
+            reason,
+            status: ReportStatus::from_row(status),
+            reviewed_by,
+            reviewed_at,
+            created_at,
+        }
+    }
+}
+
+/// A conversation report with the messages of the conversation
+/// when it was reported.
+///
+/// Returned by `conversation_report_detail` handler.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationReport {
+    #[serde(flatten)]
+    pub report: ConversationReportIndex,
+    /// The reported conversation direct messages, oldest message first,
+    /// `isAuthor` is set on the messages sent by the reporter.
+    pub messages: serde_json::Value,
+}
+
+impl ConversationReport {
+    /// Creates a new `ConversationReport` from the database row
+    #[must_use]
+    pub const fn from_row(report: ConversationReportIndex, messages: serde_json::Value) -> Self {
+        Self { report, messages }
+    }
+}
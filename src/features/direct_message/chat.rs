@@ -1,111 +1,528 @@
 //! Chat system impls
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use tokio::sync::broadcast;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use tokio::sync::mpsc;
 
 use crate::{
-    error::{ServerError, ServerResult},
-    types::ModelID,
+    error::ServerResult, server::state::DatabaseConnection, types::ModelID, CHAT_FEED_CHANNEL,
+    CHAT_FEED_MAX_PAYLOAD, CHAT_INSTANCE_HEARTBEAT, CHAT_INSTANCE_TIMEOUT,
+    CHAT_REPLAY_MAX_MESSAGES,
 };
 
 use super::{
-    forms::{ForwardMessage, IncomingMessageError, MessageIsRead},
-    models::DirectMessage,
+    forms::{ForwardMessage, IncomingMessageError, MessageIsDelivered, MessageIsRead},
+    models::{ChatInstance, Conversation, DirectMessage},
+    registry::{ChatRegistry, MessageListener},
+    utils::TypingThrottle,
 };
 
-/// Broadcasts `BroadcastMessage` with subscribed users.
+/// Delivers `BroadcastMessage`s to the connections of the users they are sent to.
+///
+/// Messages are delivered to the connections of this server instance,
+/// the Postgres backend shares the messages and users presence
+/// between all the server instances.
 #[derive(Debug, Clone)]
-pub struct ChatFeed(Arc<broadcast::Sender<BroadcastMessage>>);
+pub struct ChatFeed {
+    registry: ChatRegistry,
+    /// Sends messages to be delivered to this server instance connections
+    dispatcher: mpsc::UnboundedSender<BroadcastMessage>,
+    backend: ChatBackend,
+    typing: TypingThrottle,
+}
 
-impl Default for ChatFeed {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Chat feed pub/sub backend
+#[derive(Debug, Clone)]
+enum ChatBackend {
+    /// Messages and presence are kept in this server instance
+    Local(LocalPresence),
+    /// Messages are published to all server instances with `LISTEN/NOTIFY`,
+    /// presence and direct messages are kept in the database.
+    Postgres {
+        instance: ChatInstance,
+        publisher: mpsc::UnboundedSender<BroadcastMessage>,
+        db: DatabaseConnection,
+    },
 }
 
 impl ChatFeed {
-    /// Creates a new `ChatFeed`
+    /// Creates a new `ChatFeed` delivering messages in this server instance
+    ///
+    /// Spawns the task delivering messages, must be called within a tokio runtime.
     #[must_use]
-    pub fn new() -> Self {
-        let (sender, _recv) = broadcast::channel(1024);
-        Self(Arc::new(sender))
+    pub fn local(db: DatabaseConnection) -> Self {
+        let registry = ChatRegistry::default();
+        let (dispatcher, messages) = mpsc::unbounded_channel();
+        tokio::spawn(dispatch_messages(messages, registry.clone(), db));
+
+        Self {
+            registry,
+            dispatcher,
+            backend: ChatBackend::Local(LocalPresence::default()),
+            typing: TypingThrottle::default(),
+        }
+    }
+
+    /// Creates a new `ChatFeed` sharing messages between server instances
+    /// with Postgres `LISTEN/NOTIFY`.
+    ///
+    /// Spawns the tasks delivering, publishing, listening and sending heartbeats,
+    /// must be called within a tokio runtime.
+    #[must_use]
+    pub fn postgres(db: DatabaseConnection) -> Self {
+        let registry = ChatRegistry::default();
+        let (dispatcher, messages) = mpsc::unbounded_channel();
+        tokio::spawn(dispatch_messages(messages, registry.clone(), db.clone()));
+
+        let (publisher, messages) = mpsc::unbounded_channel();
+        let instance = ChatInstance::new();
+        tokio::spawn(publish_messages(messages, dispatcher.clone(), db.clone()));
+        tokio::spawn(listen_messages(
+            dispatcher.clone(),
+            registry.clone(),
+            db.clone(),
+        ));
+        tokio::spawn(send_heartbeats(
+            instance,
+            registry.clone(),
+            publisher.clone(),
+            db.clone(),
+        ));
+
+        Self {
+            registry,
+            dispatcher,
+            backend: ChatBackend::Postgres {
+                instance,
+                publisher,
+                db,
+            },
+            typing: TypingThrottle::default(),
+        }
     }
 
-    /// Sends a `BroadcastMessage` to all subscribed message listeners.
+    /// Sends a `BroadcastMessage` to the connections of the users it is sent to.
     pub fn broadcast(&self, msg: BroadcastMessage) {
-        let _ = self.0.send(msg);
+        let msg = match &self.backend {
+            ChatBackend::Local(_) => msg,
+            ChatBackend::Postgres { publisher, .. } => match publisher.send(msg) {
+                Ok(()) => return,
+                Err(mpsc::error::SendError(msg)) => {
+                    tracing::error!("Chat publisher stopped, message delivered locally");
+                    msg
+                }
+            },
+        };
+        let _ = self.dispatcher.send(msg);
+    }
+
+    /// Returns the typing throttle shared by the users connections
+    #[must_use]
+    pub const fn typing(&self) -> &TypingThrottle {
+        &self.typing
     }
 
-    /// Returns an new `MessageListener`
+    /// Registers a new connection of the user,
+    /// return the listener receiving messages sent to the user.
     #[must_use]
-    pub fn subscribe(&self) -> MessageListener {
-        MessageListener(self.0.subscribe())
+    pub fn subscribe(&self, user_id: ModelID) -> MessageListener {
+        self.registry.connect(user_id)
+    }
+
+    /// Registers a new connection of the user resuming after the `after` message,
+    /// the messages the user received since are replayed before the live messages.
+    pub async fn resume(
+        &self,
+        user_id: ModelID,
+        after: Option<ModelID>,
+        db: DatabaseConnection,
+    ) -> MessageListener {
+        // Subscribe before fetching so no message is missed in between
+        let mut listener = self.subscribe(user_id);
+        if let Some(after) = after {
+            match DirectMessage::replay(user_id, after, CHAT_REPLAY_MAX_MESSAGES, db).await {
+                Ok(Some(messages)) => listener.replay(messages),
+                // The client refetches its conversations instead
+                Ok(None) | Err(_) => listener.resync(),
+            }
+        }
+        listener
+    }
+
+    /// Records a new chat connection of the user,
+    /// broadcasts `UserConnected` if the user was offline.
+    pub async fn user_connected(&self, user_id: ModelID) {
+        let came_online = match &self.backend {
+            ChatBackend::Local(presence) => Ok(presence.connect(user_id)),
+            ChatBackend::Postgres { instance, db, .. } => {
+                instance.connect(user_id, db.clone()).await
+            }
+        };
+        match came_online {
+            Ok(true) => self.broadcast(BroadcastMessage::user_connected(user_id)),
+            Ok(false) => {}
+            Err(err) => tracing::error!("Failed to record chat connection: {}", err),
+        }
+    }
+
+    /// Returns the users sharing a conversation with the user that are online
+    pub async fn online_contacts(
+        &self,
+        user_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<Vec<ModelID>> {
+        match &self.backend {
+            ChatBackend::Local(presence) => {
+                let online = presence.users();
+                if online.is_empty() {
+                    return Ok(Vec::new());
+                }
+                Conversation::participants(user_id, &online, db).await
+            }
+            ChatBackend::Postgres { db, .. } => {
+                ChatInstance::online_contacts(user_id, db.clone()).await
+            }
+        }
+    }
+
+    /// Removes a chat connection of the user,
+    /// broadcasts `UserDisconnected` if the user has no connections left.
+    ///
+    /// Typing stopped is broadcast to the receivers the user was typing to.
+    pub async fn user_disconnected(&self, user_id: ModelID) {
+        for receiver_id in self.typing.receivers(user_id) {
+            self.broadcast(BroadcastMessage::typing_stopped(receiver_id, user_id));
+        }
+        let went_offline = match &self.backend {
+            ChatBackend::Local(presence) => Ok(presence.disconnect(user_id)),
+            ChatBackend::Postgres { instance, db, .. } => {
+                instance.disconnect(user_id, db.clone()).await
+            }
+        };
+        match went_offline {
+            Ok(true) => self.broadcast(BroadcastMessage::user_disconnected(user_id)),
+            Ok(false) => {}
+            Err(err) => tracing::error!("Failed to remove chat connection: {}", err),
+        }
     }
 }
 
-// ===== MessageListener impls =====
+/// Number of chat connections of each user in this server instance
+#[derive(Debug, Clone, Default)]
+struct LocalPresence(Arc<Mutex<HashMap<ModelID, usize>>>);
 
-/// Listens for `BroadcastMessage` sent on the queue.
-#[derive(Debug)]
-pub struct MessageListener(broadcast::Receiver<BroadcastMessage>);
+impl LocalPresence {
+    /// Adds a user connection, return true if it is the user first connection
+    fn connect(&self, user_id: ModelID) -> bool {
+        let mut users = self.0.lock().unwrap();
+        let connections = users.entry(user_id).or_default();
+        *connections += 1;
+        let first = *connections == 1;
+        drop(users);
+        first
+    }
+
+    /// Returns the users connected to this server instance
+    fn users(&self) -> Vec<ModelID> {
+        self.0.lock().unwrap().keys().copied().collect()
+    }
 
-impl MessageListener {
-    /// Listens for `BroadcastMessage` sent on the queue.
-    pub async fn listen(&mut self) -> ServerResult<BroadcastMessage> {
-        self.0
-            .recv()
-            .await
-            .map_err(|err| ServerError::new(err.to_string()))
+    /// Removes a user connection, return true if it was the user last connection
+    fn disconnect(&self, user_id: ModelID) -> bool {
+        let mut users = self.0.lock().unwrap();
+        match users.get_mut(&user_id) {
+            Some(connections) if *connections > 1 => {
+                *connections -= 1;
+                false
+            }
+            Some(_) => {
+                users.remove(&user_id);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Delivers the messages to the connections of this server instance,
+/// presence messages are delivered to the users sharing a conversation with the user.
+async fn dispatch_messages(
+    mut messages: mpsc::UnboundedReceiver<BroadcastMessage>,
+    registry: ChatRegistry,
+    db: DatabaseConnection,
+) {
+    // Contacts are fetched in their own task so that
+    // presence messages don't hold up direct messages.
+    let (contacts, contact_messages) = mpsc::unbounded_channel();
+    tokio::spawn(dispatch_contact_messages(
+        contact_messages,
+        registry.clone(),
+        db,
+    ));
+
+    while let Some(msg) = messages.recv().await {
+        match msg.forward_to {
+            MessageForwardTo::UserId(user_id) => registry.send(user_id, &msg.message),
+            MessageForwardTo::Contacts(user_id) => {
+                let _ = contacts.send((user_id, msg.message));
+            }
+        }
+    }
+}
+
+/// Delivers the messages to the users sharing a conversation with the user
+/// that are connected to this server instance, in the order they were sent.
+async fn dispatch_contact_messages(
+    mut messages: mpsc::UnboundedReceiver<(ModelID, ForwardMessage)>,
+    registry: ChatRegistry,
+    db: DatabaseConnection,
+) {
+    while let Some((user_id, message)) = messages.recv().await {
+        let connected = registry.users();
+        if connected.is_empty() {
+            continue;
+        }
+        match Conversation::participants(user_id, &connected, db.clone()).await {
+            Ok(contacts) => {
+                for contact in contacts {
+                    registry.send(contact, &message);
+                }
+            }
+            Err(err) => tracing::error!("Failed to fetch chat contacts: {}", err),
+        }
+    }
+}
+
+// ===== Postgres backend impls =====
+
+/// Publishes the broadcast messages to all server instances in the order they were sent
+///
+/// Messages too large for a notification or that failed
+/// to be published are delivered in this instance only.
+async fn publish_messages(
+    mut messages: mpsc::UnboundedReceiver<BroadcastMessage>,
+    dispatcher: mpsc::UnboundedSender<BroadcastMessage>,
+    db: DatabaseConnection,
+) {
+    while let Some(msg) = messages.recv().await {
+        let payload = match serde_json::to_string(&PublishedMessage::from(msg.clone())) {
+            Ok(payload) if payload.len() <= CHAT_FEED_MAX_PAYLOAD => payload,
+            Ok(payload) => {
+                tracing::warn!(
+                    "Chat message of {} bytes too large to publish, delivered locally",
+                    payload.len()
+                );
+                let _ = dispatcher.send(msg);
+                continue;
+            }
+            Err(err) => {
+                tracing::error!("Chat message serialization error: {}", err);
+                continue;
+            }
+        };
+        if let Err(err) = ChatInstance::publish(CHAT_FEED_CHANNEL, &payload, db.clone()).await {
+            tracing::error!("Failed to publish chat message, delivered locally: {}", err);
+            let _ = dispatcher.send(msg);
+        }
+    }
+}
+
+/// Listens for broadcast messages published by all server instances,
+/// including this one, and delivers them to this instance connections.
+async fn listen_messages(
+    dispatcher: mpsc::UnboundedSender<BroadcastMessage>,
+    registry: ChatRegistry,
+    db: DatabaseConnection,
+) {
+    let retry = Duration::from_secs(1);
+    let mut listener = loop {
+        match PgListener::connect_with(&db.pool).await {
+            Ok(mut listener) => match listener.listen(CHAT_FEED_CHANNEL).await {
+                Ok(()) => break listener,
+                Err(err) => tracing::error!("Failed to listen on the chat feed: {}", err),
+            },
+            Err(err) => tracing::error!("Failed to connect the chat feed listener: {}", err),
+        }
+        tokio::time::sleep(retry).await;
+    };
+
+    loop {
+        // The listener reconnects on the next call after an error,
+        // messages published in the meantime are lost.
+        match listener.recv().await {
+            Ok(notification) => match serde_json::from_str(notification.payload()) {
+                Ok(PublishedMessage::Broadcast(msg)) => {
+                    let _ = dispatcher.send(msg);
+                }
+                Ok(PublishedMessage::DirectMessage {
+                    forward_to,
+                    message_id,
+                }) => {
+                    if let Some(msg) =
+                        find_direct_message(forward_to, message_id, &registry, db.clone()).await
+                    {
+                        let _ = dispatcher.send(msg);
+                    }
+                }
+                Err(err) => tracing::error!("Chat message deserialization error: {}", err),
+            },
+            Err(err) => {
+                tracing::error!("Chat feed listener error: {}", err);
+                tokio::time::sleep(retry).await;
+            }
+        }
+    }
+}
+
+/// Fetches the published direct message if its receiver
+/// is connected to this server instance.
+async fn find_direct_message(
+    forward_to: MessageForwardTo,
+    message_id: ModelID,
+    registry: &ChatRegistry,
+    db: DatabaseConnection,
+) -> Option<BroadcastMessage> {
+    if let MessageForwardTo::UserId(user_id) = forward_to {
+        if !registry.is_connected(user_id) {
+            return None;
+        }
+    }
+    match DirectMessage::find(message_id, db).await {
+        Ok(message) => message.map(|message| BroadcastMessage {
+            forward_to,
+            message: ForwardMessage::DirectMessage(message),
+        }),
+        Err(err) => {
+            tracing::error!("Failed to fetch published direct message: {}", err);
+            None
+        }
+    }
+}
+
+/// Keeps this server instance registered with the chat feed and removes
+/// instances that stopped sending heartbeats, users connected only
+/// to the removed instances are broadcast as disconnected.
+///
+/// If this instance was removed, its users presence is restored
+/// from the registry once it is registered again.
+async fn send_heartbeats(
+    instance: ChatInstance,
+    registry: ChatRegistry,
+    publisher: mpsc::UnboundedSender<BroadcastMessage>,
+    db: DatabaseConnection,
+) {
+    let mut interval = tokio::time::interval(CHAT_INSTANCE_HEARTBEAT.unsigned_abs());
+    loop {
+        interval.tick().await;
+        match instance.heartbeat(db.clone()).await {
+            Ok(true) => restore_presence(&instance, &registry, &publisher, db.clone()).await,
+            Ok(false) => {}
+            Err(err) => {
+                tracing::error!("Failed to send chat heartbeat: {}", err);
+                continue;
+            }
+        }
+        let stale_before = time::OffsetDateTime::now_utc() - CHAT_INSTANCE_TIMEOUT;
+        match ChatInstance::remove_stale(stale_before, db.clone()).await {
+            Ok(offline_users) => {
+                for user_id in offline_users {
+                    let _ = publisher.send(BroadcastMessage::user_disconnected(user_id));
+                }
+            }
+            Err(err) => tracing::error!("Failed to remove stale chat instances: {}", err),
+        }
+    }
+}
+
+/// Inserts the presence of the users connected to this server instance,
+/// users that were not connected to any other instance are broadcast as connected.
+async fn restore_presence(
+    instance: &ChatInstance,
+    registry: &ChatRegistry,
+    publisher: &mpsc::UnboundedSender<BroadcastMessage>,
+    db: DatabaseConnection,
+) {
+    let connections = registry.connections();
+    if connections.is_empty() {
+        return;
+    }
+    tracing::warn!(
+        "Chat instance registered again, restoring presence of {} users",
+        connections.len()
+    );
+    match instance.restore_presence(connections, db).await {
+        Ok(online_users) => {
+            for user_id in online_users {
+                let _ = publisher.send(BroadcastMessage::user_connected(user_id));
+            }
+        }
+        Err(err) => tracing::error!("Failed to restore chat presence: {}", err),
     }
 }
 
 // ===== BroadcastMessage impls =====
 
 /// Message send on message queue.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct BroadcastMessage {
     pub forward_to: MessageForwardTo,
     message: ForwardMessage,
 }
 
-/// Marks the message to whom it should be forwarded to.
-#[derive(Debug, Clone)]
-pub enum MessageForwardTo {
-    UserId(ModelID),
-    ForAll,
+/// Message published between server instances, direct messages are
+/// published by id as their content may not fit in a notification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum PublishedMessage {
+    Broadcast(BroadcastMessage),
+    #[serde(rename_all = "camelCase")]
+    DirectMessage {
+        forward_to: MessageForwardTo,
+        message_id: ModelID,
+    },
 }
 
-impl BroadcastMessage {
-    /// Returns message if it is intended for this user.
-    #[must_use]
-    pub fn message(self, user_id: ModelID) -> Option<ForwardMessage> {
-        if self.is_for_me(user_id) {
-            Some(self.message)
-        } else {
-            None
+impl From<BroadcastMessage> for PublishedMessage {
+    fn from(msg: BroadcastMessage) -> Self {
+        match msg.message {
+            ForwardMessage::DirectMessage(message) => Self::DirectMessage {
+                forward_to: msg.forward_to,
+                message_id: message.id,
+            },
+            message => Self::Broadcast(BroadcastMessage {
+                forward_to: msg.forward_to,
+                message,
+            }),
         }
     }
+}
 
-    /// Return whether the message is intended for this user.
-    #[must_use]
-    pub fn is_for_me(&self, user_id: ModelID) -> bool {
-        match self.forward_to {
-            MessageForwardTo::UserId(id) => id == user_id,
-            MessageForwardTo::ForAll => true,
-        }
-    }
+/// Marks the message to whom it should be forwarded to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MessageForwardTo {
+    UserId(ModelID),
+    /// Users sharing a conversation with this user
+    Contacts(ModelID),
+}
 
+impl BroadcastMessage {
     /// Creates `UserConnected` forward message.
     #[must_use]
     pub fn user_connected(user_id: ModelID) -> Self {
-        Self::for_all(ForwardMessage::UserConnected(user_id))
+        Self::for_contacts(user_id, ForwardMessage::UserConnected(user_id))
     }
 
     /// Creates `UserDisconnected` forward message.
     #[must_use]
     pub fn user_disconnected(user_id: ModelID) -> Self {
-        Self::for_all(ForwardMessage::UserDisconnected(user_id))
+        Self::for_contacts(user_id, ForwardMessage::UserDisconnected(user_id))
     }
 
     /// Creates `DirectMessage` forward message.
@@ -120,6 +537,30 @@ impl BroadcastMessage {
         Self::for_user(to, ForwardMessage::MessageIsRead(message))
     }
 
+    /// Creates `MessageIsDelivered` forward message.
+    #[must_use]
+    pub fn message_is_delivered(to: ModelID, message: MessageIsDelivered) -> Self {
+        Self::for_user(to, ForwardMessage::MessageIsDelivered(message))
+    }
+
+    /// Creates `TypingStarted` forward message.
+    #[must_use]
+    pub fn typing_started(to: ModelID, user_id: ModelID) -> Self {
+        Self::for_user(to, ForwardMessage::TypingStarted(user_id))
+    }
+
+    /// Creates `TypingStopped` forward message.
+    #[must_use]
+    pub fn typing_stopped(to: ModelID, user_id: ModelID) -> Self {
+        Self::for_user(to, ForwardMessage::TypingStopped(user_id))
+    }
+
+    /// Creates `Presence` forward message.
+    #[must_use]
+    pub fn presence(to: ModelID, online_contacts: Vec<ModelID>) -> Self {
+        Self::for_user(to, ForwardMessage::Presence(online_contacts))
+    }
+
     /// Creates `IncomingMessageError` forward message.
     #[must_use]
     pub fn message_error(to: ModelID, err: IncomingMessageError) -> Self {
@@ -135,11 +576,12 @@ impl BroadcastMessage {
         }
     }
 
-    /// Creates a `BroadcastMessage` that is sent to all connected users.
+    /// Creates a `BroadcastMessage` that is sent to the users
+    /// sharing a conversation with this user.
     #[must_use]
-    pub fn for_all(message: ForwardMessage) -> Self {
+    pub fn for_contacts(user_id: ModelID, message: ForwardMessage) -> Self {
         Self {
-            forward_to: MessageForwardTo::ForAll,
+            forward_to: MessageForwardTo::Contacts(user_id),
             message,
         }
     }
@@ -1,19 +1,30 @@
 //! Direct Message database impl
 
+use std::collections::HashMap;
+
 use itertools::Itertools;
-use time::OffsetDateTime;
+use time::{Duration, OffsetDateTime};
 
 use crate::{
+    accounts::user::models::UserIndex,
     endpoint::EndpointRejection,
     error::{ServerError, ServerResult},
     server::state::DatabaseConnection,
+    services::produce::harvest::models::HarvestIndex,
     types::ModelID,
+    MESSAGE_MAX_NEW_CONVERSATIONS,
 };
 
 use super::{
-    forms::NewMessageInsertData,
-    models::{Conversation, Conversations, DirectMessage},
-    utils::{find_message_status, insert_message_status, message_receiver_count},
+    forms::{MessageHistoryQuery, NewAttachment, NewMessageInsertData},
+    models::{
+        ChatInstance, Conversation, ConversationSummary, ConversationSummaryList, Conversations,
+        DirectMessage, MessageAttachment, MessageHistory,
+    },
+    utils::{
+        attach_message_attachments, delete_message_images, find_message_participants,
+        insert_message_attachments, insert_message_status, message_receiver_count,
+    },
 };
 
 impl Conversations {
@@ -26,8 +37,11 @@ impl Conversations {
                 message.sender_id AS "sender_id!",
                 message.receiver_id AS "receiver_id!", 
                 message.content AS "message_content!",
+                message.order_id AS message_order_id,
                 message.sent_at AS "message_sent_at!",
                 status.is_read AS is_read,
+                status.read_at AS read_at,
+                status.delivered_at AS delivered_at,
                 status.sender_has_deleted AS sender_has_deleted,
                 status.receiver_has_deleted AS receiver_has_deleted
             FROM features.direct_messages message
@@ -64,9 +78,12 @@ impl Conversations {
                                 sender_id,
                                 rec.receiver_id.into(),
                                 rec.message_content,
+                                rec.message_order_id.map(Into::into),
                                 rec.message_sent_at,
                                 sender_id == user_id,
                                 rec.is_read,
+                                rec.read_at,
+                                rec.delivered_at,
                             )
                         })
                         .collect();
@@ -77,6 +94,13 @@ impl Conversations {
                         messages,
                     ));
                 }
+                attach_message_attachments(
+                    conversations
+                        .iter_mut()
+                        .flat_map(|conv| conv.messages.iter_mut()),
+                    db,
+                )
+                .await?;
 
                 Ok(Self::from_row(conversations))
             }
@@ -107,8 +131,11 @@ impl Conversation {
                     message.sender_id AS "sender_id!",
                     message.receiver_id AS "receiver_id!", 
                     message.content AS "message_content!",
+                    message.order_id AS message_order_id,
                     message.sent_at AS "message_sent_at!",
                     status.is_read AS is_read,
+                    status.read_at AS read_at,
+                    status.delivered_at AS delivered_at,
                     status.sender_has_deleted AS sender_has_deleted,
                     status.receiver_has_deleted AS receiver_has_deleted
                 FROM features.direct_messages message
@@ -125,7 +152,7 @@ impl Conversation {
         .await
         {
             Ok(records) => {
-                let conversation: Vec<_> = records
+                let mut conversation: Vec<_> = records
                     .into_iter()
                     .filter(|rec| {
                         // Filter deleted messages
@@ -139,12 +166,16 @@ impl Conversation {
                             sender_id,
                             rec.receiver_id.into(),
                             rec.message_content,
+                            rec.message_order_id.map(Into::into),
                             rec.message_sent_at,
                             sender_id == user_id,
                             rec.is_read,
+                            rec.read_at,
+                            rec.delivered_at,
                         )
                     })
                     .collect();
+                attach_message_attachments(&mut conversation, db).await?;
 
                 Ok(Self::from_row(user_id, other_id, conversation))
             }
@@ -155,13 +186,70 @@ impl Conversation {
         }
     }
 
-    /// Inserts Direct Message into the database
+    /// Fetches users in `users` that have a conversation with the user from the database,
+    /// users that blocked each other are excluded.
+    #[tracing::instrument(name = "Fetch Conversation Participants", skip(users, db))]
+    pub async fn participants(
+        user_id: ModelID,
+        users: &[ModelID],
+        db: DatabaseConnection,
+    ) -> ServerResult<Vec<ModelID>> {
+        let users: Vec<_> = users.iter().map(|id| id.0).collect();
+        match sqlx::query!(
+            r#"
+                SELECT DISTINCT CASE WHEN message.sender_id = $1
+                        THEN message.receiver_id
+                        ELSE message.sender_id
+                    END AS "participant_id!"
+                FROM features.direct_messages message
+
+                WHERE ((message.sender_id = $1 AND message.receiver_id = ANY($2))
+                    OR (message.receiver_id = $1 AND message.sender_id = ANY($2)))
+                    -- Users that blocked each other are not contacts
+                    AND NOT EXISTS (
+                        SELECT 1 FROM accounts.user_blocks block
+                        WHERE (block.user_id = message.sender_id
+                                AND block.blocked_user_id = message.receiver_id)
+                            OR (block.user_id = message.receiver_id
+                                AND block.blocked_user_id = message.sender_id)
+                    );
+            "#,
+            user_id.0,
+            &users[..],
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => Ok(records
+                .into_iter()
+                .map(|rec| rec.participant_id.into())
+                .collect()),
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to fetch conversation participants: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Inserts Direct Message into the database.
+    ///
+    /// Returns `None` if the message starts a conversation and the sender
+    /// already started `MESSAGE_MAX_NEW_CONVERSATIONS` conversations
+    /// in the last day, messages about an order are not limited.
     #[tracing::instrument(name = "Insert Direct Message", skip(db, msg))]
     pub async fn insert(
         msg: NewMessageInsertData,
         db: DatabaseConnection,
-    ) -> ServerResult<ModelID> {
+    ) -> ServerResult<Option<ModelID>> {
         let mut tx = db.pool.begin().await?; // init transaction
+        if msg.order_id.is_none()
+            && !can_start_conversation(msg.sender_id, msg.receiver_id, &mut tx).await?
+        {
+            return Ok(None);
+        }
         match sqlx::query!(
             r#"
                 INSERT INTO features.direct_messages(
@@ -169,15 +257,17 @@ impl Conversation {
                     sender_id,
                     receiver_id,
                     content,
-                    sent_at
+                    sent_at,
+                    order_id
                 )
-                VALUES($1, $2, $3, $4, $5);
+                VALUES($1, $2, $3, $4, $5, $6);
             "#,
             msg.id.0,
             msg.sender_id.0,
             msg.receiver_id.0,
             msg.content,
             msg.sent_at,
+            msg.order_id.map(|id| id.0),
         )
         .execute(&mut *tx)
         .await
@@ -189,10 +279,12 @@ impl Conversation {
                 );
                 // Insert direct message metadata
                 insert_message_status(msg.status, &mut tx).await?;
+                insert_message_attachments(msg.id, msg.sender_id, &msg.attachments, &mut tx)
+                    .await?;
 
                 tx.commit().await?; // Commit transaction
                 tracing::debug!("Direct message and its metadata inserted successfully.");
-                Ok(msg.id)
+                Ok(Some(msg.id))
             }
             Err(err) => {
                 tracing::error!("Database error, failed to insert direct message: {}", err);
@@ -208,27 +300,31 @@ impl Conversation {
         message_id: ModelID,
         db: DatabaseConnection,
     ) -> ServerResult<()> {
-        let msg_status = find_message_status(message_id, db.clone()).await?;
+        let participants = find_message_participants(message_id, db.clone()).await?;
 
-        let is_sender = user_id == msg_status.sender_id;
-        let is_receiver = user_id == msg_status.receiver_id;
+        let is_sender = user_id == participants.sender_id;
+        let is_receiver = user_id == participants.receiver_id;
 
         if !(is_sender || is_receiver) {
             return Err(ServerError::rejection(EndpointRejection::forbidden()));
         }
 
-        // If of the user already deleted this message; delete it permanently.
-        if (is_sender && msg_status.receiver_has_deleted)
-            || (is_receiver && msg_status.sender_has_deleted)
-        {
-            DirectMessage::delete(message_id, db).await
-        }
-        // Else delete it for only this user
-        else if is_sender {
-            DirectMessage::delete_for_sender(message_id, db).await
+        // Mark the message deleted for this user, the flags are updated and read
+        // in one statement so concurrent deletes can't both miss the other flag.
+        let deleted_by_both = if is_sender {
+            DirectMessage::delete_for_sender(message_id, db.clone()).await?
         } else {
-            DirectMessage::delete_for_receiver(message_id, db).await
+            DirectMessage::delete_for_receiver(message_id, db.clone()).await?
+        };
+
+        // If both users deleted this message; delete it permanently.
+        if deleted_by_both {
+            let images = DirectMessage::delete(message_id, db).await?;
+            if !images.is_empty() {
+                tokio::spawn(async move { delete_message_images(images).await });
+            }
         }
+        Ok(())
     }
 
     /// Deletes Direct Message from the database for everyone
@@ -238,14 +334,18 @@ impl Conversation {
         user_id: ModelID,
         db: DatabaseConnection,
     ) -> ServerResult<()> {
-        let msg_status = find_message_status(message_id, db.clone()).await?;
+        let participants = find_message_participants(message_id, db.clone()).await?;
 
-        let is_sender = user_id == msg_status.sender_id;
+        let is_sender = user_id == participants.sender_id;
 
         if !is_sender {
             return Err(ServerError::rejection(EndpointRejection::forbidden()));
         }
-        DirectMessage::delete(message_id, db).await
+        let images = DirectMessage::delete(message_id, db).await?;
+        if !images.is_empty() {
+            tokio::spawn(async move { delete_message_images(images).await });
+        }
+        Ok(())
     }
 
     /// Updates the direct message is_read in the database
@@ -267,7 +367,8 @@ impl Conversation {
             r#"
                 UPDATE features.message_status status
                     SET is_read = TRUE,
-                        read_at = $1
+                        read_at = $1,
+                        delivered_at = COALESCE(status.delivered_at, $1)
                 WHERE status.message_id = ANY($2);
             "#,
             OffsetDateTime::now_utc(),
@@ -292,11 +393,1171 @@ impl Conversation {
             }
         }
     }
+    /// Updates the messages the sender sent to the receiver are delivered in the database,
+    /// return the messages that were not delivered before.
+    #[tracing::instrument(name = "Update messages are delivered", skip(db))]
+    pub async fn update_is_delivered(
+        receiver_id: ModelID,
+        sender_id: ModelID,
+        message_ids: Vec<ModelID>,
+        db: DatabaseConnection,
+    ) -> ServerResult<Vec<ModelID>> {
+        let message_ids: Vec<_> = message_ids.into_iter().map(|id| id.0).collect();
+        match sqlx::query!(
+            r#"
+                UPDATE features.message_status status
+                    SET delivered_at = $1
+                FROM features.direct_messages message
+                WHERE status.message_id = message.id
+                    AND message.id = ANY($2)
+                    AND message.receiver_id = $3
+                    AND message.sender_id = $4
+                    AND status.delivered_at IS NULL
+                RETURNING message.id;
+            "#,
+            OffsetDateTime::now_utc(),
+            &message_ids[..],
+            receiver_id.0,
+            sender_id.0,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => Ok(records.into_iter().map(|rec| rec.id.into()).collect()),
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to update direct message delivered status: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Advisory lock namespace of the conversations a user starts
+const NEW_CONVERSATION_LOCK_NAMESPACE: &str = "new_conversation";
+
+/// Returns true if the sender already messaged the receiver or started
+/// less than `MESSAGE_MAX_NEW_CONVERSATIONS` conversations in the last day.
+///
+/// The sender is locked until the transaction ends,
+/// so concurrent messages are counted one after the other.
+async fn can_start_conversation(
+    sender_id: ModelID,
+    receiver_id: ModelID,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> ServerResult<bool> {
+    if let Err(err) = sqlx::query!(
+        r#"
+            SELECT pg_advisory_xact_lock(hashtext($1), hashtext($2))::text;
+        "#,
+        NEW_CONVERSATION_LOCK_NAMESPACE,
+        sender_id.0.to_string(),
+    )
+    .fetch_one(&mut **tx)
+    .await
+    {
+        tracing::error!("Database error, failed to lock new conversations: {}", err);
+        return Err(err.into());
+    }
+
+    match sqlx::query!(
+        r#"
+            SELECT EXISTS(
+                SELECT 1 FROM features.direct_messages message
+                WHERE (message.sender_id = $1 AND message.receiver_id = $2)
+                    OR (message.sender_id = $2 AND message.receiver_id = $1)
+            ) AS "exists!",
+            (
+                SELECT COUNT(*)
+                FROM features.direct_messages message
+
+                WHERE message.sender_id = $1
+                    AND message.sent_at >= $3
+                    AND message.order_id IS NULL
+                    -- Only the first message of the conversation
+                    AND NOT EXISTS (
+                        SELECT 1 FROM features.direct_messages earlier
+                        WHERE ((earlier.sender_id = $1 AND earlier.receiver_id = message.receiver_id)
+                            OR (earlier.sender_id = message.receiver_id AND earlier.receiver_id = $1))
+                            AND (earlier.sent_at, earlier.id) < (message.sent_at, message.id)
+                    )
+            ) AS "started!";
+        "#,
+        sender_id.0,
+        receiver_id.0,
+        OffsetDateTime::now_utc() - Duration::days(1),
+    )
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(rec) => Ok(rec.exists || rec.started < MESSAGE_MAX_NEW_CONVERSATIONS),
+        Err(err) => {
+            tracing::error!(
+                "Database error, failed to count started conversations: {}",
+                err
+            );
+            Err(err.into())
+        }
+    }
+}
+
+// ===== Message history impls =====
+
+impl MessageHistory {
+    /// Fetches a page of the conversation messages from the database,
+    /// messages deleted by the user are excluded.
+    #[tracing::instrument(name = "Fetch Message History", skip(db))]
+    #[allow(clippy::cast_possible_wrap)]
+    pub async fn find(
+        user_id: ModelID,
+        other_id: ModelID,
+        query: &MessageHistoryQuery,
+        db: DatabaseConnection,
+    ) -> ServerResult<Self> {
+        match sqlx::query!(
+            r#"
+                SELECT message.id AS message_id,
+                    message.sender_id,
+                    message.receiver_id,
+                    message.content AS message_content,
+                    message.order_id AS message_order_id,
+                    message.sent_at AS message_sent_at,
+                    status.is_read,
+                    status.read_at,
+                    status.delivered_at
+                FROM features.direct_messages message
+                INNER JOIN features.message_status status
+                    ON message.id = status.message_id
+
+                WHERE (
+                    (message.sender_id = $1 AND message.receiver_id = $2
+                        AND NOT status.sender_has_deleted)
+                    OR (message.sender_id = $2 AND message.receiver_id = $1
+                        AND NOT status.receiver_has_deleted)
+                )
+                    -- Keyset pagination: only messages sent before the cursor message
+                    AND ($3::uuid IS NULL
+                        OR (message.sent_at, message.id) < (
+                            SELECT cursor_.sent_at, cursor_.id
+                            FROM features.direct_messages cursor_
+                            WHERE cursor_.id = $3
+                        )
+                    )
+
+                ORDER BY message.sent_at DESC, message.id DESC
+                LIMIT $4;
+            "#,
+            user_id.0,
+            other_id.0,
+            query.before.map(|id| id.0),
+            query.limit as i64 + 1,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let mut messages: Vec<_> = records
+                    .into_iter()
+                    .map(|rec| {
+                        let sender_id: ModelID = rec.sender_id.into();
+                        DirectMessage::from_row(
+                            rec.message_id.into(),
+                            sender_id,
+                            rec.receiver_id.into(),
+                            rec.message_content,
+                            rec.message_order_id.map(Into::into),
+                            rec.message_sent_at,
+                            sender_id == user_id,
+                            rec.is_read,
+                            rec.read_at,
+                            rec.delivered_at,
+                        )
+                    })
+                    .collect();
+                attach_message_attachments(&mut messages, db).await?;
+
+                Ok(Self::from_row(messages, query.limit))
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch message history: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+// ===== Conversation summary impls =====
+
+impl ConversationSummary {
+    /// Fetches the user conversations with their last message
+    /// and unread messages count from the database.
+    #[tracing::instrument(name = "Fetch Conversation Summaries", skip(db))]
+    pub async fn records(
+        user_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<ConversationSummaryList> {
+        match sqlx::query!(
+            r#"
+                WITH visible AS (
+                    SELECT message.id,
+                        message.sender_id,
+                        message.receiver_id,
+                        message.content,
+                        message.order_id,
+                        message.sent_at,
+                        status.is_read,
+                        status.read_at,
+                        status.delivered_at,
+                        CASE WHEN message.sender_id = $1
+                            THEN message.receiver_id
+                            ELSE message.sender_id
+                        END AS participant_id
+                    FROM features.direct_messages message
+                    INNER JOIN features.message_status status
+                        ON message.id = status.message_id
+                    WHERE (message.sender_id = $1 AND NOT status.sender_has_deleted)
+                        OR (message.receiver_id = $1 AND NOT status.receiver_has_deleted)
+                ),
+                latest AS (
+                    SELECT DISTINCT ON (visible.participant_id) visible.*
+                    FROM visible
+                    ORDER BY visible.participant_id, visible.sent_at DESC, visible.id DESC
+                )
+                SELECT latest.id AS "message_id!",
+                    latest.sender_id AS "sender_id!",
+                    latest.receiver_id AS "receiver_id!",
+                    latest.content AS "message_content!",
+                    latest.order_id AS message_order_id,
+                    latest.sent_at AS "message_sent_at!",
+                    latest.is_read AS "is_read!",
+                    latest.read_at,
+                    latest.delivered_at,
+                    participant.id AS participant_id,
+                    participant.first_name AS participant_first_name,
+                    participant.last_name AS participant_last_name,
+                    participant_profile.photo AS "participant_photo?",
+                    (
+                        SELECT COUNT(*)
+                        FROM visible unread
+                        WHERE unread.participant_id = latest.participant_id
+                            AND unread.receiver_id = $1
+                            AND NOT unread.is_read
+                    ) AS "unread_count!"
+                FROM latest
+                INNER JOIN accounts.users participant
+                    ON latest.participant_id = participant.id
+                LEFT JOIN accounts.user_profiles participant_profile
+                    ON participant.id = participant_profile.user_id
+
+                ORDER BY latest.sent_at DESC;
+            "#,
+            user_id.0,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let mut conversations: ConversationSummaryList = records
+                    .into_iter()
+                    .map(|rec| {
+                        let sender_id: ModelID = rec.sender_id.into();
+                        Self {
+                            participant: UserIndex::from_row(
+                                rec.participant_id.into(),
+                                rec.participant_first_name,
+                                rec.participant_last_name,
+                                rec.participant_photo,
+                            ),
+                            last_message: DirectMessage::from_row(
+                                rec.message_id.into(),
+                                sender_id,
+                                rec.receiver_id.into(),
+                                rec.message_content,
+                                rec.message_order_id.map(Into::into),
+                                rec.message_sent_at,
+                                sender_id == user_id,
+                                rec.is_read,
+                                rec.read_at,
+                                rec.delivered_at,
+                            ),
+                            unread_count: rec.unread_count,
+                        }
+                    })
+                    .collect();
+                attach_message_attachments(
+                    conversations.iter_mut().map(|conv| &mut conv.last_message),
+                    db,
+                )
+                .await?;
+                Ok(conversations)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to fetch conversation summaries: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
+
+// ===== Message attachment impls =====
+
+impl MessageAttachment {
+    /// Inserts images uploaded by the user not yet sent in a message into the database
+    #[tracing::instrument(name = "Insert Message Images", skip(db))]
+    pub async fn insert_images(
+        uploader_id: ModelID,
+        image_ids: Vec<ModelID>,
+        db: DatabaseConnection,
+    ) -> ServerResult<Vec<Self>> {
+        let ids: Vec<_> = image_ids.iter().map(|id| id.0).collect();
+        let files: Vec<_> = image_ids.iter().map(|id| format!("{id}.jpg")).collect();
+
+        match sqlx::query!(
+            r#"
+                INSERT INTO features.message_attachments(
+                    id,
+                    uploader_id,
+                    kind,
+                    image,
+                    created_at
+                )
+                SELECT attachment.id, $3, 'image', attachment.image, $4
+                FROM unnest($1::uuid[], $2::text[]) AS attachment(id, image);
+            "#,
+            &ids[..],
+            &files[..],
+            uploader_id.0,
+            OffsetDateTime::now_utc(),
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Message images inserted successfully: {:?}", result);
+                Ok(image_ids
+                    .into_iter()
+                    .zip(files)
+                    .map(|(id, image)| Self::Image { id, image })
+                    .collect())
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to insert message images: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Returns true if the user can send the attachments:
+    /// images uploaded by the user not yet sent and harvests still listed.
+    #[tracing::instrument(name = "Check Message Attachments", skip(db))]
+    #[allow(clippy::cast_possible_wrap)]
+    pub async fn can_attach(
+        user_id: ModelID,
+        attachments: &[NewAttachment],
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        let mut images = Vec::new();
+        let mut harvests = Vec::new();
+        for attachment in attachments {
+            match attachment {
+                NewAttachment::Image { id } => images.push(id.0),
+                NewAttachment::Harvest { harvest_id } => harvests.push(harvest_id.0),
+            }
+        }
+        match sqlx::query!(
+            r#"
+                SELECT (
+                    SELECT COUNT(*)
+                    FROM features.message_attachments attachment
+                    WHERE attachment.id = ANY($2)
+                        AND attachment.uploader_id = $1
+                        AND attachment.kind = 'image'
+                        AND attachment.message_id IS NULL
+                ) AS "images!",
+                (
+                    SELECT COUNT(*)
+                    FROM services.harvests harvest
+                    WHERE harvest.id = ANY($3)
+                        AND NOT harvest.finished
+                ) AS "harvests!";
+            "#,
+            user_id.0,
+            &images[..],
+            &harvests[..],
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(rec) => {
+                Ok(rec.images == images.len() as i64 && rec.harvests == harvests.len() as i64)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to check message attachments: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Returns true if the user uploaded the image or
+    /// the image was sent in a message the user has not deleted.
+    #[tracing::instrument(name = "Check Message Image Access", skip(db))]
+    pub async fn can_view_image(
+        user_id: ModelID,
+        image_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                SELECT EXISTS(
+                    SELECT 1
+                    FROM features.message_attachments attachment
+                    LEFT JOIN features.direct_messages message
+                        ON attachment.message_id = message.id
+                    LEFT JOIN features.message_status status
+                        ON message.id = status.message_id
+
+                    WHERE attachment.id = $1
+                        AND attachment.kind = 'image'
+                        AND (
+                            (attachment.message_id IS NULL AND attachment.uploader_id = $2)
+                            OR (message.sender_id = $2 AND NOT status.sender_has_deleted)
+                            OR (message.receiver_id = $2 AND NOT status.receiver_has_deleted)
+                        )
+                ) AS "allowed!";
+            "#,
+            image_id.0,
+            user_id.0,
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.allowed),
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to check message image access: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Returns true if the image is in the messages of a reported conversation
+    #[tracing::instrument(name = "Fetch Message Image Is Reported", skip(db))]
+    pub async fn is_reported(image_id: ModelID, db: DatabaseConnection) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                SELECT EXISTS(
+                    SELECT 1
+                    FROM features.conversation_reports report,
+                        jsonb_array_elements(report.messages) message,
+                        jsonb_array_elements(message -> 'attachments') attachment
+
+                    WHERE attachment ->> 'type' = 'Image'
+                        AND attachment ->> 'id' = $1
+                ) AS "reported!";
+            "#,
+            image_id.0.to_string(),
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.reported),
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to check reported message image: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Fetches the attachments of the messages from the database,
+    /// return the attachments with the id of their message.
+    #[tracing::instrument(name = "Fetch Message Attachments", skip(message_ids, db))]
+    pub async fn records(
+        message_ids: &[ModelID],
+        db: DatabaseConnection,
+    ) -> ServerResult<Vec<(ModelID, Self)>> {
+        let message_ids: Vec<_> = message_ids.iter().map(|id| id.0).collect();
+        let records = match sqlx::query!(
+            r#"
+                SELECT attachment.id,
+                    attachment.message_id AS "message_id!",
+                    attachment.image,
+                    attachment.harvest_id
+                FROM features.message_attachments attachment
+
+                WHERE attachment.message_id = ANY($1)
+                ORDER BY attachment.created_at, attachment.id;
+            "#,
+            &message_ids[..],
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => records,
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to fetch message attachments: {}",
+                    err
+                );
+                return Err(err.into());
+            }
+        };
+
+        let harvest_ids: Vec<_> = records
+            .iter()
+            .filter_map(|rec| rec.harvest_id)
+            .unique()
+            .collect();
+        let harvests = if harvest_ids.is_empty() {
+            HashMap::new()
+        } else {
+            Self::harvest_cards(&harvest_ids, db).await?
+        };
+
+        Ok(records
+            .into_iter()
+            .map(|rec| {
+                let attachment = rec.image.map_or_else(
+                    || Self::Harvest {
+                        id: rec.id.into(),
+                        harvest: rec
+                            .harvest_id
+                            .and_then(|harvest_id| harvests.get(&harvest_id).cloned())
+                            .map(Box::new),
+                    },
+                    |image| Self::Image {
+                        id: rec.id.into(),
+                        image,
+                    },
+                );
+                (rec.message_id.into(), attachment)
+            })
+            .collect())
+    }
+
+    /// Fetches the harvests shared in messages from the database
+    async fn harvest_cards(
+        harvest_ids: &[uuid::Uuid],
+        db: DatabaseConnection,
+    ) -> ServerResult<HashMap<uuid::Uuid, HarvestIndex>> {
+        match sqlx::query!(
+            r#"
+                SELECT harvest.id AS harvest_id,
+                    harvest.price AS harvest_price,
+                    harvest.harvest_date AS harvest_harvest_date,
+                    harvest.images AS harvest_images,
+                    services.harvest_low_stock(
+                        harvest.available_quantity,
+                        harvest.reserved_quantity,
+                        harvest.low_stock_threshold
+                    ) AS "harvest_low_stock!",
+                    cultivar.name AS cultivar_name,
+                    cultivar_category.name AS cultivar_category,
+                    cultivar.image AS cultivar_image,
+                    farm.name AS farm_name,
+                    farm.logo AS farm_logo,
+                    location_.place_name AS location_place_name,
+                    location_.coords AS location_coords,
+                    region.name AS "location_region?",
+                    country.name AS location_country
+                FROM services.harvests harvest
+                INNER JOIN services.cultivars cultivar
+                    ON harvest.cultivar_id = cultivar.id
+                INNER JOIN services.cultivar_categories cultivar_category
+                    ON cultivar.category_id = cultivar_category.id
+                INNER JOIN services.locations location_
+                    ON harvest.location_id = location_.id
+                INNER JOIN services.farms farm
+                    ON location_.farm_id = farm.id
+                LEFT JOIN services.regions region
+                    ON location_.region_id = region.id
+                INNER JOIN services.countries country
+                    ON location_.country_id = country.id
+
+                WHERE harvest.id = ANY($1);
+            "#,
+            harvest_ids,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => Ok(records
+                .into_iter()
+                .map(|rec| {
+                    let harvest = HarvestIndex::from_row(
+                        rec.harvest_id.into(),
+                        rec.harvest_price,
+                        rec.harvest_harvest_date,
+                        rec.harvest_images,
+                        rec.cultivar_name,
+                        rec.cultivar_category,
+                        rec.cultivar_image,
+                        rec.location_place_name,
+                        rec.location_region,
+                        rec.location_country,
+                        rec.location_coords,
+                        rec.farm_name,
+                        rec.farm_logo,
+                        rec.harvest_low_stock,
+                        rust_decimal::Decimal::ZERO,
+                    );
+                    (rec.harvest_id, harvest)
+                })
+                .collect()),
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to fetch message harvest cards: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Deletes images uploaded before `uploaded_before` that were never sent
+    /// in a message from the database, their files are left to the orphaned files cleanup.
+    /// Return the number of images deleted.
+    #[tracing::instrument(name = "Delete Unsent Message Images", skip(db))]
+    pub async fn delete_unsent_images(
+        uploaded_before: OffsetDateTime,
+        db: DatabaseConnection,
+    ) -> ServerResult<u64> {
+        match sqlx::query!(
+            r#"
+                DELETE FROM features.message_attachments attachment
+                WHERE attachment.message_id IS NULL
+                    AND attachment.created_at < $1;
+            "#,
+            uploaded_before,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => Ok(result.rows_affected()),
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to delete unsent message images: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
+
+// ===== Chat instance impls =====
+
+/// Advisory lock namespace of the chat presence
+const CHAT_PRESENCE_LOCK_NAMESPACE: &str = "chat_presence";
+
+impl ChatInstance {
+    /// Registers the instance or updates its heartbeat in the database,
+    /// return true if the instance was registered.
+    ///
+    /// An instance is registered again after it was removed
+    /// for missing heartbeats, its users presence has to be restored.
+    #[tracing::instrument(name = "Update Chat Instance Heartbeat", skip(db))]
+    pub async fn heartbeat(&self, db: DatabaseConnection) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                INSERT INTO features.chat_instances(id, started_at, heartbeat_at)
+                VALUES($1, $2, $3)
+                ON CONFLICT ON CONSTRAINT chat_instances_pkey
+                DO UPDATE SET heartbeat_at = EXCLUDED.heartbeat_at
+                RETURNING (xmax = 0) AS "registered!";
+            "#,
+            self.id.0,
+            self.started_at,
+            OffsetDateTime::now_utc(),
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(rec) => {
+                tracing::trace!("Chat instance heartbeat updated");
+                Ok(rec.registered)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to update chat instance heartbeat: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Adds a chat connection of the user on this instance,
+    /// return true if the user had no connection on any instance.
+    ///
+    /// Only the heartbeat registers the instance, connections made while
+    /// the instance is not registered are restored by the heartbeat.
+    #[tracing::instrument(name = "Insert Chat Connection", skip(self, db))]
+    pub async fn connect(&self, user_id: ModelID, db: DatabaseConnection) -> ServerResult<bool> {
+        let mut tx = db.pool.begin().await?; // init transaction
+        lock_user_presence(user_id, &mut tx).await?;
+
+        let was_online = user_is_online(user_id, &mut tx).await?;
+        match sqlx::query!(
+            r#"
+                INSERT INTO features.chat_presence(
+                    instance_id,
+                    user_id,
+                    connections,
+                    connected_at
+                )
+                SELECT $1, $2, 1, $3
+                WHERE EXISTS (
+                    SELECT 1 FROM features.chat_instances instance
+                    WHERE instance.id = $1
+                )
+                ON CONFLICT ON CONSTRAINT chat_presence_pkey
+                DO UPDATE SET connections = chat_presence.connections + 1;
+            "#,
+            self.id.0,
+            user_id.0,
+            OffsetDateTime::now_utc(),
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            Ok(result) => {
+                tx.commit().await?; // Commit transaction
+                tracing::trace!("Chat connection inserted: {:?}", result);
+                Ok(result.rows_affected() > 0 && !was_online)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to insert chat connection: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Inserts the presence of the users connected to this instance,
+    /// return the users that had no connection on any other instance.
+    ///
+    /// Users that connected since the instance was registered are left unchanged.
+    #[tracing::instrument(name = "Restore Chat Presence", skip(self, connections, db))]
+    pub async fn restore_presence(
+        &self,
+        connections: Vec<(ModelID, usize)>,
+        db: DatabaseConnection,
+    ) -> ServerResult<Vec<ModelID>> {
+        let (user_ids, connections): (Vec<_>, Vec<_>) = connections
+            .into_iter()
+            .map(|(user_id, connections)| {
+                (user_id.0, i32::try_from(connections).unwrap_or(i32::MAX))
+            })
+            .unzip();
+        match sqlx::query!(
+            r#"
+                WITH restored AS (
+                    INSERT INTO features.chat_presence(
+                        instance_id,
+                        user_id,
+                        connections,
+                        connected_at
+                    )
+                    SELECT $1, users.user_id, users.connections, $4
+                    FROM unnest($2::uuid[], $3::integer[]) AS users(user_id, connections)
+                    WHERE EXISTS (
+                        SELECT 1 FROM accounts.users user_
+                        WHERE user_.id = users.user_id
+                    )
+                    ON CONFLICT ON CONSTRAINT chat_presence_pkey DO NOTHING
+                    RETURNING chat_presence.user_id
+                )
+                SELECT restored.user_id
+                FROM restored
+                WHERE NOT EXISTS (
+                    SELECT 1
+                    FROM features.chat_presence live
+                    WHERE live.user_id = restored.user_id
+                        AND live.instance_id <> $1
+                );
+            "#,
+            self.id.0,
+            &user_ids[..],
+            &connections[..],
+            OffsetDateTime::now_utc(),
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => Ok(records.into_iter().map(|rec| rec.user_id.into()).collect()),
+            Err(err) => {
+                tracing::error!("Database error, failed to restore chat presence: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Removes a chat connection of the user on this instance,
+    /// return true if the user has no connection left on any instance.
+    #[tracing::instrument(name = "Delete Chat Connection", skip(self, db))]
+    pub async fn disconnect(&self, user_id: ModelID, db: DatabaseConnection) -> ServerResult<bool> {
+        let mut tx = db.pool.begin().await?; // init transaction
+        lock_user_presence(user_id, &mut tx).await?;
+
+        let removed = match sqlx::query!(
+            r#"
+                DELETE FROM features.chat_presence presence
+                WHERE presence.instance_id = $1
+                    AND presence.user_id = $2
+                    AND presence.connections <= 1;
+            "#,
+            self.id.0,
+            user_id.0,
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            Ok(result) => {
+                tracing::trace!("Chat presence deleted: {:?}", result);
+                result.rows_affected() > 0
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to delete chat presence: {}", err);
+                return Err(err.into());
+            }
+        };
+
+        if !removed {
+            match sqlx::query!(
+                r#"
+                    UPDATE features.chat_presence presence
+                        SET connections = presence.connections - 1
+                    WHERE presence.instance_id = $1
+                        AND presence.user_id = $2;
+                "#,
+                self.id.0,
+                user_id.0,
+            )
+            .execute(&mut *tx)
+            .await
+            {
+                Ok(result) => tracing::trace!("Chat connection deleted: {:?}", result),
+                Err(err) => {
+                    tracing::error!("Database error, failed to delete chat connection: {}", err);
+                    return Err(err.into());
+                }
+            }
+        }
+
+        // The instance last connection was removed, check the other instances
+        let went_offline = removed && !user_is_online(user_id, &mut tx).await?;
+        tx.commit().await?; // Commit transaction
+        Ok(went_offline)
+    }
+
+    /// Deletes instances without a heartbeat since `heartbeat_before` and
+    /// their users presence from the database.
+    /// Return users that were only connected to the deleted instances.
+    #[tracing::instrument(name = "Delete Stale Chat Instances", skip(db))]
+    pub async fn remove_stale(
+        heartbeat_before: OffsetDateTime,
+        db: DatabaseConnection,
+    ) -> ServerResult<Vec<ModelID>> {
+        match sqlx::query!(
+            r#"
+                WITH stale AS (
+                    DELETE FROM features.chat_instances instance
+                    WHERE instance.heartbeat_at < $1
+                    RETURNING instance.id
+                )
+                SELECT DISTINCT presence.user_id
+                FROM features.chat_presence presence
+                WHERE presence.instance_id IN (SELECT stale.id FROM stale)
+                    AND NOT EXISTS (
+                        SELECT 1
+                        FROM features.chat_presence live
+                        WHERE live.user_id = presence.user_id
+                            AND live.instance_id NOT IN (SELECT stale.id FROM stale)
+                    );
+            "#,
+            heartbeat_before,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => Ok(records.into_iter().map(|rec| rec.user_id.into()).collect()),
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to delete stale chat instances: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Fetches users sharing a conversation with the user
+    /// that are connected to any instance from the database.
+    #[tracing::instrument(name = "Fetch Online Contacts", skip(db))]
+    pub async fn online_contacts(
+        user_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<Vec<ModelID>> {
+        match sqlx::query!(
+            r#"
+                SELECT DISTINCT presence.user_id
+                FROM features.chat_presence presence
+
+                WHERE EXISTS (
+                    SELECT 1
+                    FROM features.direct_messages message
+                    WHERE (message.sender_id = $1 AND message.receiver_id = presence.user_id)
+                        OR (message.receiver_id = $1 AND message.sender_id = presence.user_id)
+                )
+                    -- Users that blocked each other are not contacts
+                    AND NOT EXISTS (
+                        SELECT 1 FROM accounts.user_blocks block
+                        WHERE (block.user_id = $1 AND block.blocked_user_id = presence.user_id)
+                            OR (block.user_id = presence.user_id AND block.blocked_user_id = $1)
+                    );
+            "#,
+            user_id.0,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => Ok(records.into_iter().map(|rec| rec.user_id.into()).collect()),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch online contacts: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Publishes the payload to all instances listening on the channel
+    pub async fn publish(channel: &str, payload: &str, db: DatabaseConnection) -> ServerResult<()> {
+        match sqlx::query!(
+            r#"
+                SELECT pg_notify($1, $2)::text;
+            "#,
+            channel,
+            payload,
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("Database error, failed to publish chat message: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Locks the user presence until the transaction ends,
+/// so concurrent connections agree on when the user came online.
+async fn lock_user_presence(
+    user_id: ModelID,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> ServerResult<()> {
+    match sqlx::query!(
+        r#"
+            SELECT pg_advisory_xact_lock(hashtext($1), hashtext($2))::text;
+        "#,
+        CHAT_PRESENCE_LOCK_NAMESPACE,
+        user_id.0.to_string(),
+    )
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            tracing::error!("Database error, failed to lock chat presence: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// Returns true if the user has a chat connection on any instance
+async fn user_is_online(
+    user_id: ModelID,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> ServerResult<bool> {
+    match sqlx::query!(
+        r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM features.chat_presence presence
+                WHERE presence.user_id = $1
+            ) AS "online!";
+        "#,
+        user_id.0,
+    )
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(rec) => Ok(rec.online),
+        Err(err) => {
+            tracing::error!("Database error, failed to fetch chat presence: {}", err);
+            Err(err.into())
+        }
+    }
 }
 
 // ===== Direct Message impls =====
 
 impl DirectMessage {
+    /// Fetches the message sent to the receiver from the database
+    #[tracing::instrument(name = "Find DirectMessage", skip(db))]
+    pub async fn find(message_id: ModelID, db: DatabaseConnection) -> ServerResult<Option<Self>> {
+        let mut message = match sqlx::query!(
+            r#"
+                SELECT message.id AS message_id,
+                    message.sender_id,
+                    message.receiver_id,
+                    message.content AS message_content,
+                    message.order_id AS message_order_id,
+                    message.sent_at AS message_sent_at,
+                    status.is_read,
+                    status.read_at,
+                    status.delivered_at
+                FROM features.direct_messages message
+                INNER JOIN features.message_status status
+                    ON message.id = status.message_id
+
+                WHERE message.id = $1
+                    AND NOT status.receiver_has_deleted;
+            "#,
+            message_id.0,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(Some(rec)) => Self::from_row(
+                rec.message_id.into(),
+                rec.sender_id.into(),
+                rec.receiver_id.into(),
+                rec.message_content,
+                rec.message_order_id.map(Into::into),
+                rec.message_sent_at,
+                false,
+                rec.is_read,
+                rec.read_at,
+                rec.delivered_at,
+            ),
+            Ok(None) => return Ok(None),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch direct message: {}", err);
+                return Err(err.into());
+            }
+        };
+
+        attach_message_attachments(std::iter::once(&mut message), db).await?;
+        Ok(Some(message))
+    }
+
+    /// Fetches the messages the user received after the `after` message
+    /// from the database, oldest message first.
+    ///
+    /// Returns `None` if the `after` message is not found in the user
+    /// conversations or more than `limit` messages were received since.
+    #[tracing::instrument(name = "Fetch Replayed Messages", skip(db))]
+    #[allow(clippy::cast_possible_wrap)]
+    pub async fn replay(
+        user_id: ModelID,
+        after: ModelID,
+        limit: i64,
+        db: DatabaseConnection,
+    ) -> ServerResult<Option<Vec<Self>>> {
+        let cursor = match sqlx::query!(
+            r#"
+                SELECT message.sent_at
+                FROM features.direct_messages message
+                WHERE message.id = $2
+                    AND (message.sender_id = $1 OR message.receiver_id = $1);
+            "#,
+            user_id.0,
+            after.0,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(Some(rec)) => rec.sent_at,
+            Ok(None) => return Ok(None),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch replay cursor: {}", err);
+                return Err(err.into());
+            }
+        };
+
+        match sqlx::query!(
+            r#"
+                SELECT message.id AS message_id,
+                    message.sender_id,
+                    message.receiver_id,
+                    message.content AS message_content,
+                    message.order_id AS message_order_id,
+                    message.sent_at AS message_sent_at,
+                    status.is_read,
+                    status.read_at,
+                    status.delivered_at
+                FROM features.direct_messages message
+                INNER JOIN features.message_status status
+                    ON message.id = status.message_id
+
+                WHERE message.receiver_id = $1
+                    AND NOT status.receiver_has_deleted
+                    AND (message.sent_at, message.id) > ($2, $3)
+
+                ORDER BY message.sent_at, message.id
+                LIMIT $4;
+            "#,
+            user_id.0,
+            cursor,
+            after.0,
+            limit + 1,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) if records.len() as i64 > limit => Ok(None),
+            Ok(records) => {
+                let mut messages: Vec<_> = records
+                    .into_iter()
+                    .map(|rec| {
+                        Self::from_row(
+                            rec.message_id.into(),
+                            rec.sender_id.into(),
+                            rec.receiver_id.into(),
+                            rec.message_content,
+                            rec.message_order_id.map(Into::into),
+                            rec.message_sent_at,
+                            false,
+                            rec.is_read,
+                            rec.read_at,
+                            rec.delivered_at,
+                        )
+                    })
+                    .collect();
+                attach_message_attachments(&mut messages, db).await?;
+
+                Ok(Some(messages))
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch replayed messages: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
     /// Updates direct message is read in the database
     #[allow(dead_code)]
     async fn update_is_read(message_ids: Vec<ModelID>, db: DatabaseConnection) -> ServerResult<()> {
@@ -305,7 +1566,8 @@ impl DirectMessage {
             r#"
                 UPDATE features.message_status status
                     SET is_read = TRUE,
-                        read_at = $1
+                        read_at = $1,
+                        delivered_at = COALESCE(status.delivered_at, $1)
                 WHERE status.message_id = ANY($2);
             "#,
             OffsetDateTime::now_utc(),
@@ -331,21 +1593,29 @@ impl DirectMessage {
         }
     }
 
-    /// Permanently delete direct message from the database
-    async fn delete(message_id: ModelID, db: DatabaseConnection) -> ServerResult<()> {
+    /// Permanently delete direct message and its attachments from the database,
+    /// return the attachment images to be deleted from the file system.
+    async fn delete(message_id: ModelID, db: DatabaseConnection) -> ServerResult<Vec<String>> {
         match sqlx::query!(
             r#"
-                DELETE FROM features.message_status status
-                WHERE status.message_id = $1
+                WITH deleted AS (
+                    DELETE FROM features.direct_messages message
+                    WHERE message.id = $1
+                    RETURNING message.id
+                )
+                SELECT attachment.image AS "image!"
+                FROM features.message_attachments attachment
+                WHERE attachment.message_id IN (SELECT deleted.id FROM deleted)
+                    AND attachment.image IS NOT NULL;
             "#,
             message_id.0
         )
-        .execute(&db.pool)
+        .fetch_all(&db.pool)
         .await
         {
-            Ok(result) => {
-                tracing::trace!("Direct message successfully deleted: {:?}", result);
-                Ok(())
+            Ok(records) => {
+                tracing::trace!("Direct message successfully deleted: {}", message_id);
+                Ok(records.into_iter().map(|rec| rec.image).collect())
             }
             Err(err) => {
                 tracing::error!("Database error, failed to delete direct message: {}", err);
@@ -354,27 +1624,29 @@ impl DirectMessage {
         }
     }
 
-    /// Deleted direct message for this `sender` in the database
-    async fn delete_for_sender(message_id: ModelID, db: DatabaseConnection) -> ServerResult<()> {
+    /// Deleted direct message for this `sender` in the database,
+    /// returns true if both the sender and receiver have deleted the message.
+    async fn delete_for_sender(message_id: ModelID, db: DatabaseConnection) -> ServerResult<bool> {
         match sqlx::query!(
             r#"
                 UPDATE features.message_status status
                     SET sender_has_deleted = TRUE,
                     sender_deleted_at = $1
-                WHERE status.message_id = $2;
+                WHERE status.message_id = $2
+                RETURNING status.sender_has_deleted, status.receiver_has_deleted;
             "#,
             OffsetDateTime::now_utc(),
             message_id.0
         )
-        .execute(&db.pool)
+        .fetch_optional(&db.pool)
         .await
         {
-            Ok(result) => {
+            Ok(rec) => {
                 tracing::trace!(
-                    "Direct message successfully deleted for sender: {:?}",
-                    result
+                    "Direct message successfully deleted for sender: {}",
+                    message_id
                 );
-                Ok(())
+                Ok(rec.is_some_and(|rec| rec.sender_has_deleted && rec.receiver_has_deleted))
             }
             Err(err) => {
                 tracing::error!(
@@ -386,27 +1658,32 @@ impl DirectMessage {
         }
     }
 
-    /// Deleted direct message for this `receiver` in the database
-    async fn delete_for_receiver(message_id: ModelID, db: DatabaseConnection) -> ServerResult<()> {
+    /// Deleted direct message for this `receiver` in the database,
+    /// returns true if both the sender and receiver have deleted the message.
+    async fn delete_for_receiver(
+        message_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
         match sqlx::query!(
             r#"
                 UPDATE features.message_status status
                     SET receiver_has_deleted = TRUE,
                     receiver_deleted_at = $1
-                WHERE status.message_id = $2;
+                WHERE status.message_id = $2
+                RETURNING status.sender_has_deleted, status.receiver_has_deleted;
             "#,
             OffsetDateTime::now_utc(),
             message_id.0
         )
-        .execute(&db.pool)
+        .fetch_optional(&db.pool)
         .await
         {
-            Ok(result) => {
+            Ok(rec) => {
                 tracing::trace!(
-                    "Direct message successfully deleted for receiver: {:?}",
-                    result
+                    "Direct message successfully deleted for receiver: {}",
+                    message_id
                 );
-                Ok(())
+                Ok(rec.is_some_and(|rec| rec.sender_has_deleted && rec.receiver_has_deleted))
             }
             Err(err) => {
                 tracing::error!(
@@ -1,21 +1,42 @@
 //! Direct Message form impls
 
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Json, Request},
+};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
-use crate::types::ModelID;
+use crate::{
+    auth::CurrentUser,
+    endpoint::{EndpointRejection, EndpointResult},
+    server::state::ServerState,
+    types::ModelID,
+    MESSAGE_HISTORY_MAX_LIMIT, MESSAGE_MAX_ATTACHMENTS,
+};
 
 use super::models::DirectMessage;
 
 /// Message sent from server to user(s) via websocket.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "body")]
 pub enum ForwardMessage {
     DirectMessage(DirectMessage),
     MessageIsRead(MessageIsRead),
+    MessageIsDelivered(MessageIsDelivered),
     UserConnected(ModelID),
     UserDisconnected(ModelID),
+    /// The user started typing a message to the receiver
+    TypingStarted(ModelID),
+    /// The user stopped typing a message to the receiver
+    TypingStopped(ModelID),
+    /// Contacts of the user that are online
+    Presence(Vec<ModelID>),
     IncomingMessageError(IncomingMessageError),
+    /// Messages sent to the connection were dropped because it fell behind,
+    /// the client should refetch its conversations.
+    Resync,
 }
 
 /// Message sent from client to server via websocket.
@@ -24,11 +45,33 @@ pub enum ForwardMessage {
 pub enum IncomingMessage {
     NewMessage(NewMessage),
     MessageIsRead(MessageIsRead),
+    MessageIsDelivered(MessageIsDelivered),
     MessageDelete(MessageDelete),
+    TypingStarted(Typing),
+    TypingStopped(Typing),
+    /// Requests the contacts of the user that are online
+    PresenceQuery,
     UserConnected,
     UserDisconnected,
 }
 
+#[async_trait]
+impl FromRequest<ServerState> for IncomingMessage
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let _ = { CurrentUser::from_parts(&mut parts, state).await? };
+        let Json(msg) = Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        Ok(msg)
+    }
+}
+
 // ===== Variant impls =====
 
 /// New direct message ws request
@@ -36,6 +79,21 @@ pub enum IncomingMessage {
 pub struct NewMessage {
     pub content: String,
     pub receiver_id: String,
+    /// The order the message is about
+    #[serde(default)]
+    pub order_id: Option<ModelID>,
+    #[serde(default)]
+    pub attachments: Vec<NewAttachment>,
+}
+
+/// Attachment sent with a new direct message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(tag = "type")]
+pub enum NewAttachment {
+    /// An image uploaded with `POST /account/users/conversations/attachments`
+    Image { id: ModelID },
+    /// A harvest listing shared in the message
+    Harvest { harvest_id: ModelID },
 }
 
 /// New Message cleaned data
@@ -45,8 +103,10 @@ pub struct NewMessageInsertData {
     pub sender_id: ModelID,
     pub receiver_id: ModelID,
     pub content: String,
+    pub order_id: Option<ModelID>,
     pub sent_at: OffsetDateTime,
     pub status: NewMessageStatusInsertData,
+    pub attachments: Vec<NewAttachment>,
 }
 
 /// New Message metadata
@@ -59,6 +119,14 @@ pub struct NewMessageStatusInsertData {
 }
 
 impl NewMessage {
+    /// Validates the message has content or attachments,
+    /// and not more than `MESSAGE_MAX_ATTACHMENTS` attachments.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        let has_content = !self.content.trim().is_empty() || !self.attachments.is_empty();
+        has_content && self.attachments.len() <= MESSAGE_MAX_ATTACHMENTS.into()
+    }
+
     /// Convert `Self` into `NewMessageInsertData`
     #[must_use]
     pub fn insert_data(self, user_id: ModelID) -> NewMessageInsertData {
@@ -68,8 +136,10 @@ impl NewMessage {
             sender_id: user_id,
             receiver_id: ModelID::from_str_unchecked(self.receiver_id),
             content: self.content,
+            order_id: self.order_id,
             sent_at: OffsetDateTime::now_utc(),
             status: NewMessageStatusInsertData::new(message_id),
+            attachments: self.attachments.into_iter().unique().collect(),
         }
     }
 }
@@ -83,9 +153,13 @@ impl NewMessageInsertData {
             sender_id: self.sender_id,
             receiver_id: self.receiver_id,
             content: self.content.clone(),
+            order_id: self.order_id,
             sent_at: self.sent_at,
             is_author: false,
             is_read: false,
+            read_at: None,
+            delivered_at: None,
+            attachments: Vec::new(),
         }
     }
 }
@@ -96,7 +170,7 @@ impl NewMessageStatusInsertData {
     pub fn new(message_id: ModelID) -> Self {
         Self {
             message_id,
-            is_read: true,
+            is_read: false,
             sender_has_deleted: false,
             receiver_has_deleted: false,
         }
@@ -111,6 +185,21 @@ pub struct MessageIsRead {
     pub messages: Vec<ModelID>,
 }
 
+/// Update messages are delivered ws request
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageIsDelivered {
+    pub sender_id: ModelID,
+    pub messages: Vec<ModelID>,
+}
+
+/// Typing started or stopped ws request
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Typing {
+    pub receiver_id: ModelID,
+}
+
 /// Delete direct message ws request
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -118,16 +207,62 @@ pub struct MessageDelete {
     pub message_id: ModelID,
 }
 
+/// `chat` and `chat/events` query parameters.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatResumeQuery {
+    /// The last message the client received, the messages received
+    /// after it are replayed before the live messages.
+    #[serde(default)]
+    pub after: Option<ModelID>,
+}
+
+/// `conversations/:other_user_id/messages` query parameters.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageHistoryQuery {
+    /// Messages sent before this message are returned,
+    /// the most recent messages are returned if not set.
+    #[serde(default)]
+    pub before: Option<ModelID>,
+    /// maximum number of messages should be returned
+    #[serde(default = "default_messages_len_limit")]
+    pub limit: usize,
+}
+
+/// Default maximum number of messages returned `30`
+const fn default_messages_len_limit() -> usize {
+    30
+}
+
+impl MessageHistoryQuery {
+    /// Validates the query parameters
+    pub fn validate(&self) -> EndpointResult<()> {
+        if self.limit == 0 || self.limit > MESSAGE_HISTORY_MAX_LIMIT {
+            return Err(EndpointRejection::BadRequest(
+                format!("limit must be between 1 and {MESSAGE_HISTORY_MAX_LIMIT}").into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// An error sent to the user which
 /// encountered while working with an `IncomingMessage`.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 // #[serde(tag = "code")]
 pub enum IncomingMessageError {
     /// `IncomingMessage` could not be deserialized error.
     UnprocessableEntity,
     /// Server error occurs while working with an `IncomingMessage`.
     InternalServerError,
-    // NotFound,
-    // Forbidden,
+    /// The user is not allowed to perform the `IncomingMessage`.
+    Forbidden,
+    /// A resource referenced by the `IncomingMessage` was not found.
+    NotFound,
+    /// The user blocked the receiver or was blocked by the receiver.
+    Blocked,
+    /// The user started the maximum number of conversations allowed per day.
+    TooManyConversations,
     // BadRequest(String),
 }
@@ -1,27 +1,49 @@
 //! `DirectMessage` system impls
 
+use std::{convert::Infallible, path::PathBuf};
+
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Multipart, Path, Query, Request, State,
+    },
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
     },
-    response::IntoResponse,
     Json,
 };
 use futures_util::{
-    stream::{SplitSink, SplitStream, StreamExt},
+    stream::{self, SplitSink, SplitStream, Stream, StreamExt},
     SinkExt,
 };
+use tokio::time::{interval_at, timeout, Instant, MissedTickBehavior};
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
 
 use crate::{
+    accounts::blocked_users::models::BlockedUser,
     auth::CurrentUser,
     endpoint::{EndpointRejection, EndpointResult},
+    files,
     server::state::{DatabaseConnection, ServerState},
+    services::produce::order::models::Order,
+    settings::MESSAGE_UPLOAD_DIR,
+    types::ModelID,
+    CHAT_IDLE_TIMEOUT, CHAT_PING_INTERVAL,
 };
 
 use super::{
-    forms::{IncomingMessage, IncomingMessageError},
-    models::{Conversation, Conversations},
+    forms::{
+        ChatResumeQuery, ForwardMessage, IncomingMessage, IncomingMessageError,
+        MessageHistoryQuery, MessageIsDelivered, NewMessage, NewMessageInsertData,
+    },
+    models::{
+        Conversation, ConversationSummary, ConversationSummaryList, Conversations,
+        MessageAttachment, MessageHistory,
+    },
+    utils::attach_message_attachments,
     BroadcastMessage, ChatFeed, MessageListener,
 };
 
@@ -37,32 +59,191 @@ pub async fn user_conversations(
     )
 }
 
+/// Handles the `GET /account/users/conversations` route.
+#[tracing::instrument(skip(db))]
+pub async fn conversation_list(
+    user: CurrentUser,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<ConversationSummaryList>> {
+    let conversations = ConversationSummary::records(user.id, db).await?;
+    Ok(Json(conversations))
+}
+
+/// Handles the `GET /account/users/conversations/:other_user_id/messages` route.
+#[tracing::instrument(skip(db))]
+pub async fn conversation_messages(
+    user: CurrentUser,
+    Path(other_user_id): Path<ModelID>,
+    Query(query): Query<MessageHistoryQuery>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<MessageHistory>> {
+    query.validate()?;
+    let history = MessageHistory::find(user.id, other_user_id, &query, db).await?;
+    Ok(Json(history))
+}
+
+/// Handles the `POST /account/users/conversations/attachments` route.
+///
+/// Uploads images to be sent as direct message attachments.
+#[tracing::instrument(skip(db, multipart))]
+pub async fn message_attachment_uploads(
+    user: CurrentUser,
+    State(db): State<DatabaseConnection>,
+    multipart: Multipart,
+) -> EndpointResult<Json<Vec<MessageAttachment>>> {
+    let (handler, mut uploads) = files::accept_uploads(multipart, crate::MESSAGE_MAX_ATTACHMENTS);
+
+    // Receive images
+    tokio::spawn(async move { handler.accept().await });
+
+    let mut image_ids = Vec::with_capacity(crate::MESSAGE_MAX_ATTACHMENTS.into());
+    while let Some(file) = uploads.files().await {
+        // Save an image to the file system
+        image_ids.push(file.id);
+        file.queue_image(MESSAGE_UPLOAD_DIR, db.clone()).await?;
+    }
+    if image_ids.is_empty() {
+        return Err(EndpointRejection::BadRequest(
+            "Message images not received".into(),
+        ));
+    }
+
+    let images = MessageAttachment::insert_images(user.id, image_ids, db).await?;
+    Ok(Json(images))
+}
+
+/// Handles the `GET /account/users/conversations/attachments/:file_name` route.
+///
+/// Serves a message image to its uploader, the users it was sent between
+/// and staff when the conversation was reported.
+#[tracing::instrument(skip(db, request))]
+pub async fn message_attachment_image(
+    user: CurrentUser,
+    Path(file_name): Path<String>,
+    State(db): State<DatabaseConnection>,
+    request: Request,
+) -> EndpointResult<Response> {
+    let not_found = || EndpointRejection::NotFound("Message image not found.".into());
+
+    // Images are saved in all the output formats under the attachment id
+    let Some((image_id, ext)) = file_name.split_once('.') else {
+        return Err(not_found());
+    };
+    let Ok(image_id) = ModelID::try_from(image_id) else {
+        return Err(not_found());
+    };
+    if !crate::IMAGE_OUTPUT_FORMATS
+        .iter()
+        .any(|fmt| fmt.extensions_str()[0] == ext)
+    {
+        return Err(not_found());
+    }
+    let can_view = MessageAttachment::can_view_image(user.id, image_id, db.clone()).await?
+        // Staff view the images of reported conversations
+        || (user.is_staff && MessageAttachment::is_reported(image_id, db).await?);
+    if !can_view {
+        return Err(not_found());
+    }
+
+    let path = PathBuf::from(MESSAGE_UPLOAD_DIR).join(format!("{image_id}.{ext}"));
+    let mut response = ServeFile::new(path)
+        .oneshot(request)
+        .await
+        .unwrap_or_else(|err| match err {});
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, max-age=86400"),
+    );
+    Ok(response.into_response())
+}
+
 /// Sets up direct message chat system.
+///
+/// A reconnecting client passes the last message it received as `after`
+/// to have the messages it missed replayed.
 #[allow(clippy::unused_async)]
 pub async fn direct_message_websocket(
     ws: WebSocketUpgrade,
     user: CurrentUser,
+    Query(query): Query<ChatResumeQuery>,
     State(state): State<ServerState>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| direct_message_handler(socket, user, state))
+    ws.on_upgrade(move |socket| direct_message_handler(socket, user, query.after, state))
+}
+
+/// Handles the `GET /account/users/chat/events` route.
+///
+/// Server-sent events fallback of the chat websocket, streams the same
+/// messages as the websocket. A reconnecting client resumes after the
+/// `after` message or the `Last-Event-ID` header the browser sends.
+pub async fn direct_message_events(
+    user: CurrentUser,
+    Query(query): Query<ChatResumeQuery>,
+    headers: HeaderMap,
+    State(state): State<ServerState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let user_id = user.id;
+    let chat = state.chat_feed();
+    let after = query.after.or_else(|| {
+        headers
+            .get("last-event-id")
+            .and_then(|id| id.to_str().ok())
+            .and_then(|id| ModelID::try_from(id).ok())
+    });
+
+    // Broadcast user connected
+    chat.user_connected(user_id).await;
+    let listener = chat.resume(user_id, after, state.database()).await;
+    let connection = ChatConnectionGuard { user_id, chat };
+
+    // The stream is dropped once the client disconnects
+    let events = stream::unfold(
+        (listener, connection),
+        |(mut listener, connection)| async move {
+            let msg = listener.listen().await?;
+            let mut event = Event::default().data(serde_json::to_string(&msg).unwrap());
+            if let ForwardMessage::DirectMessage(direct_msg) = &msg {
+                event = event.id(direct_msg.id.to_string());
+            }
+            Some((Ok(event), (listener, connection)))
+        },
+    );
+    Sse::new(events).keep_alive(KeepAlive::new().interval(CHAT_PING_INTERVAL))
+}
+
+/// Handles the `POST /account/users/chat/messages` route.
+///
+/// Processes a message sent by a client receiving the chat over server-sent events,
+/// the results are sent on the events stream as they are on the websocket.
+#[tracing::instrument(skip(user, state, msg))]
+pub async fn direct_message_send(
+    user: CurrentUser,
+    State(state): State<ServerState>,
+    msg: IncomingMessage,
+) -> StatusCode {
+    process_incoming_message(user, msg, state.chat_feed(), state.database()).await;
+    StatusCode::ACCEPTED
 }
 
 /// Manages connected user chat.
-async fn direct_message_handler(stream: WebSocket, user: CurrentUser, state: ServerState) {
+async fn direct_message_handler(
+    stream: WebSocket,
+    user: CurrentUser,
+    after: Option<ModelID>,
+    state: ServerState,
+) {
     let user_id = user.id;
     let chat = state.chat_feed();
     let (outgoing, incoming) = stream.split();
 
     // Broadcast user connected
-    chat.broadcast(BroadcastMessage::user_connected(user_id));
-
-    // Listens for message sent in the message queue
-    // and forward them to user if it is intended for them.
-    let mut send_messages = tokio::spawn({
-        let user = user.clone();
-        let message_listener = state.chat_feed_as_ref().subscribe();
-        async move { listen_send_messages(user, message_listener, outgoing).await }
-    });
+    chat.user_connected(user_id).await;
+
+    // Listens for messages sent to the user
+    // and forward them to this connection.
+    let message_listener = chat.resume(user_id, after, state.database()).await;
+    let mut send_messages =
+        tokio::spawn(async move { listen_send_messages(message_listener, outgoing).await });
 
     // Receive incoming messages sent by user via websocket
     // and process them.
@@ -78,29 +259,38 @@ async fn direct_message_handler(stream: WebSocket, user: CurrentUser, state: Ser
     };
 
     // Broadcast user disconnected
-    chat.broadcast(BroadcastMessage::user_disconnected(user_id));
+    chat.user_disconnected(user_id).await;
 }
 
-/// Listens for `BroadcastsMessage`s sent in message queue
-/// and forward them user via ws if it is intended for them.
+/// Listens for `ForwardMessage`s sent to the user connection
+/// and forward them to the user via ws, pings the user
+/// every `CHAT_PING_INTERVAL`.
 async fn listen_send_messages(
-    user: CurrentUser,
     mut messages: MessageListener,
     mut outgoing: SplitSink<WebSocket, Message>,
 ) {
-    while let Ok(msg) = messages.listen().await {
-        // Forward the message if it is intended for this user.
-        if let Some(forward_msg) = msg.message(user.id) {
-            let forward_msg = serde_json::to_string(&forward_msg).unwrap();
-            if outgoing.send(Message::Text(forward_msg)).await.is_err() {
-                break;
-            }
+    let mut ping = interval_at(Instant::now() + CHAT_PING_INTERVAL, CHAT_PING_INTERVAL);
+    ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        let outgoing_msg = tokio::select! {
+            msg = messages.listen() => match msg {
+                Some(msg) => Message::Text(serde_json::to_string(&msg).unwrap()),
+                None => break,
+            },
+            _ = ping.tick() => Message::Ping(Vec::new()),
+        };
+        if outgoing.send(outgoing_msg).await.is_err() {
+            break;
         }
     }
 }
 
 /// Receive `IncomingMessage`s sent by the user via ws
 /// and broadcast them to subscribed message listeners.
+///
+/// The connection is closed if the user sends nothing,
+/// pongs included, for `CHAT_IDLE_TIMEOUT`.
 async fn recv_broadcast_messages(
     user: CurrentUser,
     chat: ChatFeed,
@@ -108,10 +298,22 @@ async fn recv_broadcast_messages(
     db: DatabaseConnection,
 ) {
     // Listens for incoming message and process them.
-    while let Some(Ok(Message::Text(msg))) = incoming.next().await {
+    loop {
+        let msg = match timeout(CHAT_IDLE_TIMEOUT, incoming.next()).await {
+            Ok(Some(Ok(Message::Text(msg)))) => msg,
+            // Pings are answered automatically, pongs keep the connection alive
+            Ok(Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Binary(_)))) => continue,
+            Ok(Some(Ok(Message::Close(_)) | Err(_)) | None) => break,
+            Err(_elapsed) => {
+                tracing::debug!("Chat connection of user {} timed out", user.id);
+                break;
+            }
+        };
         let msg_result: Result<IncomingMessage, _> = serde_json::from_str(&msg);
         match msg_result {
-            Ok(msg) => process_incoming_message(user.clone(), msg, chat.clone(), db.clone()).await,
+            Ok(msg) => {
+                process_incoming_message(user.clone(), msg, chat.clone(), db.clone()).await;
+            }
             Err(err) => {
                 tracing::error!("IncomingMessage deserialization error: {:?}", err);
                 chat.broadcast(BroadcastMessage::message_error(
@@ -123,6 +325,20 @@ async fn recv_broadcast_messages(
     }
 }
 
+/// Broadcasts the user disconnected when the events stream is dropped
+struct ChatConnectionGuard {
+    user_id: ModelID,
+    chat: ChatFeed,
+}
+
+impl Drop for ChatConnectionGuard {
+    fn drop(&mut self) {
+        let chat = self.chat.clone();
+        let user_id = self.user_id;
+        tokio::spawn(async move { chat.user_disconnected(user_id).await });
+    }
+}
+
 // Process IncomingMessages
 async fn process_incoming_message(
     user: CurrentUser,
@@ -132,12 +348,29 @@ async fn process_incoming_message(
 ) {
     match msg {
         IncomingMessage::NewMessage(new_msg) => {
-            let insert_data = new_msg.insert_data(user.id);
-            let direct_msg = insert_data.direct_message();
-            match Conversation::insert(insert_data, db).await {
-                Ok(_) => chat.broadcast(BroadcastMessage::direct_message(
-                    direct_msg.receiver_id,
-                    direct_msg,
+            // The message received ends the typing indicator
+            if let Ok(receiver_id) = ModelID::try_from(new_msg.receiver_id.as_str()) {
+                chat.typing().stop(user.id, receiver_id);
+            }
+            process_new_message(user, new_msg, chat, db).await;
+        }
+
+        IncomingMessage::MessageIsDelivered(msg_delivered) => {
+            match Conversation::update_is_delivered(
+                user.id,
+                msg_delivered.sender_id,
+                msg_delivered.messages,
+                db,
+            )
+            .await
+            {
+                Ok(messages) if messages.is_empty() => {}
+                Ok(messages) => chat.broadcast(BroadcastMessage::message_is_delivered(
+                    msg_delivered.sender_id,
+                    MessageIsDelivered {
+                        sender_id: msg_delivered.sender_id,
+                        messages,
+                    },
                 )),
                 Err(_err) => {
                     chat.broadcast(BroadcastMessage::message_error(
@@ -148,6 +381,38 @@ async fn process_incoming_message(
             }
         }
 
+        IncomingMessage::TypingStarted(msg) => {
+            if chat.typing().start(user.id, msg.receiver_id) {
+                // Users that blocked each other do not see each other typing
+                match BlockedUser::is_blocked_between(user.id, msg.receiver_id, db).await {
+                    Ok(false) => {
+                        chat.broadcast(BroadcastMessage::typing_started(msg.receiver_id, user.id));
+                    }
+                    Ok(true) | Err(_) => {
+                        chat.typing().stop(user.id, msg.receiver_id);
+                    }
+                }
+            }
+        }
+
+        IncomingMessage::TypingStopped(msg) => {
+            if chat.typing().stop(user.id, msg.receiver_id) {
+                chat.broadcast(BroadcastMessage::typing_stopped(msg.receiver_id, user.id));
+            }
+        }
+
+        IncomingMessage::PresenceQuery => match chat.online_contacts(user.id, db).await {
+            Ok(online_contacts) => {
+                chat.broadcast(BroadcastMessage::presence(user.id, online_contacts));
+            }
+            Err(_err) => {
+                chat.broadcast(BroadcastMessage::message_error(
+                    user.id,
+                    IncomingMessageError::InternalServerError,
+                ));
+            }
+        },
+
         IncomingMessage::MessageIsRead(msg_read_update) => {
             let message_ids = msg_read_update.messages.clone();
             match Conversation::update_is_read(user.id, message_ids, db).await {
@@ -185,3 +450,91 @@ async fn process_incoming_message(
         }
     }
 }
+
+// Process NewMessage, the message is sent to the receiver once saved
+async fn process_new_message(
+    user: CurrentUser,
+    new_msg: NewMessage,
+    chat: ChatFeed,
+    db: DatabaseConnection,
+) {
+    if !new_msg.is_valid() {
+        chat.broadcast(BroadcastMessage::message_error(
+            user.id,
+            IncomingMessageError::UnprocessableEntity,
+        ));
+        return;
+    }
+    let insert_data = new_msg.insert_data(user.id);
+    let mut direct_msg = insert_data.direct_message();
+
+    if let Err(err) = validate_new_message(&insert_data, db.clone()).await {
+        chat.broadcast(BroadcastMessage::message_error(user.id, err));
+        return;
+    }
+
+    let has_attachments = !insert_data.attachments.is_empty();
+    let inserted = match Conversation::insert(insert_data, db.clone()).await {
+        Ok(None) => {
+            chat.broadcast(BroadcastMessage::message_error(
+                user.id,
+                IncomingMessageError::TooManyConversations,
+            ));
+            return;
+        }
+        Ok(Some(_)) if has_attachments => {
+            attach_message_attachments(std::iter::once(&mut direct_msg), db).await
+        }
+        Ok(Some(_)) => Ok(()),
+        Err(err) => Err(err),
+    };
+    match inserted {
+        Ok(()) => chat.broadcast(BroadcastMessage::direct_message(
+            direct_msg.receiver_id,
+            direct_msg,
+        )),
+        Err(_err) => {
+            chat.broadcast(BroadcastMessage::message_error(
+                user.id,
+                IncomingMessageError::InternalServerError,
+            ));
+        }
+    }
+}
+
+// Checks the sender is allowed to send the message to the receiver
+async fn validate_new_message(
+    msg: &NewMessageInsertData,
+    db: DatabaseConnection,
+) -> Result<(), IncomingMessageError> {
+    let internal_error = |_err| IncomingMessageError::InternalServerError;
+
+    // Users that blocked each other can not message each other
+    if BlockedUser::is_blocked_between(msg.sender_id, msg.receiver_id, db.clone())
+        .await
+        .map_err(internal_error)?
+    {
+        return Err(IncomingMessageError::Blocked);
+    }
+
+    if let Some(order_id) = msg.order_id {
+        // Messages about an order can only be sent between the order buyer and farmer
+        if !Order::is_between(order_id, msg.sender_id, msg.receiver_id, db.clone())
+            .await
+            .map_err(internal_error)?
+        {
+            return Err(IncomingMessageError::Forbidden);
+        }
+    }
+
+    // Images must be uploaded by the sender and harvests still listed
+    if !msg.attachments.is_empty()
+        && !MessageAttachment::can_attach(msg.sender_id, &msg.attachments, db)
+            .await
+            .map_err(internal_error)?
+    {
+        return Err(IncomingMessageError::NotFound);
+    }
+
+    Ok(())
+}
@@ -5,6 +5,9 @@ pub mod db;
 pub mod forms;
 pub mod handlers;
 pub mod models;
+mod registry;
 mod utils;
 
-pub use chat::{BroadcastMessage, ChatFeed, MessageListener};
+pub use chat::{BroadcastMessage, ChatFeed};
+pub use registry::MessageListener;
+pub use utils::attach_message_attachments;
@@ -1,47 +1,80 @@
 //! Direct Message models impls
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
-use crate::types::ModelID;
+use crate::{
+    accounts::user::models::UserIndex, services::produce::harvest::models::HarvestIndex,
+    types::ModelID,
+};
 
 /// Direct Message sent between two users.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DirectMessage {
     pub id: ModelID,
     pub sender_id: ModelID,
     pub receiver_id: ModelID,
     pub content: String,
+    /// The order the message is about
+    pub order_id: Option<ModelID>,
     pub sent_at: OffsetDateTime,
     pub is_author: bool,
     pub is_read: bool,
+    /// When the receiver read the message
+    pub read_at: Option<OffsetDateTime>,
+    /// When the receiver's client received the message
+    pub delivered_at: Option<OffsetDateTime>,
+    pub attachments: Vec<MessageAttachment>,
 }
 
 impl DirectMessage {
-    /// Creates a new `DirectMessage` from the database row
+    /// Creates a new `DirectMessage` from the database row,
+    /// attachments are fetched separately.
+    #[allow(clippy::too_many_arguments)]
     #[must_use]
     pub fn from_row(
         id: ModelID,
         sender_id: ModelID,
         receiver_id: ModelID,
         content: String,
+        order_id: Option<ModelID>,
         sent_at: OffsetDateTime,
         is_author: bool,
         is_read: bool,
+        read_at: Option<OffsetDateTime>,
+        delivered_at: Option<OffsetDateTime>,
     ) -> Self {
         Self {
             id,
             sender_id,
             receiver_id,
             content,
+            order_id,
             sent_at,
             is_author,
             is_read,
+            read_at,
+            delivered_at,
+            attachments: Vec::new(),
         }
     }
 }
 
+/// A file or listing attached to a direct message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MessageAttachment {
+    /// An image uploaded by the sender
+    Image { id: ModelID, image: String },
+    /// A harvest listing shared by the sender,
+    /// `harvest` is not set if the listing was deleted.
+    Harvest {
+        id: ModelID,
+        harvest: Option<Box<HarvestIndex>>,
+    },
+}
+
 // ===== Conversation impls =====
 
 /// A list of Conversations the user had
@@ -94,3 +127,73 @@ impl Conversation {
             .map_or(OffsetDateTime::UNIX_EPOCH, |msg| msg.sent_at)
     }
 }
+
+// ===== Message history impls =====
+
+/// A page of direct messages of a conversation,
+/// oldest message first.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageHistory {
+    pub messages: Vec<DirectMessage>,
+    // Used for querying the previous messages.
+    // if `before` is `None`, the conversation has no older messages.
+    pub before: Option<ModelID>,
+}
+
+impl MessageHistory {
+    /// Creates a new `MessageHistory` from messages ordered by the
+    /// most recent first, fetched with one extra message.
+    #[must_use]
+    pub fn from_row(mut messages: Vec<DirectMessage>, limit: usize) -> Self {
+        let before = if messages.len() > limit {
+            messages.truncate(limit);
+            messages.last().map(|msg| msg.id)
+        } else {
+            None
+        };
+        messages.reverse(); // latest messages at the end
+        Self { messages, before }
+    }
+}
+
+// ===== Conversation summary impls =====
+
+/// A conversation listed in the user conversations,
+/// ordered by the most recent message first.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSummary {
+    pub participant: UserIndex,
+    pub last_message: DirectMessage,
+    /// Number of messages the participant sent the user has not read
+    pub unread_count: i64,
+}
+
+pub type ConversationSummaryList = Vec<ConversationSummary>;
+
+// ===== Chat instance impls =====
+
+/// A server instance sharing the chat feed with the other instances
+#[derive(Debug, Clone, Copy)]
+pub struct ChatInstance {
+    pub id: ModelID,
+    pub started_at: OffsetDateTime,
+}
+
+impl ChatInstance {
+    /// Creates a new `ChatInstance`
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            id: ModelID::new(),
+            started_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+impl Default for ChatInstance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,177 @@
+//! Chat connections registry impls
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::{types::ModelID, CHAT_CONNECTION_CAPACITY};
+
+use super::{forms::ForwardMessage, models::DirectMessage};
+
+/// Chat connections of the users connected to this server instance,
+/// a user has a connection for each open tab.
+#[derive(Debug, Clone, Default)]
+pub struct ChatRegistry(Arc<RwLock<HashMap<ModelID, Vec<ChatConnection>>>>);
+
+/// A user chat connection
+#[derive(Debug, Clone)]
+struct ChatConnection {
+    id: ModelID,
+    sender: mpsc::Sender<ForwardMessage>,
+    /// Set when messages were dropped because the connection fell behind
+    lagged: Arc<AtomicBool>,
+}
+
+impl ChatRegistry {
+    /// Registers a new connection of the user,
+    /// return the listener receiving the messages sent to the user.
+    #[must_use]
+    pub fn connect(&self, user_id: ModelID) -> MessageListener {
+        let (sender, receiver) = mpsc::channel(CHAT_CONNECTION_CAPACITY);
+        let connection = ChatConnection {
+            id: ModelID::new(),
+            sender,
+            lagged: Arc::new(AtomicBool::new(false)),
+        };
+        let listener = MessageListener {
+            user_id,
+            connection_id: connection.id,
+            receiver,
+            lagged: connection.lagged.clone(),
+            replay: VecDeque::new(),
+            replayed: HashSet::new(),
+            registry: self.clone(),
+        };
+        self.0
+            .write()
+            .unwrap()
+            .entry(user_id)
+            .or_default()
+            .push(connection);
+        listener
+    }
+
+    /// Removes the user connection
+    fn disconnect(&self, user_id: ModelID, connection_id: ModelID) {
+        let mut users = self.0.write().unwrap();
+        if let Some(connections) = users.get_mut(&user_id) {
+            connections.retain(|connection| connection.id != connection_id);
+            if connections.is_empty() {
+                users.remove(&user_id);
+            }
+        }
+    }
+
+    /// Returns the users connected to this server instance
+    #[must_use]
+    pub fn users(&self) -> Vec<ModelID> {
+        self.0.read().unwrap().keys().copied().collect()
+    }
+
+    /// Returns the users connected to this server instance
+    /// with their number of connections
+    #[must_use]
+    pub fn connections(&self) -> Vec<(ModelID, usize)> {
+        self.0
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(user_id, connections)| (*user_id, connections.len()))
+            .collect()
+    }
+
+    /// Returns true if the user is connected to this server instance
+    #[must_use]
+    pub fn is_connected(&self, user_id: ModelID) -> bool {
+        self.0.read().unwrap().contains_key(&user_id)
+    }
+
+    /// Sends the message to all connections of the user,
+    /// connections that fell behind are marked as lagged and the message is dropped.
+    pub fn send(&self, user_id: ModelID, message: &ForwardMessage) {
+        let users = self.0.read().unwrap();
+        let Some(connections) = users.get(&user_id) else {
+            return;
+        };
+        for connection in connections {
+            match connection.sender.try_send(message.clone()) {
+                Ok(()) | Err(TrySendError::Closed(_)) => {}
+                Err(TrySendError::Full(_)) => {
+                    if !connection.lagged.swap(true, Ordering::Relaxed) {
+                        tracing::warn!("Chat connection of user {user_id} lagged");
+                    }
+                }
+            }
+        }
+        drop(users);
+    }
+}
+
+// ===== MessageListener impls =====
+
+/// Listens for `ForwardMessage`s sent to a user connection,
+/// the connection is removed from the registry when the listener is dropped.
+#[derive(Debug)]
+pub struct MessageListener {
+    user_id: ModelID,
+    connection_id: ModelID,
+    receiver: mpsc::Receiver<ForwardMessage>,
+    lagged: Arc<AtomicBool>,
+    /// Messages returned before the messages sent to the connection
+    replay: VecDeque<ForwardMessage>,
+    /// Replayed direct messages that may also be sent to the connection
+    replayed: HashSet<ModelID>,
+    registry: ChatRegistry,
+}
+
+impl MessageListener {
+    /// Queues the messages the user received while the client was disconnected,
+    /// they are returned before the messages sent to the connection.
+    pub fn replay(&mut self, messages: Vec<DirectMessage>) {
+        for msg in messages {
+            self.replayed.insert(msg.id);
+            self.replay.push_back(ForwardMessage::DirectMessage(msg));
+        }
+    }
+
+    /// Queues `Resync` for a client that missed messages that can't be replayed
+    pub fn resync(&mut self) {
+        self.replay.push_back(ForwardMessage::Resync);
+    }
+
+    /// Listens for `ForwardMessage` sent to the user connection,
+    /// return `Resync` in place of the messages dropped while the connection lagged.
+    pub async fn listen(&mut self) -> Option<ForwardMessage> {
+        if let Some(message) = self.replay.pop_front() {
+            return Some(message);
+        }
+        loop {
+            let message = self.receiver.recv().await?;
+            if self.lagged.swap(false, Ordering::Relaxed) {
+                // The remaining buffered messages are older than the dropped ones,
+                // the client refetches its conversations instead.
+                while self.receiver.try_recv().is_ok() {}
+                return Some(ForwardMessage::Resync);
+            }
+            // Messages sent while the replay was fetched are already replayed
+            if let ForwardMessage::DirectMessage(msg) = &message {
+                if self.replayed.remove(&msg.id) {
+                    continue;
+                }
+            }
+            return Some(message);
+        }
+    }
+}
+
+impl Drop for MessageListener {
+    fn drop(&mut self) {
+        self.registry.disconnect(self.user_id, self.connection_id);
+    }
+}
@@ -1,8 +1,25 @@
 //! Direct Message helpers impls
 
-use crate::{error::ServerResult, server::state::DatabaseConnection, types::ModelID};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use super::forms::NewMessageStatusInsertData;
+use crate::{
+    endpoint::EndpointRejection,
+    error::{ServerError, ServerResult},
+    files,
+    server::state::DatabaseConnection,
+    settings::MESSAGE_UPLOAD_DIR,
+    types::ModelID,
+    CHAT_TYPING_THROTTLE,
+};
+
+use super::{
+    forms::{NewAttachment, NewMessageStatusInsertData},
+    models::{DirectMessage, MessageAttachment},
+};
 
 /// Insert message status into the database
 pub async fn insert_message_status(
@@ -41,21 +58,16 @@ pub async fn insert_message_status(
     }
 }
 
-/// Fetch message delete status from database
-pub async fn find_message_status(
+/// Fetch message sender and receiver from database
+pub async fn find_message_participants(
     message_id: ModelID,
     db: DatabaseConnection,
-) -> ServerResult<MessageDeleteStatus> {
+) -> ServerResult<MessageParticipants> {
     match sqlx::query!(
         r#"
             SELECT message.sender_id,
-                message.receiver_id,
-                status.sender_has_deleted,
-                status.receiver_has_deleted
+                message.receiver_id
             FROM features.direct_messages message
-            LEFT JOIN features.message_status status
-                on message.id = status.message_id
-
             WHERE message.id = $1;
         "#,
         message_id.0
@@ -63,15 +75,13 @@ pub async fn find_message_status(
     .fetch_one(&db.pool)
     .await
     {
-        Ok(rec) => Ok(MessageDeleteStatus::from_row(
+        Ok(rec) => Ok(MessageParticipants::from_row(
             rec.sender_id.into(),
             rec.receiver_id.into(),
-            rec.sender_has_deleted,
-            rec.receiver_has_deleted,
         )),
         Err(err) => {
             tracing::error!(
-                "Database error, failed to fetch message delete status: {}",
+                "Database error, failed to fetch message participants: {}",
                 err
             );
             Err(err.into())
@@ -80,28 +90,19 @@ pub async fn find_message_status(
 }
 
 /// Helper struct used for carrying information
-/// for whether the message can be deleted
+/// for whether the user can delete the message
 #[derive(Debug, Clone)]
-pub struct MessageDeleteStatus {
+pub struct MessageParticipants {
     pub sender_id: ModelID,
     pub receiver_id: ModelID,
-    pub sender_has_deleted: bool,
-    pub receiver_has_deleted: bool,
 }
 
-impl MessageDeleteStatus {
-    /// Creates a new `MessageDeleteStatus` from the database row
-    pub fn from_row(
-        sender_id: ModelID,
-        receiver_id: ModelID,
-        sender_has_deleted: bool,
-        receiver_has_deleted: bool,
-    ) -> Self {
+impl MessageParticipants {
+    /// Creates a new `MessageParticipants` from the database row
+    pub const fn from_row(sender_id: ModelID, receiver_id: ModelID) -> Self {
         Self {
             sender_id,
             receiver_id,
-            sender_has_deleted,
-            receiver_has_deleted,
         }
     }
 }
@@ -142,3 +143,185 @@ pub async fn message_receiver_count(
         }
     }
 }
+
+/// Insert the message attachments into the database,
+/// images uploaded by the sender are claimed by the message.
+pub async fn insert_message_attachments(
+    message_id: ModelID,
+    sender_id: ModelID,
+    attachments: &[NewAttachment],
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> ServerResult<()> {
+    let mut images = Vec::new();
+    let mut harvests = Vec::new();
+    for attachment in attachments {
+        match attachment {
+            NewAttachment::Image { id } => images.push(id.0),
+            NewAttachment::Harvest { harvest_id } => harvests.push(harvest_id.0),
+        }
+    }
+
+    if !images.is_empty() {
+        match sqlx::query!(
+            r#"
+                UPDATE features.message_attachments attachment
+                    SET message_id = $1
+                WHERE attachment.id = ANY($2)
+                    AND attachment.uploader_id = $3
+                    AND attachment.kind = 'image'
+                    AND attachment.message_id IS NULL;
+            "#,
+            message_id.0,
+            &images[..],
+            sender_id.0,
+        )
+        .execute(&mut **tx)
+        .await
+        {
+            Ok(result) if result.rows_affected() == images.len() as u64 => {
+                tracing::trace!(
+                    "Message images successfully claimed, but transaction not committed: {:?}",
+                    result
+                );
+            }
+            Ok(result) => {
+                tracing::error!(
+                    "Message images could not be claimed, {} of {} claimed",
+                    result.rows_affected(),
+                    images.len()
+                );
+                return Err(ServerError::rejection(EndpointRejection::forbidden()));
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to claim message images: {}", err);
+                return Err(err.into());
+            }
+        }
+    }
+
+    if !harvests.is_empty() {
+        let ids: Vec<_> = harvests.iter().map(|_| ModelID::new().0).collect();
+        match sqlx::query!(
+            r#"
+                INSERT INTO features.message_attachments(
+                    id,
+                    message_id,
+                    uploader_id,
+                    kind,
+                    harvest_id,
+                    created_at
+                )
+                SELECT attachment.id, $3, $4, 'harvest', attachment.harvest_id, $5
+                FROM unnest($1::uuid[], $2::uuid[]) AS attachment(id, harvest_id);
+            "#,
+            &ids[..],
+            &harvests[..],
+            message_id.0,
+            sender_id.0,
+            time::OffsetDateTime::now_utc(),
+        )
+        .execute(&mut **tx)
+        .await
+        {
+            Ok(result) => {
+                tracing::trace!(
+                    "Message harvests successfully inserted, but transaction not committed: {:?}",
+                    result
+                );
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to insert message harvests: {}", err);
+                return Err(err.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the attachments of the messages from the database
+/// and adds them to their messages.
+pub async fn attach_message_attachments<'a, I>(
+    messages: I,
+    db: DatabaseConnection,
+) -> ServerResult<()>
+where
+    I: IntoIterator<Item = &'a mut DirectMessage>,
+{
+    let mut messages: Vec<_> = messages.into_iter().collect();
+    if messages.is_empty() {
+        return Ok(());
+    }
+    let message_ids: Vec<_> = messages.iter().map(|msg| msg.id).collect();
+
+    let mut attachments: HashMap<ModelID, Vec<MessageAttachment>> = HashMap::new();
+    for (message_id, attachment) in MessageAttachment::records(&message_ids, db).await? {
+        attachments.entry(message_id).or_default().push(attachment);
+    }
+    for msg in &mut messages {
+        if let Some(message_attachments) = attachments.remove(&msg.id) {
+            msg.attachments = message_attachments;
+        }
+    }
+    Ok(())
+}
+
+/// Delete message image attachments from the file system
+///
+/// # Errors
+///
+/// Return an error if failed to delete files
+pub async fn delete_message_images(images: Vec<String>) -> ServerResult<()> {
+    let paths = images
+        .iter()
+        .flat_map(|file| files::saved_paths(MESSAGE_UPLOAD_DIR, file))
+        .collect();
+    files::delete_files(paths).await
+}
+
+/// Throttles the typing events the users forward to the receivers,
+/// shared by all the user chat connections to this server instance.
+#[derive(Debug, Clone, Default)]
+pub struct TypingThrottle(Arc<Mutex<HashMap<ModelID, HashMap<ModelID, Instant>>>>);
+
+impl TypingThrottle {
+    /// Return true if typing started should be forwarded to the receiver,
+    /// it's forwarded once per `CHAT_TYPING_THROTTLE`.
+    pub fn start(&self, user_id: ModelID, receiver_id: ModelID) -> bool {
+        let now = Instant::now();
+        let mut users = self.0.lock().unwrap();
+        let receivers = users.entry(user_id).or_default();
+        let forward = receivers
+            .get(&receiver_id)
+            .is_none_or(|forwarded_at| now.duration_since(*forwarded_at) >= CHAT_TYPING_THROTTLE);
+        if forward {
+            receivers.insert(receiver_id, now);
+        }
+        drop(users);
+        forward
+    }
+
+    /// Return true if typing stopped should be forwarded to the receiver,
+    /// only if typing started was forwarded.
+    pub fn stop(&self, user_id: ModelID, receiver_id: ModelID) -> bool {
+        let mut users = self.0.lock().unwrap();
+        let Some(receivers) = users.get_mut(&user_id) else {
+            return false;
+        };
+        let stopped = receivers.remove(&receiver_id).is_some();
+        if receivers.is_empty() {
+            users.remove(&user_id);
+        }
+        drop(users);
+        stopped
+    }
+
+    /// Returns the receivers the user is still typing to
+    pub fn receivers(&self, user_id: ModelID) -> Vec<ModelID> {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(&user_id)
+            .map(|receivers| receivers.into_keys().collect())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,243 @@
+//! Harvest wishlist database impl
+
+use std::collections::HashSet;
+
+use crate::{
+    endpoint::{EndpointRejection, EndpointResult},
+    error::ServerResult,
+    server::state::DatabaseConnection,
+    services::produce::harvest::models::HarvestIndex,
+    types::{ModelID, Pagination},
+};
+
+use super::{
+    forms::WishlistInsertData,
+    models::{WishlistHarvest, WishlistHarvestList},
+};
+
+impl WishlistHarvest {
+    /// Fetches user wishlist harvests from the database,
+    /// finished and archived harvests are returned as unavailable.
+    #[tracing::instrument(name = "Fetch User Wishlist", skip(db))]
+    pub async fn records(
+        user_id: ModelID,
+        pg: Pagination,
+        db: DatabaseConnection,
+    ) -> ServerResult<WishlistHarvestList> {
+        let (offset, limit) = pg.offset_limit();
+        match sqlx::query!(
+            r#"
+                SELECT harvest.id AS harvest_id,
+                    harvest.price AS harvest_price,
+                    harvest.harvest_date AS harvest_harvest_date,
+                    harvest.images AS harvest_images,
+                    services.harvest_low_stock(
+                        harvest.available_quantity,
+                        harvest.reserved_quantity,
+                        harvest.low_stock_threshold
+                    ) AS "harvest_low_stock!",
+                    (NOT harvest.finished
+                        AND NOT location_.deleted
+                        AND NOT farm.deleted
+                        AND farm.owner_id IS NOT NULL) AS "harvest_available!",
+                    cultivar.name AS cultivar_name,
+                    cultivar_category.name AS cultivar_category,
+                    cultivar.image AS cultivar_image,
+                    farm.name AS farm_name,
+                    farm.logo AS farm_logo,
+                    location_.place_name AS location_place_name,
+                    location_.coords AS location_coords,
+                    region.name AS "location_region?",
+                    country.name AS location_country,
+                    wishlist.created_at AS saved_at
+                FROM services.harvests_wishlist wishlist
+                INNER JOIN services.harvests harvest
+                    ON wishlist.harvest_id = harvest.id
+                INNER JOIN services.cultivars cultivar
+                    ON harvest.cultivar_id = cultivar.id
+                INNER JOIN services.cultivar_categories cultivar_category
+                    ON cultivar.category_id = cultivar_category.id
+                INNER JOIN services.locations location_
+                    ON harvest.location_id = location_.id
+                INNER JOIN services.farms farm
+                    ON location_.farm_id = farm.id
+                LEFT JOIN services.regions region
+                    ON location_.region_id = region.id
+                INNER JOIN services.countries country
+                    ON location_.country_id = country.id
+
+                WHERE wishlist.user_id = $1
+                ORDER BY wishlist.created_at DESC
+                LIMIT $2
+                OFFSET $3;
+            "#,
+            user_id.0,
+            limit,
+            offset,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let harvests = records
+                    .into_iter()
+                    .map(|rec| {
+                        let harvest = HarvestIndex::from_row(
+                            rec.harvest_id.into(),
+                            rec.harvest_price,
+                            rec.harvest_harvest_date,
+                            rec.harvest_images,
+                            rec.cultivar_name,
+                            rec.cultivar_category,
+                            rec.cultivar_image,
+                            rec.location_place_name,
+                            rec.location_region,
+                            rec.location_country,
+                            rec.location_coords,
+                            rec.farm_name,
+                            rec.farm_logo,
+                            rec.harvest_low_stock,
+                            rust_decimal::Decimal::ZERO,
+                        );
+                        Self::from_row(harvest, rec.harvest_available, rec.saved_at)
+                    })
+                    .collect();
+
+                Ok(harvests)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch user wishlist: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Inserts a harvest into the user wishlist,
+    /// saving an already saved harvest does nothing.
+    #[tracing::instrument(name = "Insert Wishlist Harvest", skip(db))]
+    pub async fn insert(wishlist: WishlistInsertData, db: DatabaseConnection) -> ServerResult<()> {
+        match sqlx::query!(
+            r#"
+                INSERT INTO services.harvests_wishlist(
+                    user_id,
+                    harvest_id,
+                    created_at
+                )
+                VALUES($1, $2, $3)
+                ON CONFLICT (user_id, harvest_id) DO NOTHING;
+            "#,
+            wishlist.user_id.0,
+            wishlist.harvest_id.0,
+            wishlist.created_at,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Harvest saved to wishlist successfully: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to save harvest to wishlist: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Removes a harvest from the user wishlist,
+    /// returns false if the harvest was not saved.
+    #[tracing::instrument(name = "Delete Wishlist Harvest", skip(db))]
+    pub async fn delete(
+        user_id: ModelID,
+        harvest_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                DELETE FROM services.harvests_wishlist wishlist
+                WHERE wishlist.user_id = $1
+                    AND wishlist.harvest_id = $2;
+            "#,
+            user_id.0,
+            harvest_id.0,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Harvest removed from wishlist: {:?}", result);
+                Ok(result.rows_affected() > 0)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to remove harvest from wishlist: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Fetches which of the `harvest_ids` are on the user wishlist
+    #[tracing::instrument(name = "Fetch Saved Harvest Ids", skip(db, harvest_ids))]
+    pub async fn saved_ids(
+        user_id: ModelID,
+        harvest_ids: &[uuid::Uuid],
+        db: DatabaseConnection,
+    ) -> ServerResult<HashSet<ModelID>> {
+        match sqlx::query!(
+            r#"
+                SELECT wishlist.harvest_id
+                FROM services.harvests_wishlist wishlist
+                WHERE wishlist.user_id = $1
+                    AND wishlist.harvest_id = ANY($2::uuid[]);
+            "#,
+            user_id.0,
+            harvest_ids,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => Ok(records
+                .into_iter()
+                .map(|rec| rec.harvest_id.into())
+                .collect()),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch saved harvests: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Returns true if the harvest exists and is not finished
+///
+/// # Errors
+///
+/// Return an internal server error if the database query fails.
+pub async fn active_harvest_exists(
+    harvest_id: ModelID,
+    db: DatabaseConnection,
+) -> EndpointResult<bool> {
+    match sqlx::query!(
+        r#"
+            SELECT EXISTS(
+                SELECT 1 FROM services.active_harvests harvest
+                WHERE harvest.id = $1
+            ) AS "exists!"
+        "#,
+        harvest_id.0
+    )
+    .fetch_one(&db.pool)
+    .await
+    {
+        Ok(rec) => Ok(rec.exists),
+        Err(err) => {
+            tracing::error!("Database error: {}", err);
+            Err(EndpointRejection::internal_server_error())
+        }
+    }
+}
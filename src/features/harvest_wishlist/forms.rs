@@ -0,0 +1,78 @@
+//! Harvest wishlist forms impls
+
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Json, Request},
+};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    auth::CurrentUser,
+    endpoint::{validators::ValidateString, EndpointRejection, EndpointResult},
+    server::state::ServerState,
+    types::ModelID,
+};
+
+use super::db::active_harvest_exists;
+
+/// Harvest wishlist add form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WishlistForm {
+    pub harvest_id: String,
+}
+
+/// Harvest wishlist add form cleaned data
+#[derive(Debug, Clone)]
+pub struct WishlistInsertData {
+    pub user_id: ModelID,
+    pub harvest_id: ModelID,
+    pub created_at: OffsetDateTime,
+}
+
+impl WishlistForm {
+    /// Converts `Self` into `WishlistInsertData`
+    #[must_use]
+    pub fn data(self, user_id: ModelID) -> WishlistInsertData {
+        WishlistInsertData {
+            user_id,
+            harvest_id: ModelID::from_str_unchecked(self.harvest_id),
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Validates wishlist form inputs
+    fn validate(&self) -> EndpointResult<()> {
+        self.harvest_id.validate_id("Invalid harvest id")?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for WishlistForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let _ = { CurrentUser::from_parts(&mut parts, state).await? };
+        let Json(wishlist) =
+            Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        // Validate form fields
+        wishlist.validate()?;
+
+        // Only harvests still listed can be saved
+        let harvest_id = ModelID::from_str_unchecked(&wishlist.harvest_id);
+        if !active_harvest_exists(harvest_id, state.database()).await? {
+            return Err(EndpointRejection::NotFound("Harvest not found.".into()));
+        }
+
+        Ok(wishlist)
+    }
+}
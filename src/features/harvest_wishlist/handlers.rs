@@ -0,0 +1,57 @@
+//! Harvest wishlist http handlers impls
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    auth::CurrentUser,
+    endpoint::{EndpointRejection, EndpointResult},
+    server::state::DatabaseConnection,
+    types::{ModelID, Pagination},
+};
+
+use super::{
+    forms::WishlistForm,
+    models::{WishlistHarvest, WishlistHarvestList},
+};
+
+/// Handles the `GET /account/users/me/wishlist` route.
+#[tracing::instrument(skip(db, user))]
+pub async fn wishlist_list(
+    user: CurrentUser,
+    pg: Option<Query<Pagination>>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<WishlistHarvestList>> {
+    let pagination = pg.unwrap_or_default().0;
+    let harvests = WishlistHarvest::records(user.id, pagination, db).await?;
+    Ok(Json(harvests))
+}
+
+/// Handles the `POST /account/users/me/wishlist` route.
+#[tracing::instrument(skip(db, user, form))]
+pub async fn wishlist_add(
+    user: CurrentUser,
+    State(db): State<DatabaseConnection>,
+    form: WishlistForm,
+) -> EndpointResult<StatusCode> {
+    WishlistHarvest::insert(form.data(user.id), db).await?;
+    Ok(StatusCode::CREATED)
+}
+
+/// Handles the `DELETE /account/users/me/wishlist/:harvest_id` route.
+#[tracing::instrument(skip(db, user))]
+pub async fn wishlist_remove(
+    user: CurrentUser,
+    harvest_id: ModelID,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<StatusCode> {
+    if WishlistHarvest::delete(user.id, harvest_id, db).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(EndpointRejection::NotFound(
+            "Harvest not found in your wishlist.".into(),
+        ))
+    }
+}
@@ -0,0 +1,9 @@
+//! Harvest wishlist impls
+
+pub mod db;
+pub mod forms;
+pub mod handlers;
+pub mod models;
+mod utils;
+
+pub use utils::{mark_saved, mark_saved_harvest};
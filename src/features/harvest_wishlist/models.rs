@@ -0,0 +1,35 @@
+//! Harvest wishlist models impls
+
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::services::produce::harvest::models::HarvestIndex;
+
+/// A `Vec` of wishlist harvests
+pub type WishlistHarvestList = Vec<WishlistHarvest>;
+
+/// A harvest saved on the user's wishlist
+///
+/// Returned by `wishlist_list` handler.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WishlistHarvest {
+    #[serde(flatten)]
+    pub harvest: HarvestIndex,
+    /// False if the harvest is finished, archived or its farm is deleted
+    pub available: bool,
+    pub saved_at: OffsetDateTime,
+}
+
+impl WishlistHarvest {
+    /// Creates a new `WishlistHarvest` from the database row
+    #[must_use]
+    pub fn from_row(mut harvest: HarvestIndex, available: bool, saved_at: OffsetDateTime) -> Self {
+        harvest.saved = Some(true);
+        Self {
+            harvest,
+            available,
+            saved_at,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Harvest wishlist helpers impls
+
+use crate::{
+    auth::CurrentUser,
+    error::ServerResult,
+    server::state::DatabaseConnection,
+    services::produce::harvest::models::{Harvest, HarvestIndex},
+};
+
+use super::models::WishlistHarvest;
+
+/// Sets the `saved` flag of the harvests for the current user,
+/// the flag is left unset for anonymous users.
+///
+/// # Errors
+///
+/// Return database error
+pub async fn mark_saved(
+    user: Option<&CurrentUser>,
+    harvests: &mut [HarvestIndex],
+    db: DatabaseConnection,
+) -> ServerResult<()> {
+    let Some(user) = user else {
+        return Ok(());
+    };
+    if harvests.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<_> = harvests.iter().map(|harvest| harvest.id.0).collect();
+    let saved = WishlistHarvest::saved_ids(user.id, &ids, db).await?;
+    for harvest in harvests {
+        harvest.saved = Some(saved.contains(&harvest.id));
+    }
+    Ok(())
+}
+
+/// Sets the `saved` flag of the harvest for the current user
+///
+/// # Errors
+///
+/// Return database error
+pub async fn mark_saved_harvest(
+    user: Option<&CurrentUser>,
+    harvest: &mut Harvest,
+    db: DatabaseConnection,
+) -> ServerResult<()> {
+    let Some(user) = user else {
+        return Ok(());
+    };
+
+    let saved = WishlistHarvest::saved_ids(user.id, &[harvest.id.0], db).await?;
+    harvest.saved = Some(saved.contains(&harvest.id));
+    Ok(())
+}
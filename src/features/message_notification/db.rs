@@ -0,0 +1,231 @@
+//! Message notification database impl
+
+use time::OffsetDateTime;
+
+use crate::{
+    error::ServerResult,
+    server::state::DatabaseConnection,
+    types::{Language, ModelID},
+};
+
+use super::{
+    forms::MessageNotificationUpdateData,
+    models::{MessageNotificationSettings, QuietHours, UnreadMessage},
+};
+
+impl MessageNotificationSettings {
+    /// Fetches the user message notification settings from the database,
+    /// return the default settings if the user has not changed them.
+    #[tracing::instrument(name = "Find Message Notification Settings", skip(db))]
+    pub async fn find(user_id: ModelID, db: DatabaseConnection) -> ServerResult<Self> {
+        match sqlx::query!(
+            r#"
+                SELECT settings.email_enabled,
+                    settings.quiet_hours_start,
+                    settings.quiet_hours_end,
+                    settings.utc_offset
+                FROM features.message_notification_settings settings
+                WHERE settings.user_id = $1;
+            "#,
+            user_id.0
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.map_or_else(Self::default, |rec| {
+                Self::from_row(
+                    rec.email_enabled,
+                    rec.quiet_hours_start,
+                    rec.quiet_hours_end,
+                    rec.utc_offset,
+                )
+            })),
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to fetch message notification settings: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Inserts or updates the user message notification settings in the database
+    #[tracing::instrument(name = "Update Message Notification Settings", skip(db, values))]
+    pub async fn update(
+        user_id: ModelID,
+        values: MessageNotificationUpdateData,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        let quiet_hours = values.quiet_hours;
+        match sqlx::query!(
+            r#"
+                INSERT INTO features.message_notification_settings(
+                    user_id,
+                    email_enabled,
+                    quiet_hours_start,
+                    quiet_hours_end,
+                    utc_offset,
+                    updated_at
+                )
+                VALUES($1, $2, $3, $4, $5, $6)
+                ON CONFLICT ON CONSTRAINT message_notification_settings_pkey
+                DO UPDATE SET email_enabled = EXCLUDED.email_enabled,
+                    quiet_hours_start = EXCLUDED.quiet_hours_start,
+                    quiet_hours_end = EXCLUDED.quiet_hours_end,
+                    utc_offset = EXCLUDED.utc_offset,
+                    updated_at = EXCLUDED.updated_at;
+            "#,
+            user_id.0,
+            values.email_enabled,
+            quiet_hours.map(|hours| hours.start),
+            quiet_hours.map(|hours| hours.end),
+            quiet_hours.map_or(0, |hours| hours.utc_offset),
+            values.updated_at,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Message notification settings updated: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to update message notification settings: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
+
+impl UnreadMessage {
+    /// Fetches unread messages sent between `sent_after` and `sent_before`
+    /// the receivers have not been emailed about, ordered by receiver.
+    ///
+    /// Messages to receivers who turned off message emails, have not
+    /// verified their email or blocked each other with the sender are excluded.
+    #[tracing::instrument(name = "Fetch Unread Messages", skip(db))]
+    pub async fn records(
+        sent_after: OffsetDateTime,
+        sent_before: OffsetDateTime,
+        db: DatabaseConnection,
+    ) -> ServerResult<Vec<Self>> {
+        match sqlx::query!(
+            r#"
+                SELECT message.id,
+                    message.sender_id,
+                    sender.first_name AS sender_first_name,
+                    sender.last_name AS sender_last_name,
+                    message.content,
+                    message.sent_at,
+                    message.receiver_id,
+                    receiver.first_name AS receiver_first_name,
+                    receiver.language AS receiver_language,
+                    email.email AS receiver_email,
+                    settings.quiet_hours_start AS "quiet_hours_start?",
+                    settings.quiet_hours_end AS "quiet_hours_end?",
+                    settings.utc_offset AS "utc_offset?"
+                FROM features.direct_messages message
+                INNER JOIN features.message_status status
+                    ON message.id = status.message_id
+                INNER JOIN accounts.users sender
+                    ON message.sender_id = sender.id
+                INNER JOIN accounts.users receiver
+                    ON message.receiver_id = receiver.id
+                INNER JOIN accounts.emails email
+                    ON receiver.id = email.user_id
+                LEFT JOIN features.message_notification_settings settings
+                    ON receiver.id = settings.user_id
+
+                WHERE NOT status.is_read
+                    AND NOT status.receiver_has_deleted
+                    AND status.emailed_at IS NULL
+                    AND message.sent_at > $1
+                    AND message.sent_at <= $2
+                    AND email.verified
+                    AND COALESCE(settings.email_enabled, true)
+                    -- Users that blocked each other are not emailed about their messages
+                    AND NOT EXISTS (
+                        SELECT 1 FROM accounts.user_blocks block
+                        WHERE (block.user_id = message.sender_id
+                                AND block.blocked_user_id = message.receiver_id)
+                            OR (block.user_id = message.receiver_id
+                                AND block.blocked_user_id = message.sender_id)
+                    )
+                ORDER BY message.receiver_id, message.sent_at;
+            "#,
+            sent_after,
+            sent_before,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let messages = records
+                    .into_iter()
+                    .map(|rec| Self {
+                        id: rec.id.into(),
+                        sender_id: rec.sender_id.into(),
+                        sender_name: rec.sender_last_name.map_or_else(
+                            || rec.sender_first_name.clone(),
+                            |last_name| format!("{} {last_name}", rec.sender_first_name),
+                        ),
+                        content: rec.content,
+                        sent_at: rec.sent_at,
+                        receiver_id: rec.receiver_id.into(),
+                        receiver_first_name: rec.receiver_first_name,
+                        receiver_email: rec.receiver_email,
+                        receiver_language: Language::from_row(&rec.receiver_language),
+                        quiet_hours: QuietHours::from_row(
+                            rec.quiet_hours_start,
+                            rec.quiet_hours_end,
+                            rec.utc_offset.unwrap_or_default(),
+                        ),
+                    })
+                    .collect();
+                Ok(messages)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch unread messages: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Marks the messages as emailed to the receiver
+    #[tracing::instrument(name = "Update Unread Messages Emailed", skip(db, ids))]
+    pub async fn emailed(
+        ids: &[ModelID],
+        emailed_at: OffsetDateTime,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        let ids: Vec<_> = ids.iter().map(|id| id.0).collect();
+        match sqlx::query!(
+            r#"
+                UPDATE features.message_status status
+                SET emailed_at = $1
+                WHERE status.message_id = ANY($2::uuid[]);
+            "#,
+            emailed_at,
+            &ids[..],
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Unread messages marked emailed: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to mark unread messages emailed: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Message notification forms impls
+
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Json, Request},
+};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    endpoint::{EndpointRejection, EndpointResult},
+    server::state::ServerState,
+};
+
+use super::models::QuietHours;
+
+/// Message notification settings update form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageNotificationForm {
+    pub email_enabled: bool,
+    /// Quiet hours are turned off if not set
+    pub quiet_hours: Option<QuietHours>,
+}
+
+/// Message notification settings update form cleaned data
+#[derive(Debug, Clone)]
+pub struct MessageNotificationUpdateData {
+    pub email_enabled: bool,
+    pub quiet_hours: Option<QuietHours>,
+    pub updated_at: OffsetDateTime,
+}
+
+impl From<MessageNotificationForm> for MessageNotificationUpdateData {
+    fn from(form: MessageNotificationForm) -> Self {
+        Self {
+            email_enabled: form.email_enabled,
+            quiet_hours: form.quiet_hours,
+            updated_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+impl MessageNotificationForm {
+    /// Validates message notification form inputs
+    fn validate(&self) -> EndpointResult<()> {
+        if let Some(quiet_hours) = self.quiet_hours {
+            if quiet_hours.start == quiet_hours.end {
+                return Err(EndpointRejection::BadRequest(
+                    "Quiet hours start and end must be different".into(),
+                ));
+            }
+            if !(-720..=840).contains(&quiet_hours.utc_offset) {
+                return Err(EndpointRejection::BadRequest(
+                    "UTC offset must be between -720 and 840 minutes".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for MessageNotificationForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let Json(settings) = Json::<Self>::from_request(req, state).await?;
+
+        // Validate form fields
+        settings.validate()?;
+
+        Ok(settings)
+    }
+}
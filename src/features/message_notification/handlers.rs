@@ -0,0 +1,31 @@
+//! Message notification http handlers impls
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+
+use crate::{auth::CurrentUser, endpoint::EndpointResult, server::state::DatabaseConnection};
+
+use super::{forms::MessageNotificationForm, models::MessageNotificationSettings};
+
+/// Handles the `GET /account/settings/message-notifications` route.
+#[tracing::instrument(skip(db, user))]
+pub async fn message_notification_settings(
+    user: CurrentUser,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<MessageNotificationSettings>> {
+    let settings = MessageNotificationSettings::find(user.id, db).await?;
+    Ok(Json(settings))
+}
+
+/// Handles the `PUT /account/settings/message-notifications` route.
+#[tracing::instrument(skip(db, user, form))]
+pub async fn message_notification_settings_update(
+    user: CurrentUser,
+    State(db): State<DatabaseConnection>,
+    form: MessageNotificationForm,
+) -> EndpointResult<StatusCode> {
+    MessageNotificationSettings::update(user.id, form.into(), db).await?;
+    Ok(StatusCode::OK)
+}
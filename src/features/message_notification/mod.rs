@@ -0,0 +1,6 @@
+//! Unread direct message email notifications impls
+
+pub mod db;
+pub mod forms;
+pub mod handlers;
+pub mod models;
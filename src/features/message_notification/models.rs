@@ -0,0 +1,117 @@
+//! Message notification models impls
+
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, Time, UtcOffset};
+
+use crate::types::{Language, ModelID};
+
+/// User unread direct message email notification settings
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageNotificationSettings {
+    /// Email the user about unread direct messages
+    pub email_enabled: bool,
+    /// Hours the user is not emailed
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for MessageNotificationSettings {
+    fn default() -> Self {
+        Self {
+            email_enabled: true,
+            quiet_hours: None,
+        }
+    }
+}
+
+impl MessageNotificationSettings {
+    /// Creates a new `MessageNotificationSettings` from the database row
+    #[must_use]
+    pub fn from_row(
+        email_enabled: bool,
+        quiet_hours_start: Option<Time>,
+        quiet_hours_end: Option<Time>,
+        utc_offset: i16,
+    ) -> Self {
+        Self {
+            email_enabled,
+            quiet_hours: QuietHours::from_row(quiet_hours_start, quiet_hours_end, utc_offset),
+        }
+    }
+}
+
+/// Hours of the day in the user local time the user is not emailed,
+/// a start after the end spans midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    /// Local time formatted as `HH:MM`
+    #[serde(with = "hour_minute")]
+    pub start: Time,
+    /// Local time formatted as `HH:MM`
+    #[serde(with = "hour_minute")]
+    pub end: Time,
+    /// The user local time offset from UTC in minutes
+    pub utc_offset: i16,
+}
+
+impl QuietHours {
+    /// Creates a new `QuietHours` from the database row
+    #[must_use]
+    pub fn from_row(start: Option<Time>, end: Option<Time>, utc_offset: i16) -> Option<Self> {
+        Some(Self {
+            start: start?,
+            end: end?,
+            utc_offset,
+        })
+    }
+
+    /// Returns true if `now` is within the quiet hours
+    #[must_use]
+    pub fn contains(&self, now: OffsetDateTime) -> bool {
+        let offset = UtcOffset::from_whole_seconds(i32::from(self.utc_offset) * 60)
+            .unwrap_or(UtcOffset::UTC);
+        let local = now.to_offset(offset).time();
+        if self.start <= self.end {
+            self.start <= local && local < self.end
+        } else {
+            self.start <= local || local < self.end
+        }
+    }
+}
+
+/// Serializes `Time` as `HH:MM`
+mod hour_minute {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use time::Time;
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn serialize<S: Serializer>(time: &Time, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:02}:{:02}", time.hour(), time.minute()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Time, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value
+            .split_once(':')
+            .and_then(|(hour, minute)| Some((hour.parse().ok()?, minute.parse().ok()?)))
+            .and_then(|(hour, minute)| Time::from_hms(hour, minute, 0).ok())
+            .ok_or_else(|| de::Error::custom(format!("invalid time `{value}`, expected HH:MM")))
+    }
+}
+
+/// An unread direct message the receiver has not been emailed about,
+/// used by the unread messages maintenance task.
+#[derive(Debug, Clone)]
+pub struct UnreadMessage {
+    pub id: ModelID,
+    pub sender_id: ModelID,
+    pub sender_name: String,
+    pub content: String,
+    pub sent_at: OffsetDateTime,
+    pub receiver_id: ModelID,
+    pub receiver_first_name: String,
+    pub receiver_email: String,
+    pub receiver_language: Language,
+    pub quiet_hours: Option<QuietHours>,
+}
@@ -1,4 +1,8 @@
 //! Features impls
 
+pub mod conversation_report;
 pub mod direct_message;
 pub mod harvest_subscription;
+pub mod harvest_wishlist;
+pub mod message_notification;
+pub mod saved_search;
@@ -0,0 +1,334 @@
+//! Saved search database impl
+
+use time::OffsetDateTime;
+
+use crate::{error::ServerResult, server::state::DatabaseConnection, types::ModelID};
+
+use super::{
+    forms::{SavedSearchInsertData, SavedSearchUpdateData},
+    models::{SavedSearch, SavedSearchAlert, SavedSearchList, SavedSearchUnsubscribe},
+};
+
+impl SavedSearch {
+    /// Fetches user saved search records from the database
+    #[tracing::instrument(name = "Fetch User Saved Searches", skip(db))]
+    pub async fn records(
+        user_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<SavedSearchList> {
+        match sqlx::query!(
+            r#"
+                SELECT search.id,
+                    search.name,
+                    search.filters,
+                    search.geo,
+                    search.email_alerts,
+                    search.created_at,
+                    search.updated_at
+                FROM features.saved_searches search
+                WHERE search.user_id = $1
+                ORDER BY search.created_at;
+            "#,
+            user_id.0
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let searches = records
+                    .into_iter()
+                    .map(|rec| {
+                        Self::from_row(
+                            rec.id.into(),
+                            rec.name,
+                            rec.filters,
+                            rec.geo,
+                            rec.email_alerts,
+                            rec.created_at,
+                            rec.updated_at,
+                        )
+                    })
+                    .collect();
+
+                Ok(searches)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch saved searches: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Inserts saved search into the database
+    #[tracing::instrument(name = "Insert Saved Search", skip(db, search))]
+    pub async fn insert(
+        search: SavedSearchInsertData,
+        db: DatabaseConnection,
+    ) -> ServerResult<ModelID> {
+        match sqlx::query!(
+            r#"
+                INSERT INTO features.saved_searches(
+                    id,
+                    user_id,
+                    name,
+                    filters,
+                    geo,
+                    email_alerts,
+                    unsubscribe_token,
+                    last_checked_at,
+                    created_at
+                )
+                VALUES($1, $2, $3, $4, $5, $6, $7, $8, $8);
+            "#,
+            search.id.0,
+            search.user_id.0,
+            search.name,
+            search.filters,
+            search.geo,
+            search.email_alerts,
+            search.unsubscribe_token,
+            search.created_at,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Saved search inserted successfully: {:?}", result);
+                Ok(search.id)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to insert saved search: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Updates user saved search in the database,
+    /// returns false if the user has no such saved search.
+    #[tracing::instrument(name = "Update Saved Search", skip(db, search))]
+    pub async fn update(
+        id: ModelID,
+        user_id: ModelID,
+        search: SavedSearchUpdateData,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                UPDATE features.saved_searches search
+                SET name = $1,
+                    filters = $2,
+                    geo = $3,
+                    email_alerts = $4,
+                    updated_at = $5
+                WHERE search.id = $6
+                    AND search.user_id = $7;
+            "#,
+            search.name,
+            search.filters,
+            search.geo,
+            search.email_alerts,
+            search.updated_at,
+            id.0,
+            user_id.0,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Saved search updated successfully: {:?}", result);
+                Ok(result.rows_affected() > 0)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to update saved search: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Deletes user saved search from the database,
+    /// returns false if the user has no such saved search.
+    #[tracing::instrument(name = "Delete Saved Search", skip(db))]
+    pub async fn delete(
+        id: ModelID,
+        user_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                DELETE FROM features.saved_searches search
+                WHERE search.id = $1
+                    AND search.user_id = $2;
+            "#,
+            id.0,
+            user_id.0,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Saved search deleted successfully: {:?}", result);
+                Ok(result.rows_affected() > 0)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to delete saved search: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Turns off the email alerts of the saved search the token belongs to,
+    /// returns false if the token is not valid.
+    #[tracing::instrument(name = "Unsubscribe Saved Search", skip(db, token))]
+    pub async fn unsubscribe(token: String, db: DatabaseConnection) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                UPDATE features.saved_searches search
+                SET email_alerts = false,
+                    updated_at = $1
+                WHERE search.unsubscribe_token = $2;
+            "#,
+            OffsetDateTime::now_utc(),
+            token,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Saved search unsubscribed: {:?}", result);
+                Ok(result.rows_affected() > 0)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to unsubscribe saved search: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
+
+impl SavedSearchUnsubscribe {
+    /// Fetches the saved search the unsubscribe token belongs to from the database
+    #[tracing::instrument(name = "Find Saved Search Unsubscribe", skip(db, token))]
+    pub async fn find(token: String, db: DatabaseConnection) -> ServerResult<Option<Self>> {
+        match sqlx::query!(
+            r#"
+                SELECT search.name,
+                    search.email_alerts
+                FROM features.saved_searches search
+                WHERE search.unsubscribe_token = $1;
+            "#,
+            token,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.map(|rec| Self {
+                name: rec.name,
+                email_alerts: rec.email_alerts,
+            })),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch saved search: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+impl SavedSearchAlert {
+    /// Fetches saved searches with email alerts turned on,
+    /// ordered by user.
+    #[tracing::instrument(name = "Fetch Saved Search Alerts", skip(db))]
+    pub async fn records(db: DatabaseConnection) -> ServerResult<Vec<Self>> {
+        match sqlx::query!(
+            r#"
+                SELECT search.id,
+                    search.user_id,
+                    user_.first_name AS user_first_name,
+                    email.email AS user_email,
+                    user_.language AS user_language,
+                    search.name,
+                    search.filters,
+                    search.geo,
+                    search.unsubscribe_token,
+                    search.last_checked_at
+                FROM features.saved_searches search
+                INNER JOIN accounts.users user_
+                    ON search.user_id = user_.id
+                INNER JOIN accounts.emails email
+                    ON user_.id = email.user_id
+
+                WHERE search.email_alerts
+                    AND email.verified
+                ORDER BY search.user_id, search.created_at;
+            "#,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let searches = records
+                    .into_iter()
+                    .map(|rec| {
+                        Self::from_row(
+                            rec.id.into(),
+                            rec.user_id.into(),
+                            rec.user_first_name,
+                            rec.user_email,
+                            &rec.user_language,
+                            rec.name,
+                            rec.filters,
+                            rec.geo,
+                            rec.unsubscribe_token,
+                            rec.last_checked_at,
+                        )
+                    })
+                    .collect();
+
+                Ok(searches)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to fetch saved search alerts: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Records the time the saved searches were last checked for new harvests
+    #[tracing::instrument(name = "Update Saved Search Checked", skip(db, ids))]
+    pub async fn checked(
+        ids: &[ModelID],
+        checked_at: OffsetDateTime,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        let ids: Vec<_> = ids.iter().map(|id| id.0).collect();
+        match sqlx::query!(
+            r#"
+                UPDATE features.saved_searches search
+                SET last_checked_at = $1
+                WHERE search.id = ANY($2::uuid[]);
+            "#,
+            checked_at,
+            &ids[..],
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) => {
+                tracing::debug!("Saved searches checked at updated: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to update saved searches checked at: {}",
+                    err
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
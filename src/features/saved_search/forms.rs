@@ -0,0 +1,130 @@
+//! Saved search forms impls
+
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Json, Request},
+};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    auth::{CurrentUser, Token},
+    endpoint::{
+        validators::{TransformString, ValidateString},
+        EndpointRejection, EndpointResult,
+    },
+    server::state::ServerState,
+    services::produce::HarvestFilter,
+    types::{geo::GeoQuery, ModelID},
+};
+
+/// Saved search create and update form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearchForm {
+    pub name: String,
+    /// `harvests/feed` filters
+    #[serde(default)]
+    pub filters: HarvestFilter,
+    /// `harvests/feed` geo filters
+    #[serde(default)]
+    pub geo: GeoQuery,
+    /// Email the user when new harvests match the search
+    #[serde(default = "default_email_alerts")]
+    pub email_alerts: bool,
+}
+
+/// Email alerts are turned on by default
+const fn default_email_alerts() -> bool {
+    true
+}
+
+/// Saved search create form cleaned data
+#[derive(Debug, Clone)]
+pub struct SavedSearchInsertData {
+    pub id: ModelID,
+    pub user_id: ModelID,
+    pub name: String,
+    pub filters: serde_json::Value,
+    pub geo: serde_json::Value,
+    pub email_alerts: bool,
+    pub unsubscribe_token: String,
+    pub created_at: OffsetDateTime,
+}
+
+/// Saved search update form cleaned data
+#[derive(Debug, Clone)]
+pub struct SavedSearchUpdateData {
+    pub name: String,
+    pub filters: serde_json::Value,
+    pub geo: serde_json::Value,
+    pub email_alerts: bool,
+    pub updated_at: OffsetDateTime,
+}
+
+impl SavedSearchForm {
+    /// Converts `Self` into `SavedSearchInsertData`
+    #[must_use]
+    pub fn data(self, user_id: ModelID) -> SavedSearchInsertData {
+        let created_at = OffsetDateTime::now_utc();
+        SavedSearchInsertData {
+            id: ModelID::new(),
+            user_id,
+            name: self.name,
+            filters: serde_json::to_value(self.filters).unwrap(),
+            geo: serde_json::to_value(self.geo).unwrap(),
+            email_alerts: self.email_alerts,
+            unsubscribe_token: Token::default().plaintext,
+            created_at,
+        }
+    }
+
+    /// Validates saved search form inputs
+    fn validate(&mut self) -> EndpointResult<()> {
+        // Clean the data
+        self.name = self.name.clean();
+        // Saved searches are evaluated from the start of the feed
+        self.filters.offset = None;
+
+        self.name
+            .validate_len(1, 64, "Search name must be between 1 and 64 characters")?;
+        self.filters.validate()?;
+        self.geo.filter()?;
+
+        Ok(())
+    }
+}
+
+#[allow(clippy::fallible_impl_from)]
+impl From<SavedSearchForm> for SavedSearchUpdateData {
+    fn from(form: SavedSearchForm) -> Self {
+        Self {
+            name: form.name,
+            filters: serde_json::to_value(form.filters).unwrap(),
+            geo: serde_json::to_value(form.geo).unwrap(),
+            email_alerts: form.email_alerts,
+            updated_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for SavedSearchForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let _ = { CurrentUser::from_parts(&mut parts, state).await? };
+        let Json(mut search) =
+            Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        // Validate form fields
+        search.validate()?;
+
+        Ok(search)
+    }
+}
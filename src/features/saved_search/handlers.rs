@@ -0,0 +1,118 @@
+//! Saved search http handlers impls
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    auth::{CurrentUser, TokenConfirm},
+    endpoint::{EndpointRejection, EndpointResult},
+    server::state::DatabaseConnection,
+    types::ModelID,
+};
+
+use super::{
+    forms::SavedSearchForm,
+    models::{SavedSearch, SavedSearchList, SavedSearchUnsubscribe},
+};
+
+/// Handles the `GET /account/users/me/saved-searches` route.
+#[tracing::instrument(skip(db, user))]
+pub async fn saved_search_list(
+    user: CurrentUser,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<SavedSearchList>> {
+    let searches = SavedSearch::records(user.id, db).await?;
+    Ok(Json(searches))
+}
+
+/// Handles the `POST /account/users/me/saved-searches` route.
+#[tracing::instrument(skip(db, user, form))]
+pub async fn saved_search_create(
+    user: CurrentUser,
+    State(db): State<DatabaseConnection>,
+    form: SavedSearchForm,
+) -> EndpointResult<(StatusCode, Json<ModelID>)> {
+    let search_id = SavedSearch::insert(form.data(user.id), db).await?;
+    Ok((StatusCode::CREATED, Json(search_id)))
+}
+
+/// Handles the `PUT /account/users/me/saved-searches/:search_id` route.
+#[tracing::instrument(skip(db, user, form))]
+pub async fn saved_search_update(
+    user: CurrentUser,
+    search_id: ModelID,
+    State(db): State<DatabaseConnection>,
+    form: SavedSearchForm,
+) -> EndpointResult<StatusCode> {
+    if SavedSearch::update(search_id, user.id, form.into(), db).await? {
+        Ok(StatusCode::OK)
+    } else {
+        Err(EndpointRejection::NotFound(
+            "Saved search not found.".into(),
+        ))
+    }
+}
+
+/// Handles the `DELETE /account/users/me/saved-searches/:search_id` route.
+#[tracing::instrument(skip(db, user))]
+pub async fn saved_search_delete(
+    user: CurrentUser,
+    search_id: ModelID,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<StatusCode> {
+    if SavedSearch::delete(search_id, user.id, db).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(EndpointRejection::NotFound(
+            "Saved search not found.".into(),
+        ))
+    }
+}
+
+/// Handles the `GET /account/saved-searches/unsubscribe` route.
+///
+/// Returns the saved search of the digest email unsubscribe link
+/// for the user to confirm, the email alerts are left unchanged
+/// so link scanners and prefetchers do not unsubscribe the user.
+#[tracing::instrument(skip(db, token))]
+pub async fn saved_search_unsubscribe_confirm(
+    token: Option<Query<TokenConfirm>>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<SavedSearchUnsubscribe>> {
+    let token = unsubscribe_token(token)?;
+    let Some(search) = SavedSearchUnsubscribe::find(token, db).await? else {
+        return Err(EndpointRejection::BadRequest(UNSUBSCRIBE_ERR_MSG.into()));
+    };
+    Ok(Json(search))
+}
+
+/// Handles the `POST /account/saved-searches/unsubscribe` route.
+///
+/// Turns off a saved search email alerts from the digest email link,
+/// the user does not need to be logged in.
+#[tracing::instrument(skip(db, token))]
+pub async fn saved_search_unsubscribe(
+    token: Option<Query<TokenConfirm>>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<&'static str> {
+    let token = unsubscribe_token(token)?;
+    if !SavedSearch::unsubscribe(token, db).await? {
+        return Err(EndpointRejection::BadRequest(UNSUBSCRIBE_ERR_MSG.into()));
+    }
+
+    Ok("You will no longer receive emails for this saved search.")
+}
+
+static UNSUBSCRIBE_ERR_MSG: &str = "Your unsubscribe link is no longer valid.";
+
+/// Returns the token of the digest email unsubscribe link
+fn unsubscribe_token(token: Option<Query<TokenConfirm>>) -> EndpointResult<String> {
+    let Some(Query(token)) = token else {
+        return Err(EndpointRejection::BadRequest(
+            "Unsubscribe token required!".into(),
+        ));
+    };
+    Ok(token.token)
+}
@@ -0,0 +1,6 @@
+//! Saved harvest search impls
+
+pub mod db;
+pub mod forms;
+pub mod handlers;
+pub mod models;
@@ -0,0 +1,140 @@
+//! Saved search models impls
+
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::{
+    error::ServerResult,
+    server::state::DatabaseConnection,
+    services::produce::{
+        harvest::models::{Harvest, HarvestList},
+        HarvestFilter,
+    },
+    types::{geo::GeoQuery, Language, ModelID},
+};
+
+/// A `Vec` of saved searches
+pub type SavedSearchList = Vec<SavedSearch>;
+
+/// The model representing a row in the `saved_searches` database table.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    pub id: ModelID,
+    pub name: String,
+    pub filters: HarvestFilter,
+    pub geo: GeoQuery,
+    pub email_alerts: bool,
+    pub created_at: OffsetDateTime,
+    pub updated_at: Option<OffsetDateTime>,
+}
+
+impl SavedSearch {
+    /// Creates a new `SavedSearch` from the database row
+    #[must_use]
+    pub fn from_row(
+        id: ModelID,
+        name: String,
+        filters: serde_json::Value,
+        geo: serde_json::Value,
+        email_alerts: bool,
+        created_at: OffsetDateTime,
+        updated_at: Option<OffsetDateTime>,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            filters: serde_json::from_value(filters).unwrap_or_default(),
+            geo: serde_json::from_value(geo).unwrap_or_default(),
+            email_alerts,
+            created_at,
+            updated_at,
+        }
+    }
+}
+
+/// The saved search an unsubscribe link belongs to,
+/// shown to the user to confirm turning off its email alerts.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearchUnsubscribe {
+    pub name: String,
+    pub email_alerts: bool,
+}
+
+/// A saved search with email alerts turned on,
+/// used by the saved search digest maintenance task.
+#[derive(Debug, Clone)]
+pub struct SavedSearchAlert {
+    pub id: ModelID,
+    pub user_id: ModelID,
+    pub first_name: String,
+    pub email: String,
+    pub language: Language,
+    pub name: String,
+    pub filters: HarvestFilter,
+    pub geo: GeoQuery,
+    pub unsubscribe_token: String,
+    pub last_checked_at: OffsetDateTime,
+}
+
+impl SavedSearchAlert {
+    /// Creates a new `SavedSearchAlert` from the database row
+    #[allow(clippy::too_many_arguments)]
+    #[must_use]
+    pub fn from_row(
+        id: ModelID,
+        user_id: ModelID,
+        first_name: String,
+        email: String,
+        language: &str,
+        name: String,
+        filters: serde_json::Value,
+        geo: serde_json::Value,
+        unsubscribe_token: String,
+        last_checked_at: OffsetDateTime,
+    ) -> Self {
+        Self {
+            id,
+            user_id,
+            first_name,
+            email,
+            language: Language::from_row(language),
+            name,
+            filters: serde_json::from_value(filters).unwrap_or_default(),
+            geo: serde_json::from_value(geo).unwrap_or_default(),
+            unsubscribe_token,
+            last_checked_at,
+        }
+    }
+
+    /// Fetches at most `limit` harvests matching the search
+    /// listed since the search was last checked until `checked_at`.
+    ///
+    /// # Errors
+    ///
+    /// Return database error
+    pub async fn new_harvests(
+        &self,
+        limit: usize,
+        checked_at: OffsetDateTime,
+        db: DatabaseConnection,
+    ) -> ServerResult<HarvestList> {
+        let mut filters = self.filters.clone();
+        filters.offset = None;
+        filters.limit = limit;
+        filters.listed_after = Some(self.last_checked_at);
+        filters.listed_before = Some(checked_at);
+
+        // Saved filters are validated on save
+        let Ok(geo) = self.geo.filter() else {
+            tracing::warn!("Saved search: {} has invalid geo filters", self.id);
+            return Ok(Vec::new());
+        };
+
+        // The feed fetches one extra harvest used as the next page offset
+        let mut harvests = Harvest::feed(&filters, &geo, db).await?;
+        harvests.truncate(limit);
+        Ok(harvests)
+    }
+}
@@ -154,6 +154,11 @@ impl Farm {
                     harvest.id AS "harvest_id?",
                     harvest.price AS "harvest_price?",
                     harvest.images AS harvest_images,
+                    services.harvest_low_stock(
+                        harvest.available_quantity,
+                        harvest.reserved_quantity,
+                        harvest.low_stock_threshold
+                    ) AS "harvest_low_stock!",
                     harvest.harvest_date AS "harvest_harvest_date?",
                     cultivar.name AS "cultivar_name?",
                     cultivar_category.name AS "cultivar_category?",
@@ -233,6 +238,7 @@ impl Farm {
                                 rec.location_coords,
                                 rec.farm_name,
                                 rec.farm_logo,
+                                rec.harvest_low_stock,
                                 0.into(), // boost amount not important
                             )
                         })
@@ -289,9 +295,10 @@ impl Farm {
                     contact_email,
                     founded_at,
                     registered_on,
-                    deleted
+                    deleted,
+                    search_document
                 )
-                VALUES($1, $2, $3, $4, $5, $6, $7, false);
+                VALUES($1, $2, $3, $4, $5, $6, $7, false, services.farm_search_document($3));
             "#,
             farm.id.0,
             farm.owner_id.0,
@@ -343,7 +350,8 @@ impl Farm {
                 SET name = COALESCE($1, farm.name),
                     contact_number = $2,
                     contact_email = $3,
-                    founded_at = $4
+                    founded_at = $4,
+                    search_document = services.farm_search_document(COALESCE($1, farm.name))
 
                 WHERE id = $5;
            "#,
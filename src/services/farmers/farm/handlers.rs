@@ -102,7 +102,7 @@ pub async fn farm_logo_upload(
     handler.accept().await?; // Receive logo from the client
     if let Some(file) = uploads.files().await {
         // Save a logo to the file system
-        let saved_to = file.save_image(FARM_LOGO_UPLOAD_DIR).await?;
+        let saved_to = file.queue_image(FARM_LOGO_UPLOAD_DIR, db.clone()).await?;
 
         // Save image path to the database
         let (new_logo, old_logo) = Farm::insert_or_delete_logo(farm_id, Some(saved_to), db).await?;
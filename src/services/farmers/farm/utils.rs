@@ -32,9 +32,13 @@ pub async fn location_insert(
                 description,
                 coords,
                 deleted,
-                created_at
+                created_at,
+                search_document
             )
-            VALUES($1, $2, $3, $4, $5, $6, $7, false, $8);
+            VALUES(
+                $1, $2, $3, $4, $5, $6, $7, false, $8,
+                services.location_search_document($3, $6)
+            );
         "#,
         location.id.0,
         location.farm_id.0,
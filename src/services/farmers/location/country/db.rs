@@ -4,7 +4,7 @@ use crate::{
     endpoint::EndpointRejection,
     error::{ServerError, ServerResult},
     server::state::DatabaseConnection,
-    types::ModelID,
+    types::{price::Currency, ModelID},
 };
 
 use super::{
@@ -19,7 +19,8 @@ impl Country {
         match sqlx::query!(
             r#"
                 SELECT  country.id,
-                     country.name
+                     country.name,
+                     country.currency
                 FROM services.countries country
             "#
         )
@@ -29,7 +30,7 @@ impl Country {
             Ok(records) => {
                 let countries = records
                     .into_iter()
-                    .map(|rec| Self::from_row(rec.id.into(), rec.name))
+                    .map(|rec| Self::from_row(rec.id.into(), rec.name, &rec.currency))
                     .collect();
 
                 Ok(countries)
@@ -54,12 +55,14 @@ impl Country {
             r#"
                 INSERT INTO services.countries (
                     id, 
-                    name
+                    name,
+                    currency
                 )
-                VALUES ($1, $2);
+                VALUES ($1, $2, $3);
             "#,
             country.id.0,
-            country.name
+            country.name,
+            country.currency.code()
         )
         .execute(&db.pool)
         .await
@@ -88,10 +91,12 @@ impl Country {
         match sqlx::query!(
             r#"
                 UPDATE services.countries country
-                SET name = COALESCE($1, country.name)
-                WHERE country.id = $2
+                SET name = COALESCE($1, country.name),
+                    currency = COALESCE($2, country.currency)
+                WHERE country.id = $3
            "#,
             country.name,
+            country.currency.map(Currency::code),
             id.0
         )
         .execute(&db.pool)
@@ -111,6 +116,37 @@ impl Country {
         }
     }
 
+    /// Returns true if harvests listed in the country are not priced in the `currency`
+    #[tracing::instrument(name = "Fetch Country Has Harvests In Other Currency", skip(db))]
+    pub async fn has_harvests_in_other_currency(
+        id: ModelID,
+        currency: Currency,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                SELECT EXISTS(
+                    SELECT 1 FROM services.active_harvests harvest
+                    INNER JOIN services.locations location_
+                        ON harvest.location_id = location_.id
+                    WHERE location_.country_id = $1
+                        AND harvest.price ->> 'currency' IS DISTINCT FROM $2
+                ) AS "exists!"
+            "#,
+            id.0,
+            currency.code(),
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.exists),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch country harvests: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
     /// Deletes location country from the database
     #[tracing::instrument(name = "Delete Location-country", skip(db))]
     pub async fn delete(id: ModelID, db: DatabaseConnection) -> ServerResult<()> {
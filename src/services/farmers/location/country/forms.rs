@@ -12,7 +12,7 @@ use crate::{
         EndpointRejection, EndpointResult,
     },
     server::state::ServerState,
-    types::ModelID,
+    types::{price::Currency, ModelID},
 };
 
 /// Country create form
@@ -20,6 +20,10 @@ use crate::{
 #[serde(rename_all = "camelCase")]
 pub struct CountryForm {
     pub name: String,
+    /// ISO-4217 currency harvests listed in the country are priced in,
+    /// defaults to `NAD` on create and is left unchanged on update
+    #[serde(default)]
+    pub currency: Option<Currency>,
 }
 
 /// Country create form cleaned data
@@ -27,6 +31,7 @@ pub struct CountryForm {
 pub struct CountryInsertData {
     pub id: ModelID,
     pub name: String,
+    pub currency: Currency,
 }
 
 impl From<CountryForm> for CountryInsertData {
@@ -34,6 +39,7 @@ impl From<CountryForm> for CountryInsertData {
         Self {
             id: ModelID::new(),
             name: form.name,
+            currency: form.currency.unwrap_or_default(),
         }
     }
 }
@@ -42,11 +48,15 @@ impl From<CountryForm> for CountryInsertData {
 #[derive(Debug, Clone)]
 pub struct CountryUpdateData {
     pub name: String,
+    pub currency: Option<Currency>,
 }
 
 impl From<CountryForm> for CountryUpdateData {
     fn from(form: CountryForm) -> Self {
-        Self { name: form.name }
+        Self {
+            name: form.name,
+            currency: form.currency,
+        }
     }
 }
 
@@ -6,7 +6,10 @@ use axum::{
 };
 
 use crate::{
-    auth::AdminUser, endpoint::EndpointResult, server::state::DatabaseConnection, types::ModelID,
+    auth::AdminUser,
+    endpoint::{EndpointRejection, EndpointResult},
+    server::state::DatabaseConnection,
+    types::ModelID,
 };
 
 use super::{forms::CountryForm, Country, CountryList};
@@ -39,6 +42,14 @@ pub async fn country_update(
     State(db): State<DatabaseConnection>,
     form: CountryForm,
 ) -> EndpointResult<StatusCode> {
+    // Listed harvests must stay priced in the country currency
+    if let Some(currency) = form.currency {
+        if Country::has_harvests_in_other_currency(country_id, currency, db.clone()).await? {
+            return Err(EndpointRejection::BadRequest(
+                "The currency of a country with listed harvests cannot be changed.".into(),
+            ));
+        }
+    }
     Country::update(country_id, form.into(), db).await?;
     Ok(StatusCode::OK)
 }
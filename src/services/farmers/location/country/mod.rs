@@ -4,7 +4,7 @@ pub mod db;
 pub mod forms;
 pub mod handlers;
 
-use crate::types::ModelID;
+use crate::types::{price::Currency, ModelID};
 use serde::Serialize;
 
 /// A `Vec` of country
@@ -15,13 +15,18 @@ pub type CountryList = Vec<Country>;
 pub struct Country {
     pub id: ModelID,
     pub name: String,
+    pub currency: Currency,
 }
 
 impl Country {
     /// Creates a new Location country from the database row
     #[allow(clippy::missing_const_for_fn)]
     #[must_use]
-    pub fn from_row(id: ModelID, name: String) -> Self {
-        Self { id, name }
+    pub fn from_row(id: ModelID, name: String, currency: &str) -> Self {
+        Self {
+            id,
+            name,
+            currency: Currency::from_row(currency),
+        }
     }
 }
@@ -7,6 +7,7 @@ use crate::{
     error::{ServerError, ServerResult},
     server::state::DatabaseConnection,
     services::produce::harvest::{delete_harvest_photos, models::HarvestIndex},
+    types::geo::GeoFilter,
     types::ModelID,
     types::{ModelIdentifier, ModelIndex, Pagination},
 };
@@ -23,8 +24,13 @@ use super::{
 impl Location {
     /// Fetches farm location records from the database
     #[tracing::instrument(name = "Fetch LocationList", skip(db))]
-    pub async fn records(pg: Pagination, db: DatabaseConnection) -> ServerResult<LocationList> {
+    pub async fn records(
+        pg: Pagination,
+        geo: &GeoFilter,
+        db: DatabaseConnection,
+    ) -> ServerResult<LocationList> {
         let (offset, limit) = pg.offset_limit();
+        let (west, south, east, north) = geo.bbox_edges();
         match sqlx::query!(
             r#"
                 SELECT location_.id AS "location_id!",
@@ -35,7 +41,8 @@ impl Location {
                     farm.name AS farm_name,
                     (SELECT count(harvest.id)
                      FROM services.active_harvests harvest
-                     WHERE location_.id = harvest.location_id) AS harvests_count
+                     WHERE location_.id = harvest.location_id) AS harvests_count,
+                    distance.km AS "distance_km?"
                 FROM services.active_locations location_
                 LEFT JOIN services.farms farm
                     ON location_.farm_id = farm.id
@@ -43,13 +50,36 @@ impl Location {
                     ON location_.region_id = region.id
                 LEFT JOIN services.countries country
                     ON location_.country_id = country.id
-
-                ORDER BY location_.place_name
+                LEFT JOIN LATERAL (
+                    SELECT earth_distance(
+                        ll_to_earth($3::float8, $4::float8),
+                        ll_to_earth(location_.latitude, location_.longitude)
+                    ) / 1000 AS km
+                ) distance ON true
+
+                WHERE ($5::float8 IS NULL
+                        OR (earth_box(ll_to_earth($3, $4), $5 * 1000)
+                                @> ll_to_earth(location_.latitude, location_.longitude)
+                            AND distance.km <= $5))
+                    AND ($6::float8 IS NULL
+                        OR (location_.longitude BETWEEN $6 AND $8::float8
+                            AND location_.latitude BETWEEN $7::float8 AND $9::float8))
+
+                ORDER BY CASE WHEN $10::boolean THEN distance.km END NULLS LAST,
+                    location_.place_name
                 LIMIT $1
                 OFFSET $2;
             "#,
             limit,
-            offset
+            offset,
+            geo.near_lat(),
+            geo.near_lng(),
+            geo.radius_km,
+            west,
+            south,
+            east,
+            north,
+            geo.sort_by_distance,
         )
         .fetch_all(&db.pool)
         .await
@@ -58,7 +88,7 @@ impl Location {
                 let locations = records
                     .into_iter()
                     .map(|rec| {
-                        LocationIndex::from_row(
+                        let mut location = LocationIndex::from_row(
                             rec.location_id.into(),
                             rec.location_place_name,
                             rec.location_region,
@@ -66,7 +96,9 @@ impl Location {
                             rec.location_coords,
                             rec.farm_name,
                             rec.harvests_count,
-                        )
+                        );
+                        location.distance_km = rec.distance_km;
+                        location
                     })
                     .collect();
 
@@ -102,6 +134,11 @@ impl Location {
                     harvest.price AS "harvest_price?",
                     harvest.harvest_date AS "harvest_harvest_date?",
                     harvest.images AS harvest_images,
+                    services.harvest_low_stock(
+                        harvest.available_quantity,
+                        harvest.reserved_quantity,
+                        harvest.low_stock_threshold
+                    ) AS "harvest_low_stock!",
                     cultivar.name AS "cultivar_name?",
                     cultivar_category.name AS "cultivar_category?",
                     cultivar.image AS cultivar_image
@@ -162,6 +199,7 @@ impl Location {
                             rec.location_coords,
                             rec.farm_name,
                             rec.farm_logo,
+                            rec.harvest_low_stock,
                             0.into(), // boost amount not important
                         )
                     })
@@ -207,9 +245,13 @@ impl Location {
                     description, 
                     coords,
                     deleted,
-                    created_at
+                    created_at,
+                    search_document
                 )
-                 VALUES($1, $2, $3, $4, $5, $6, $7, false, $8);
+                 VALUES(
+                    $1, $2, $3, $4, $5, $6, $7, false, $8,
+                    services.location_search_document($3, $6)
+                );
             "#,
             location.id.0,
             location.farm_id.0,
@@ -251,7 +293,11 @@ impl Location {
                     region_id = $2,
                     country_id = COALESCE($3, location.country_id),
                     description = COALESCE($4, location.description),
-                    coords = $5
+                    coords = $5,
+                    search_document = services.location_search_document(
+                        COALESCE($1, location.place_name),
+                        COALESCE($4, location.description)
+                    )
                 WHERE location.id = $6;
             "#,
             location.place_name,
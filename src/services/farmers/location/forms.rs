@@ -16,7 +16,7 @@ use crate::{
     },
     server::state::ServerState,
     services::farmers::farm::permissions::check_user_owns_farm,
-    types::ModelID,
+    types::{geo::validate_coords, ModelID},
 };
 
 use super::permissions::check_user_owns_location;
@@ -56,6 +56,10 @@ impl LocationEmbeddedForm {
             )?;
         }
 
+        if let Some(ref coords) = self.coords {
+            validate_coords(coords)?;
+        }
+
         Ok(())
     }
 
@@ -129,6 +133,10 @@ impl LocationCreateForm {
             )?;
         }
 
+        if let Some(ref coords) = self.coords {
+            validate_coords(coords)?;
+        }
+
         Ok(())
     }
 
@@ -244,6 +252,10 @@ impl LocationUpdateForm {
             )?;
         }
 
+        if let Some(ref coords) = self.coords {
+            validate_coords(coords)?;
+        }
+
         Ok(())
     }
 
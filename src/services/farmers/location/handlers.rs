@@ -10,7 +10,7 @@ use crate::{
     endpoint::{EndpointRejection, EndpointResult},
     server::state::DatabaseConnection,
     types::ModelID,
-    types::{ModelIndex, Pagination},
+    types::{geo::GeoQuery, ModelIndex, Pagination},
 };
 
 use super::{
@@ -24,10 +24,12 @@ use super::{
 pub async fn location_list(
     _: AdminUser,
     pg: Option<Query<Pagination>>,
+    Query(geo): Query<GeoQuery>,
     State(db): State<DatabaseConnection>,
 ) -> EndpointResult<Json<LocationList>> {
     let pagination = pg.unwrap_or_default().0;
-    let locations = Location::records(pagination, db).await?;
+    let geo = geo.filter()?;
+    let locations = Location::records(pagination, &geo, db).await?;
     Ok(Json(locations))
 }
 
@@ -65,6 +65,9 @@ pub struct LocationIndex {
     pub region: Option<String>,
     pub country: String,
     pub coords: Option<Point>,
+    /// Distance in kilometres from the searched `near` point
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_km: Option<f64>,
     pub harvest_count: u64,
 }
 
@@ -88,6 +91,7 @@ impl LocationIndex {
             region,
             country,
             coords: try_into_point(coords),
+            distance_km: None,
             harvest_count: harvest_count.unwrap_or(0) as u64,
         }
     }
@@ -1,2 +1,3 @@
 pub mod farmers;
 pub mod produce;
+pub mod search;
@@ -90,9 +90,17 @@ impl CultivarCategory {
     ) -> ServerResult<()> {
         match sqlx::query!(
             r#"
-                UPDATE services.cultivar_categories category
-                SET name = COALESCE($1, category.name)
-                WHERE category.id = $2
+                WITH category AS (
+                    UPDATE services.cultivar_categories category
+                    SET name = COALESCE($1, category.name)
+                    WHERE category.id = $2
+                    RETURNING category.id, category.name
+                )
+                -- Keep the category's cultivars search documents up to date
+                UPDATE services.cultivars cultivar
+                SET search_document = services.cultivar_search_document(cultivar.name, category.name)
+                FROM category
+                WHERE cultivar.category_id = category.id
            "#,
             category.name,
             id.0
@@ -86,6 +86,11 @@ impl Cultivar {
                     harvest.price AS "harvest_price?",
                     harvest.harvest_date AS "harvest_harvest_date?",
                     harvest.images AS harvest_images,
+                    services.harvest_low_stock(
+                        harvest.available_quantity,
+                        harvest.reserved_quantity,
+                        harvest.low_stock_threshold
+                    ) AS "harvest_low_stock!",
                     farm.name AS "farm_name?",
                     farm.logo AS farm_logo,
                     location_.place_name AS "location_place_name?",
@@ -145,6 +150,7 @@ impl Cultivar {
                             rec.location_coords,
                             rec.farm_name.unwrap(),
                             rec.farm_logo,
+                            rec.harvest_low_stock,
                             0.into(), // boost amount not important
                         )
                     })
@@ -177,9 +183,20 @@ impl Cultivar {
                 INSERT INTO services.cultivars (
                     id, 
                     category_id, 
-                    name
+                    name,
+                    search_document
                 )
-                VALUES ($1, $2, $3);
+                VALUES (
+                    $1,
+                    $2,
+                    $3,
+                    services.cultivar_search_document(
+                        $3,
+                        (SELECT category.name
+                         FROM services.cultivar_categories category
+                         WHERE category.id = $2)
+                    )
+                );
             "#,
             cultivar.id.0,
             cultivar.category_id.0,
@@ -213,7 +230,13 @@ impl Cultivar {
             r#"
                 UPDATE services.cultivars cultivar
                 SET name = COALESCE($1, cultivar.name),
-                    category_id = COALESCE($2, cultivar.category_id)
+                    category_id = COALESCE($2, cultivar.category_id),
+                    search_document = services.cultivar_search_document(
+                        COALESCE($1, cultivar.name),
+                        (SELECT category.name
+                         FROM services.cultivar_categories category
+                         WHERE category.id = COALESCE($2, cultivar.category_id))
+                    )
                 WHERE cultivar.id = $3
            "#,
             cultivar.name,
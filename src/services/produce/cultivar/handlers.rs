@@ -102,7 +102,7 @@ pub async fn cultivar_image_upload(
     handler.accept().await?; // Receive file from the client
     if let Some(file) = uploads.files().await {
         // Save an image to the file system
-        let paths = file.save_image(CULTIVAR_UPLOAD_DIR).await?;
+        let paths = file.queue_image(CULTIVAR_UPLOAD_DIR, db.clone()).await?;
 
         // Save image path to the database
         let (path, old_image) = Cultivar::insert_photo(cultivar_id, paths.clone(), db).await?;
@@ -1,76 +1,46 @@
 //! Harvest feed impls
 
-use std::future;
-
 use axum::{extract::State, Json};
 use axum_extra::extract::Query;
-use futures_util::{StreamExt, TryStreamExt};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-// use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
+use uuid::Uuid;
 
 use crate::{
-    endpoint::{validators::TransformString, EndpointRejection, EndpointResult},
+    auth::CurrentUser,
+    endpoint::{
+        validators::{TransformString, ValidateString},
+        EndpointRejection, EndpointResult,
+    },
+    features::harvest_wishlist::mark_saved,
     server::state::DatabaseConnection,
-    types::ModelID,
+    types::{geo::GeoQuery, price::Currency, ModelID},
+    HARVEST_FEED_MAX_LIMIT,
 };
 
 use super::harvest::models::{Harvest, HarvestList};
 
 /// Handles the `GET /harvests/feed` route.
-#[tracing::instrument(skip(db))]
+#[tracing::instrument(skip(db, user))]
 pub async fn harvest_feed(
+    user: Option<CurrentUser>,
     filters: Query<HarvestFilter>,
+    Query(geo): Query<GeoQuery>,
     State(db): State<DatabaseConnection>,
 ) -> EndpointResult<Json<HarvestFeed>> {
-    let cultivars = filters.cultivars();
-    let regions = filters.regions();
-    let skip_id = filters.offset_id();
-
-    let mut harvests: Vec<_> = Harvest::stream(&db)
-        .await
-        // Offset
-        .try_skip_while(|harvest| {
-            // Skips harvests until the skip_id.
-            // if skip_id is None all harvests will be returned until the limit.
-            // if skip_id is not a valid harvest_id no harvest will be returned.
-            future::ready(skip_id.map_or_else(|| Ok(false), |id| Ok(harvest.id != id)))
-        })
-        // Filters
-        .try_filter(|harvest| {
-            // Filters harvests, if no filters are available
-            // the all harvests will pass the filter, otherwise
-            // only those matched the filters will pass.
-            future::ready(match (cultivars.is_empty(), regions.is_empty()) {
-                (true, true) => true,
-                (false, false) => {
-                    cultivars.contains(&harvest.name) && regions.contains(&harvest.region)
-                }
-                (false, true) => cultivars.contains(&harvest.name),
-                (true, false) => regions.contains(&harvest.region),
-            })
-        })
-        // Limit
-        .take(filters.limit + 1)
-        .try_collect()
-        .await
-        .map_err(|_err| EndpointRejection::internal_server_error())?;
+    filters.validate()?;
+    let geo = geo.filter()?;
+    // Fetches one extra harvest, its id is used as the next page offset.
+    let mut harvests = Harvest::feed(&filters, &geo, db.clone()).await?;
 
     // Get the next skip_id
-    let offset: Option<ModelID> = harvests.pop().map(|h| h.id);
-
-    // // Sort Harvests. Harvests are sorted by boost_amount
-    // // and then with available_at date relative to today's date.
-    // let today = OffsetDateTime::now_utc().date();
-    // harvests.sort_unstable_by(|a, b| {
-    //     a.boost_amount
-    //         .cmp(&b.boost_amount)
-    //         .then_with(|| {
-    //             ((today - a.available_at).abs())
-    //                 .cmp(&(today - b.available_at).abs())
-    //                 .reverse()
-    //         })
-    //         .reverse()
-    // });
+    let offset: Option<ModelID> = if harvests.len() > filters.limit {
+        harvests.pop().map(|h| h.id)
+    } else {
+        None
+    };
+    mark_saved(user.as_ref(), &mut harvests, db).await?;
 
     Ok(Json(HarvestFeed { harvests, offset }))
 }
@@ -87,7 +57,7 @@ pub struct HarvestFeed {
 }
 
 /// `harvests/feed` query parameters.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HarvestFilter {
     /// filters for cultivar name
@@ -96,14 +66,75 @@ pub struct HarvestFilter {
     /// filters for region name
     #[serde(default)]
     pub region: Vec<String>,
+    /// filters for cultivar category name
+    #[serde(default)]
+    pub category: Vec<String>,
+    /// filters for country name
+    #[serde(default)]
+    pub country: Vec<String>,
+    /// filters for price unit, e.g. `kg`, `litre`, `crate` or `dozen`
+    #[serde(default)]
+    pub unit: Vec<String>,
+    /// filters for farm id
+    #[serde(default, alias = "farm_id")]
+    pub farm_id: Vec<String>,
+    /// minimum price per normalised unit
+    #[serde(default, alias = "min_price")]
+    pub min_price: Option<Decimal>,
+    /// maximum price per normalised unit
+    #[serde(default, alias = "max_price")]
+    pub max_price: Option<Decimal>,
+    /// filters for price currency, defaults to the currency of the `country`
+    /// filter, prices are only filtered and sorted within one currency
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    /// harvests harvested on or after this date
+    #[serde(default, alias = "harvest_date_from")]
+    pub harvest_date_from: Option<Date>,
+    /// harvests harvested on or before this date
+    #[serde(default, alias = "harvest_date_to")]
+    pub harvest_date_to: Option<Date>,
+    /// harvests ordering, harvests are always ordered by boost amount first
+    #[serde(default)]
+    pub sort: Option<HarvestSort>,
 
     /// `skip_id` - position in the result set.
-    /// query's harvests starting from this harvest_id.
+    /// query's harvests starting from this `harvest_id`.
     #[serde(default)]
     pub offset: Option<String>,
     /// maximum number of harvest should be returned
     #[serde(default = " default_harvests_len_limit")]
     pub limit: usize,
+
+    /// harvests listed after this time, used by saved search alerts
+    #[serde(skip)]
+    pub listed_after: Option<OffsetDateTime>,
+    /// harvests listed at or before this time, used by saved search alerts
+    #[serde(skip)]
+    pub listed_before: Option<OffsetDateTime>,
+}
+
+impl Default for HarvestFilter {
+    fn default() -> Self {
+        Self {
+            cultivar: Vec::new(),
+            region: Vec::new(),
+            category: Vec::new(),
+            country: Vec::new(),
+            unit: Vec::new(),
+            farm_id: Vec::new(),
+            min_price: None,
+            max_price: None,
+            currency: None,
+            harvest_date_from: None,
+            harvest_date_to: None,
+            sort: None,
+            offset: None,
+            limit: default_harvests_len_limit(),
+            listed_after: None,
+            listed_before: None,
+        }
+    }
 }
 
 /// Default maximum number of harvests returned `20`
@@ -112,7 +143,46 @@ const fn default_harvests_len_limit() -> usize {
 }
 
 impl HarvestFilter {
+    /// Validates the filters
+    pub fn validate(&self) -> EndpointResult<()> {
+        if self.limit == 0 || self.limit > HARVEST_FEED_MAX_LIMIT {
+            return Err(EndpointRejection::BadRequest(
+                format!("limit must be between 1 and {HARVEST_FEED_MAX_LIMIT}").into(),
+            ));
+        }
+        if let (Some(min), Some(max)) = (self.min_price, self.max_price) {
+            if min > max {
+                return Err(EndpointRejection::BadRequest(
+                    "min_price must be less than or equal to max_price".into(),
+                ));
+            }
+        }
+        if let (Some(from), Some(to)) = (self.harvest_date_from, self.harvest_date_to) {
+            if from > to {
+                return Err(EndpointRejection::BadRequest(
+                    "harvest_date_from must be on or before harvest_date_to".into(),
+                ));
+            }
+        }
+        let by_price = self.min_price.is_some()
+            || self.max_price.is_some()
+            || matches!(
+                self.sort,
+                Some(HarvestSort::PriceAsc | HarvestSort::PriceDesc)
+            );
+        if by_price && self.currency.is_none() && self.country.len() != 1 {
+            return Err(EndpointRejection::BadRequest(
+                "currency or a single country is required to filter or sort by price".into(),
+            ));
+        }
+        for id in &self.farm_id {
+            id.validate_id("Invalid farm id")?;
+        }
+        Ok(())
+    }
+
     /// `skip_id` - position in the result set.
+    #[must_use]
     pub fn offset_id(&self) -> Option<ModelID> {
         self.offset
             .as_ref()
@@ -120,12 +190,72 @@ impl HarvestFilter {
     }
 
     /// Cleaned cultivar name filters
+    #[must_use]
     pub fn cultivars(&self) -> Vec<String> {
         self.cultivar.iter().map(|c| c.to_titlecase()).collect()
     }
 
     /// Cleaned region name filters
-    pub fn regions(&self) -> Vec<Option<String>> {
-        self.region.iter().map(|r| Some(r.to_titlecase())).collect()
+    #[must_use]
+    pub fn regions(&self) -> Vec<String> {
+        self.region.iter().map(|r| r.to_titlecase()).collect()
+    }
+
+    /// Cleaned cultivar category name filters
+    #[must_use]
+    pub fn categories(&self) -> Vec<String> {
+        self.category.iter().map(|c| c.to_titlecase()).collect()
+    }
+
+    /// Cleaned country name filters
+    #[must_use]
+    pub fn countries(&self) -> Vec<String> {
+        self.country.iter().map(|c| c.to_titlecase()).collect()
+    }
+
+    /// Cleaned price unit filters
+    #[must_use]
+    pub fn units(&self) -> Vec<String> {
+        self.unit.iter().map(|u| u.trim().to_lowercase()).collect()
+    }
+
+    /// Farm id filters, invalid ids are rejected by `validate`
+    #[must_use]
+    pub fn farm_ids(&self) -> Vec<Uuid> {
+        self.farm_id
+            .iter()
+            .filter_map(|id| ModelID::try_from(id.as_str()).ok())
+            .map(|id| id.0)
+            .collect()
+    }
+}
+
+/// Harvest feed ordering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HarvestSort {
+    /// Cheapest price per normalised unit first
+    PriceAsc,
+    /// Most expensive price per normalised unit first
+    PriceDesc,
+    /// Most recently listed first
+    Newest,
+    /// Earliest harvest date first
+    HarvestDate,
+    /// Nearest to the `near` point first
+    Distance,
+}
+
+impl HarvestSort {
+    /// Returns the sort as used in the feed query
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::PriceAsc => "price_asc",
+            Self::PriceDesc => "price_desc",
+            Self::Newest => "newest",
+            Self::HarvestDate => "harvest_date",
+            Self::Distance => "distance",
+        }
     }
 }
@@ -1,94 +1,244 @@
 //! Harvest database impl
 
-use futures_util::stream::{Stream, TryStreamExt};
-
 use crate::{
     endpoint::EndpointRejection,
     error::{ServerError, ServerResult},
     server::state::DatabaseConnection,
+    services::produce::{HarvestFilter, HarvestSort},
+    types::geo::GeoFilter,
+    types::price::Currency,
     types::ModelID,
     types::Pagination,
 };
 
 use super::{
-    forms::{HarvestInsertData, HarvestUpdateData},
-    models::{Harvest, HarvestIndex, HarvestList},
+    forms::{HarvestInsertData, HarvestStockChangeData, HarvestUpdateData},
+    models::{
+        Harvest, HarvestIndex, HarvestList, HarvestStock, HarvestStockChange, HarvestStockLevel,
+    },
     utils::{delete_harvest_photos, delete_or_archive_harvest, find_delete_harvest},
 };
 
 impl Harvest {
-    /// Fetches a stream of harvest records from the database
-    #[tracing::instrument(name = "Fetch HarvestStream", skip(db))]
-    pub async fn stream<'a>(
-        db: &'a DatabaseConnection,
-    ) -> impl Stream<Item = Result<HarvestIndex, sqlx::Error>> + 'a {
+    /// Fetches a page of the harvest feed from the database
+    ///
+    /// Harvests are ordered by boost amount, then by the requested sort,
+    /// by default how close their `harvest_date` is to today, and then by id,
+    /// the `offset` harvest marks where the page starts in that ordering.
+    #[tracing::instrument(name = "Fetch HarvestFeed", skip(db))]
+    #[allow(clippy::cast_possible_wrap, clippy::too_many_lines)]
+    pub async fn feed(
+        filter: &HarvestFilter,
+        geo: &GeoFilter,
+        db: DatabaseConnection,
+    ) -> ServerResult<HarvestList> {
         //NB! Don't forget to select harvests from services.active_harvests
         let today = time::OffsetDateTime::now_utc().date();
-        sqlx::query!(
+        let cultivars = filter.cultivars();
+        let regions = filter.regions();
+        let categories = filter.categories();
+        let countries = filter.countries();
+        let units = filter.units();
+        let farm_ids = filter.farm_ids();
+        // if offset is not an active harvest_id no harvest will be returned.
+        let offset = filter.offset_id().map(|id| id.0);
+        let (west, south, east, north) = geo.bbox_edges();
+        match sqlx::query!(
             r#"
-                SELECT harvest.id AS "harvest_id!",
-                    harvest.cultivar_id,
-                    harvest.price AS "harvest_price!",
-                    harvest.harvest_date AS "harvest_harvest_date!",
-                    harvest.images AS harvest_images,
-                    cultivar.name AS cultivar_name,
-                    cultivar_category.name AS cultivar_category,
-                    cultivar.image AS cultivar_image, 
-                    farm.name AS farm_name,
-                    farm.logo AS farm_logo,
-                    location_.place_name AS location_place_name,
-                    location_.coords AS location_coords,
-                    region.name AS "location_region?",
-                    country.name AS location_country,
-                    subscription.amount AS "boost_amount?",
-                    subscription.expires_at AS "subscription_expires_at?"
-                FROM services.active_harvests harvest
-                LEFT JOIN services.cultivars cultivar
-                    ON harvest.cultivar_id = cultivar.id
-                LEFT JOIN services.cultivar_categories cultivar_category
-                    ON cultivar.category_id = cultivar_category.id
-                LEFT JOIN services.locations location_
-                    ON harvest.location_id = location_.id
-                LEFT JOIN services.farms farm
-                    ON location_.farm_id = farm.id
-                LEFT JOIN services.regions region
-                    ON location_.region_id = region.id
-                LEFT JOIN services.countries country
-                    ON location_.country_id = country.id
-
-                LEFT JOIN features.harvest_subscriptions subscription
-                    ON harvest.id  = subscription.harvest_id
-
-                ORDER BY subscription.amount DESC NULLS LAST,
-                    greatest(AGE(harvest.harvest_date), -AGE(harvest.harvest_date));
+                -- Prices are only compared within one currency
+                WITH feed_currency AS (
+                    SELECT COALESCE($23::text, (
+                        SELECT country_.currency
+                        FROM services.countries country_
+                        WHERE cardinality($15::text[]) = 1
+                            AND country_.name = $15[1]
+                    )) AS code
+                ),
+                feed AS (
+                    SELECT harvest.id AS harvest_id,
+                        harvest.price AS harvest_price,
+                        harvest.harvest_date AS harvest_harvest_date,
+                        harvest.images AS harvest_images,
+                        services.harvest_low_stock(
+                            harvest.available_quantity,
+                            harvest.reserved_quantity,
+                            harvest.low_stock_threshold
+                        ) AS harvest_low_stock,
+                        cultivar.name AS cultivar_name,
+                        cultivar_category.name AS cultivar_category,
+                        cultivar.image AS cultivar_image,
+                        farm.name AS farm_name,
+                        farm.logo AS farm_logo,
+                        location_.place_name AS location_place_name,
+                        location_.coords AS location_coords,
+                        region.name AS location_region,
+                        country.name AS location_country,
+                        distance.km AS distance_km,
+                        CASE WHEN subscription.expires_at >= $4
+                            THEN COALESCE(subscription.amount, 0)
+                            ELSE 0
+                        END AS boost_amount,
+                        CASE $13::text
+                            WHEN 'distance' THEN COALESCE(distance.km, 'infinity')
+                            WHEN 'price_asc' THEN
+                                COALESCE(services.price_per_unit(harvest.price)::float8, 'infinity')
+                            WHEN 'price_desc' THEN
+                                COALESCE(-services.price_per_unit(harvest.price)::float8, 'infinity')
+                            WHEN 'newest' THEN -extract(epoch FROM harvest.created_at)::float8
+                            WHEN 'harvest_date' THEN (harvest.harvest_date - $4)::float8
+                            ELSE abs(harvest.harvest_date - $4)::float8
+                        END AS sort_key
+                    FROM services.active_harvests harvest
+                    LEFT JOIN services.cultivars cultivar
+                        ON harvest.cultivar_id = cultivar.id
+                    LEFT JOIN services.cultivar_categories cultivar_category
+                        ON cultivar.category_id = cultivar_category.id
+                    LEFT JOIN services.locations location_
+                        ON harvest.location_id = location_.id
+                    LEFT JOIN services.farms farm
+                        ON location_.farm_id = farm.id
+                    LEFT JOIN services.regions region
+                        ON location_.region_id = region.id
+                    LEFT JOIN services.countries country
+                        ON location_.country_id = country.id
+                    LEFT JOIN LATERAL (
+                        SELECT earth_distance(
+                            ll_to_earth($6::float8, $7::float8),
+                            ll_to_earth(location_.latitude, location_.longitude)
+                        ) / 1000 AS km
+                    ) distance ON true
+
+                    LEFT JOIN features.harvest_subscriptions subscription
+                        ON harvest.id  = subscription.harvest_id
+                    CROSS JOIN feed_currency
+
+                    WHERE (cardinality($1::text[]) = 0 OR cultivar.name = ANY($1))
+                        AND (cardinality($2::text[]) = 0 OR region.name = ANY($2))
+                        AND (cardinality($14::text[]) = 0 OR cultivar_category.name = ANY($14))
+                        AND (feed_currency.code IS NULL
+                            OR harvest.price->>'currency' = feed_currency.code)
+                        AND (cardinality($15::text[]) = 0 OR country.name = ANY($15))
+                        AND ($16::numeric IS NULL OR services.price_per_unit(harvest.price) >= $16)
+                        AND ($17::numeric IS NULL OR services.price_per_unit(harvest.price) <= $17)
+                        AND ($18::date IS NULL OR harvest.harvest_date >= $18)
+                        AND ($19::date IS NULL OR harvest.harvest_date <= $19)
+                        AND (cardinality($20::text[]) = 0 OR services.price_unit(harvest.price) = ANY($20))
+                        AND (cardinality($21::uuid[]) = 0 OR farm.id = ANY($21))
+                        AND ($22::timestamptz IS NULL OR harvest.created_at > $22)
+                        AND ($24::timestamptz IS NULL OR harvest.created_at <= $24)
+                        AND ($8::float8 IS NULL
+                            OR (earth_box(ll_to_earth($6, $7), $8 * 1000)
+                                    @> ll_to_earth(location_.latitude, location_.longitude)
+                                AND distance.km <= $8))
+                        AND ($9::float8 IS NULL
+                            OR (location_.longitude BETWEEN $9 AND $11::float8
+                                AND location_.latitude BETWEEN $10::float8 AND $12::float8))
+                )
+                SELECT feed.harvest_id AS "harvest_id!",
+                    feed.harvest_price AS "harvest_price!",
+                    feed.harvest_harvest_date AS "harvest_harvest_date!",
+                    feed.harvest_images,
+                    feed.harvest_low_stock AS "harvest_low_stock!",
+                    feed.cultivar_name AS "cultivar_name!",
+                    feed.cultivar_category AS "cultivar_category!",
+                    feed.cultivar_image,
+                    feed.farm_name AS "farm_name!",
+                    feed.farm_logo,
+                    feed.location_place_name AS "location_place_name!",
+                    feed.location_coords,
+                    feed.location_region AS "location_region?",
+                    feed.location_country AS "location_country!",
+                    feed.distance_km AS "distance_km?",
+                    feed.boost_amount AS "boost_amount!"
+                FROM feed
+
+                -- Keyset pagination: only harvests ordered at or after the offset harvest
+                WHERE $3::uuid IS NULL
+                    OR (-feed.boost_amount, feed.sort_key, feed.harvest_id) >= (
+                        SELECT -cursor_.boost_amount, cursor_.sort_key, cursor_.harvest_id
+                        FROM feed cursor_
+                        WHERE cursor_.harvest_id = $3
+                    )
+
+                ORDER BY feed.boost_amount DESC,
+                    feed.sort_key,
+                    feed.harvest_id
+                LIMIT $5;
             "#,
+            &cultivars[..],
+            &regions[..],
+            offset,
+            today,
+            filter.limit as i64 + 1,
+            geo.near_lat(),
+            geo.near_lng(),
+            geo.radius_km,
+            west,
+            south,
+            east,
+            north,
+            filter.sort.map(HarvestSort::as_str),
+            &categories[..],
+            &countries[..],
+            filter.min_price,
+            filter.max_price,
+            filter.harvest_date_from,
+            filter.harvest_date_to,
+            &units[..],
+            &farm_ids[..],
+            filter.listed_after,
+            filter.currency.map(Currency::code),
+            filter.listed_before,
         )
-        .fetch(&db.pool)
-        .map_ok(move |rec| {
-            HarvestIndex::from_row(
-                rec.harvest_id.into(),
-                rec.harvest_price,
-                rec.harvest_harvest_date,
-                rec.harvest_images,
-                rec.cultivar_name,
-                rec.cultivar_category,
-                rec.cultivar_image,
-                rec.location_place_name,
-                rec.location_region,
-                rec.location_country,
-                rec.location_coords,
-                rec.farm_name,
-                rec.farm_logo,
-                calc_boost_amount(rec.boost_amount, rec.subscription_expires_at, today),
-            )
-        })
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let harvests = records
+                    .into_iter()
+                    .map(|rec| {
+                        let mut harvest = HarvestIndex::from_row(
+                            rec.harvest_id.into(),
+                            rec.harvest_price,
+                            rec.harvest_harvest_date,
+                            rec.harvest_images,
+                            rec.cultivar_name,
+                            rec.cultivar_category,
+                            rec.cultivar_image,
+                            rec.location_place_name,
+                            rec.location_region,
+                            rec.location_country,
+                            rec.location_coords,
+                            rec.farm_name,
+                            rec.farm_logo,
+                            rec.harvest_low_stock,
+                            rec.boost_amount,
+                        );
+                        harvest.distance_km = rec.distance_km;
+                        harvest
+                    })
+                    .collect();
+
+                Ok(harvests)
+            }
+
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch harvest feed: {}", err);
+                Err(err.into())
+            }
+        }
     }
 
     /// Fetches harvest records from the database
     #[tracing::instrument(name = "Fetch HarvestList", skip(db))]
-    pub async fn records(pg: Pagination, db: DatabaseConnection) -> ServerResult<HarvestList> {
+    pub async fn records(
+        pg: Pagination,
+        geo: &GeoFilter,
+        db: DatabaseConnection,
+    ) -> ServerResult<HarvestList> {
         //NB! Don't forget to select harvests from services.active_harvests
         let (offset, limit) = pg.offset_limit();
+        let (west, south, east, north) = geo.bbox_edges();
         match sqlx::query!(
             r#"
                 SELECT harvest.id AS "harvest_id!",
@@ -96,6 +246,11 @@ impl Harvest {
                     harvest.price AS "harvest_price!",
                     harvest.harvest_date AS "harvest_harvest_date!",
                     harvest.images AS harvest_images,
+                    services.harvest_low_stock(
+                        harvest.available_quantity,
+                        harvest.reserved_quantity,
+                        harvest.low_stock_threshold
+                    ) AS "harvest_low_stock!",
                     cultivar.name AS cultivar_name,
                     cultivar_category.name AS cultivar_category,
                     cultivar.image AS cultivar_image, 
@@ -106,7 +261,8 @@ impl Harvest {
                     region.name AS "location_region?",
                     country.name AS location_country,
                     subscription.amount AS "boost_amount?",
-                    subscription.expires_at AS "subscription_expires_at?"
+                    subscription.expires_at AS "subscription_expires_at?",
+                    distance.km AS "distance_km?"
                 FROM services.active_harvests harvest
                 LEFT JOIN services.cultivars cultivar
                     ON harvest.cultivar_id = cultivar.id
@@ -121,15 +277,39 @@ impl Harvest {
                 LEFT JOIN services.countries country
                     ON location_.country_id = country.id
 
+                LEFT JOIN LATERAL (
+                    SELECT earth_distance(
+                        ll_to_earth($3::float8, $4::float8),
+                        ll_to_earth(location_.latitude, location_.longitude)
+                    ) / 1000 AS km
+                ) distance ON true
+
                 LEFT JOIN features.harvest_subscriptions subscription
                     ON harvest.id  = subscription.harvest_id
 
-                ORDER BY harvest.created_at
+                WHERE ($5::float8 IS NULL
+                        OR (earth_box(ll_to_earth($3, $4), $5 * 1000)
+                                @> ll_to_earth(location_.latitude, location_.longitude)
+                            AND distance.km <= $5))
+                    AND ($6::float8 IS NULL
+                        OR (location_.longitude BETWEEN $6 AND $8::float8
+                            AND location_.latitude BETWEEN $7::float8 AND $9::float8))
+
+                ORDER BY CASE WHEN $10::boolean THEN distance.km END NULLS LAST,
+                    harvest.created_at
                 LIMIT $1
                 OFFSET $2;
             "#,
             limit,
-            offset
+            offset,
+            geo.near_lat(),
+            geo.near_lng(),
+            geo.radius_km,
+            west,
+            south,
+            east,
+            north,
+            geo.sort_by_distance,
         )
         .fetch_all(&db.pool)
         .await
@@ -139,7 +319,7 @@ impl Harvest {
                 let harvests = records
                     .into_iter()
                     .map(|rec| {
-                        HarvestIndex::from_row(
+                        let mut harvest = HarvestIndex::from_row(
                             rec.harvest_id.into(),
                             rec.harvest_price,
                             rec.harvest_harvest_date,
@@ -153,8 +333,11 @@ impl Harvest {
                             rec.location_coords,
                             rec.farm_name,
                             rec.farm_logo,
+                            rec.harvest_low_stock,
                             calc_boost_amount(rec.boost_amount, rec.subscription_expires_at, today),
-                        )
+                        );
+                        harvest.distance_km = rec.distance_km;
+                        harvest
                     })
                     .collect();
 
@@ -181,6 +364,14 @@ impl Harvest {
                     harvest.type AS harvest_type,
                     harvest.description AS harvest_description,
                     harvest.images AS harvest_images,
+                    harvest.available_quantity AS harvest_available_quantity,
+                    harvest.reserved_quantity AS "harvest_reserved_quantity!",
+                    harvest.quantity_unit AS harvest_quantity_unit,
+                    services.harvest_low_stock(
+                        harvest.available_quantity,
+                        harvest.reserved_quantity,
+                        harvest.low_stock_threshold
+                    ) AS "harvest_low_stock!",
                     harvest.created_at AS "harvest_created_at!",
                     cultivar.name AS cultivar_name,
                     cultivar_category.name AS cultivar_category,
@@ -225,7 +416,7 @@ impl Harvest {
         .await
         {
             Ok(rec) => {
-                let harvest = Self::from_row(
+                let mut harvest = Self::from_row(
                     rec.harvest_id.into(),
                     rec.harvest_price,
                     rec.harvest_type,
@@ -252,6 +443,14 @@ impl Harvest {
                     rec.farm_owner_last_name,
                     rec.farm_owner_photo,
                 );
+                harvest.stock = rec.harvest_available_quantity.map(|available_quantity| {
+                    HarvestStockLevel::from_row(
+                        available_quantity,
+                        rec.harvest_reserved_quantity,
+                        rec.harvest_quantity_unit.as_deref(),
+                        rec.harvest_low_stock,
+                    )
+                });
 
                 Ok(Some(harvest))
             }
@@ -275,18 +474,43 @@ impl Harvest {
     ) -> ServerResult<ModelID> {
         match sqlx::query!(
             r#"
-                INSERT INTO services.harvests(
+                WITH harvest_ AS (
+                    INSERT INTO services.harvests(
+                        id,
+                        cultivar_id,
+                        location_id, 
+                        price, 
+                        type, 
+                        description,
+                        harvest_date, 
+                        created_at,
+                        finished,
+                        search_document,
+                        available_quantity,
+                        quantity_unit,
+                        low_stock_threshold
+                    )
+                    VALUES(
+                        $1, $2, $3, $4, $5, $6, $7, $8, false,
+                        services.harvest_search_document($5, $6),
+                        $9, $10, $11
+                    )
+                    RETURNING id, available_quantity
+                )
+                -- Record the initial stock
+                INSERT INTO services.harvest_stock_changes(
                     id,
-                    cultivar_id,
-                    location_id, 
-                    price, 
-                    type, 
-                    description,
-                    harvest_date, 
-                    created_at,
-                    finished
+                    harvest_id,
+                    quantity_change,
+                    available_quantity,
+                    reserved_quantity,
+                    reason,
+                    created_at
                 )
-                VALUES($1, $2, $3, $4, $5, $6, $7, $8, false);
+                SELECT $12, harvest_.id, harvest_.available_quantity,
+                    harvest_.available_quantity, 0, 'Initial stock', $8
+                FROM harvest_
+                WHERE harvest_.available_quantity IS NOT NULL;
             "#,
             harvest.id.0,
             harvest.cultivar_id.0,
@@ -295,7 +519,11 @@ impl Harvest {
             harvest.r#type,
             harvest.description,
             harvest.harvest_date,
-            harvest.created_at
+            harvest.created_at,
+            harvest.available_quantity,
+            harvest.quantity_unit,
+            harvest.low_stock_threshold,
+            ModelID::new().0,
         )
         .execute(&db.pool)
         .await
@@ -330,7 +558,13 @@ impl Harvest {
                     type = $4,
                     description = $5,
                     harvest_date = COALESCE($6, harvest.harvest_date), 
-                    updated_at = $7
+                    updated_at = $7,
+                    search_document = services.harvest_search_document($4, $5),
+                    quantity_unit = COALESCE($9, harvest.quantity_unit),
+                    low_stock_threshold = CASE
+                        WHEN $11 THEN NULL
+                        ELSE COALESCE($10, harvest.low_stock_threshold)
+                    END
                 WHERE harvest.id = $8;
             "#,
             harvest.cultivar_id.0,
@@ -341,6 +575,9 @@ impl Harvest {
             harvest.harvest_date,
             harvest.updated_at,
             id.0,
+            harvest.quantity_unit,
+            harvest.low_stock_threshold,
+            harvest.clear_low_stock_threshold,
         )
         .execute(&db.pool)
         .await
@@ -465,6 +702,188 @@ impl Harvest {
     }
 }
 
+impl HarvestStock {
+    /// Fetches harvest stock and its adjustments history from the database
+    #[tracing::instrument(name = "Find HarvestStock", skip(db))]
+    pub async fn find(
+        harvest_id: ModelID,
+        pg: Option<Pagination>,
+        db: DatabaseConnection,
+    ) -> ServerResult<Option<Self>> {
+        match sqlx::query!(
+            r#"
+                SELECT harvest.available_quantity AS harvest_available_quantity,
+                    harvest.reserved_quantity AS harvest_reserved_quantity,
+                    harvest.quantity_unit AS harvest_quantity_unit,
+                    harvest.low_stock_threshold AS harvest_low_stock_threshold,
+                    services.harvest_low_stock(
+                        harvest.available_quantity,
+                        harvest.reserved_quantity,
+                        harvest.low_stock_threshold
+                    ) AS "harvest_low_stock!"
+                FROM services.harvests harvest
+                WHERE harvest.id = $1;
+            "#,
+            harvest_id.0,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(Some(rec)) => {
+                let stock = rec.harvest_available_quantity.map(|available_quantity| {
+                    HarvestStockLevel::from_row(
+                        available_quantity,
+                        rec.harvest_reserved_quantity,
+                        rec.harvest_quantity_unit.as_deref(),
+                        rec.harvest_low_stock,
+                    )
+                });
+                let changes = harvest_stock_changes(harvest_id, pg, db).await?;
+
+                Ok(Some(Self {
+                    harvest_id,
+                    stock,
+                    low_stock_threshold: rec.harvest_low_stock_threshold,
+                    changes,
+                }))
+            }
+            Ok(None) => Ok(None),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch harvest stock: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Adjusts the harvest available quantity and records the change
+    ///
+    /// The harvest is marked `finished` when its available quantity reaches zero.
+    #[tracing::instrument(name = "Adjust HarvestStock", skip(db, change))]
+    pub async fn adjust(
+        harvest_id: ModelID,
+        change: HarvestStockChangeData,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        match sqlx::query!(
+            r#"
+                WITH harvest_ AS (
+                    UPDATE services.harvests harvest
+                    SET available_quantity = COALESCE(harvest.available_quantity, 0) + $2,
+                        finished = COALESCE(harvest.available_quantity, 0) + $2 = 0,
+                        finished_at = CASE WHEN COALESCE(harvest.available_quantity, 0) + $2 = 0
+                            THEN $5::date
+                            ELSE harvest.finished_at
+                        END,
+                        quantity_unit = COALESCE(harvest.quantity_unit, services.price_unit(harvest.price)),
+                        updated_at = $6
+                    WHERE harvest.id = $1
+                        AND harvest.finished = false
+                    RETURNING harvest.id, harvest.available_quantity, harvest.reserved_quantity
+                )
+                INSERT INTO services.harvest_stock_changes(
+                    id,
+                    harvest_id,
+                    author_id,
+                    quantity_change,
+                    available_quantity,
+                    reserved_quantity,
+                    reason,
+                    created_at
+                )
+                SELECT $7, harvest_.id, $3, $2,
+                    harvest_.available_quantity, harvest_.reserved_quantity, $4, $6
+                FROM harvest_
+                RETURNING harvest_id;
+            "#,
+            harvest_id.0,
+            change.quantity_change,
+            change.author_id.map(|id| id.0),
+            change.reason,
+            change.created_at.date(),
+            change.created_at,
+            change.id.0,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(Some(result)) => {
+                tracing::debug!("Harvest stock adjusted successfully: {:?}", result);
+                Ok(())
+            }
+            // The harvest does not exist or is already finished
+            Ok(None) => Err(ServerError::rejection(EndpointRejection::BadRequest(
+                "Harvest not found or already finished.".into(),
+            ))),
+            Err(err) => {
+                // Handle database constraint error
+                handle_harvest_database_error(&err)?;
+
+                tracing::error!("Database error, failed to adjust harvest stock: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Fetches the harvest stock adjustments history from the database,
+/// the most recent adjustments are returned first.
+async fn harvest_stock_changes(
+    harvest_id: ModelID,
+    pg: Option<Pagination>,
+    db: DatabaseConnection,
+) -> ServerResult<Vec<HarvestStockChange>> {
+    let (offset, limit) = pg.unwrap_or_default().offset_limit();
+    match sqlx::query!(
+        r#"
+            SELECT change_.id AS change_id,
+                change_.quantity_change AS change_quantity_change,
+                change_.available_quantity AS change_available_quantity,
+                change_.reserved_quantity AS change_reserved_quantity,
+                change_.reason AS change_reason,
+                change_.author_id AS change_author_id,
+                change_.created_at AS change_created_at
+            FROM services.harvest_stock_changes change_
+
+            WHERE change_.harvest_id = $1
+            ORDER BY change_.created_at DESC
+            LIMIT $2
+            OFFSET $3;
+        "#,
+        harvest_id.0,
+        limit,
+        offset
+    )
+    .fetch_all(&db.pool)
+    .await
+    {
+        Ok(records) => {
+            let changes = records
+                .into_iter()
+                .map(|rec| {
+                    HarvestStockChange::from_row(
+                        rec.change_id.into(),
+                        rec.change_quantity_change,
+                        rec.change_available_quantity,
+                        rec.change_reserved_quantity,
+                        rec.change_reason,
+                        rec.change_author_id.map(Into::into),
+                        rec.change_created_at,
+                    )
+                })
+                .collect();
+
+            Ok(changes)
+        }
+        Err(err) => {
+            tracing::error!(
+                "Database error, failed to fetch harvest stock changes: {}",
+                err
+            );
+            Err(err.into())
+        }
+    }
+}
+
 /// Get boost amount
 #[must_use]
 fn calc_boost_amount(
@@ -493,6 +912,19 @@ pub fn handle_harvest_database_error(err: &sqlx::Error) -> ServerResult<()> {
                 "Harvest with the same cultivar name already exists.".into(),
             )));
         }
+        // Handle db check constraints
+        if db_err.is_check_violation() {
+            if db_err.constraint() == Some("harvests_reserved_quantity_check") {
+                tracing::error!("Database error, stock below reserved quantity. {:?}", err);
+                return Err(ServerError::rejection(EndpointRejection::BadRequest(
+                    "Harvest available quantity cannot be less than the reserved quantity.".into(),
+                )));
+            }
+            tracing::error!("Database error, invalid harvest stock quantity. {:?}", err);
+            return Err(ServerError::rejection(EndpointRejection::BadRequest(
+                "Harvest stock quantities cannot be negative.".into(),
+            )));
+        }
         // Handle db foreign key constraints
         if db_err.is_foreign_key_violation() {
             if let Some(constraint) = db_err.constraint() {
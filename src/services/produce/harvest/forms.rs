@@ -4,6 +4,7 @@ use axum::{
     async_trait,
     extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Json, Request},
 };
+use rust_decimal::Decimal;
 use serde::Deserialize;
 use time::{Date, OffsetDateTime};
 
@@ -15,12 +16,15 @@ use crate::{
     },
     server::state::ServerState,
     services::farmers::location::permissions::check_user_owns_location,
-    types::{price::Price, ModelID},
+    types::{
+        price::{Price, QuantityUnit},
+        ModelID,
+    },
 };
 
-use helpers::{validate_harvest_date, validate_price};
+use helpers::{fill_price_currency, validate_harvest_date, validate_price, validate_quantity};
 
-use super::permissions::check_user_can_update_harvest;
+use super::permissions::{check_user_can_update_harvest, check_user_owns_harvest};
 
 /// Harvest create form
 #[derive(Debug, Clone, Deserialize)]
@@ -32,6 +36,11 @@ pub struct HarvestCreateForm {
     pub r#type: Option<String>,
     pub description: Option<String>,
     pub harvest_date: Option<Date>,
+    /// Initial stock, the stock is not tracked if not provided
+    pub available_quantity: Option<Decimal>,
+    /// Defaults to the price unit
+    pub quantity_unit: Option<QuantityUnit>,
+    pub low_stock_threshold: Option<Decimal>,
 }
 
 /// Harvest create form cleaned data
@@ -44,6 +53,9 @@ pub struct HarvestInsertData {
     pub r#type: Option<String>,
     pub description: Option<String>,
     pub harvest_date: Date,
+    pub available_quantity: Option<Decimal>,
+    pub quantity_unit: String,
+    pub low_stock_threshold: Option<Decimal>,
     pub created_at: OffsetDateTime,
 }
 
@@ -52,6 +64,9 @@ impl From<HarvestCreateForm> for HarvestInsertData {
     fn from(form: HarvestCreateForm) -> Self {
         let created_at = OffsetDateTime::now_utc();
         let harvest_date = form.harvest_date.unwrap_or_else(|| created_at.date());
+        let quantity_unit = form
+            .quantity_unit
+            .unwrap_or_else(|| QuantityUnit::from(&form.price.unit));
         Self {
             id: ModelID::new(),
             location_id: ModelID::from_str_unchecked(&form.location_id),
@@ -60,6 +75,9 @@ impl From<HarvestCreateForm> for HarvestInsertData {
             r#type: form.r#type,
             description: form.description,
             harvest_date,
+            available_quantity: form.available_quantity,
+            quantity_unit: quantity_unit.as_str().to_owned(),
+            low_stock_threshold: form.low_stock_threshold,
             created_at,
         }
     }
@@ -87,6 +105,14 @@ impl HarvestCreateForm {
             validate_harvest_date(harvest_date)?;
         }
 
+        if let Some(quantity) = self.available_quantity {
+            validate_quantity(quantity, "Harvest available quantity cannot be negative.")?;
+        }
+
+        if let Some(threshold) = self.low_stock_threshold {
+            validate_quantity(threshold, "Harvest low stock threshold cannot be negative.")?;
+        }
+
         Ok(())
     }
 
@@ -128,6 +154,9 @@ where
         let location_id = ModelID::from_str_unchecked(harvest.location_id.as_str());
         Self::authorize_request(state, user, location_id).await?;
 
+        // The price currency is the location country's currency
+        fill_price_currency(&mut harvest.price, location_id, state.database()).await?;
+
         Ok(harvest)
     }
 }
@@ -144,6 +173,13 @@ pub struct HarvestUpdateForm {
     pub r#type: Option<String>,
     pub description: Option<String>,
     pub harvest_date: Option<Date>,
+    /// Harvest stock quantities are adjusted with `HarvestStockForm`
+    pub quantity_unit: Option<QuantityUnit>,
+    /// The low stock threshold is left unchanged if not set
+    pub low_stock_threshold: Option<Decimal>,
+    /// Removes the low stock threshold, the harvest is no longer marked low stock
+    #[serde(default)]
+    pub clear_low_stock_threshold: bool,
 }
 
 /// Harvest update form cleaned data
@@ -155,6 +191,9 @@ pub struct HarvestUpdateData {
     pub r#type: Option<String>,
     pub description: Option<String>,
     pub harvest_date: Option<Date>,
+    pub quantity_unit: Option<String>,
+    pub low_stock_threshold: Option<Decimal>,
+    pub clear_low_stock_threshold: bool,
     pub updated_at: OffsetDateTime,
 }
 
@@ -168,6 +207,9 @@ impl From<HarvestUpdateForm> for HarvestUpdateData {
             r#type: form.r#type,
             description: form.description,
             harvest_date: form.harvest_date,
+            quantity_unit: form.quantity_unit.map(|unit| unit.as_str().to_owned()),
+            low_stock_threshold: form.low_stock_threshold,
+            clear_low_stock_threshold: form.clear_low_stock_threshold,
             updated_at: OffsetDateTime::now_utc(),
         }
     }
@@ -195,6 +237,15 @@ impl HarvestUpdateForm {
             validate_harvest_date(harvest_date)?;
         }
 
+        if let Some(threshold) = self.low_stock_threshold {
+            if self.clear_low_stock_threshold {
+                return Err(EndpointRejection::BadRequest(
+                    "Harvest low stock threshold cannot be set and cleared at once.".into(),
+                ));
+            }
+            validate_quantity(threshold, "Harvest low stock threshold cannot be negative.")?;
+        }
+
         Ok(())
     }
 
@@ -238,18 +289,106 @@ where
         let location_id = ModelID::from_str_unchecked(harvest.location_id.as_str());
         Self::authorize_request(state, user, harvest_id, location_id).await?;
 
+        // The price currency is the location country's currency
+        fill_price_currency(&mut harvest.price, location_id, state.database()).await?;
+
         Ok(harvest)
     }
 }
 
+// ===== Stock form impls =====
+
+/// Harvest stock adjustment form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarvestStockForm {
+    /// Quantity added to the available quantity, negative to remove stock
+    pub quantity_change: Decimal,
+    pub reason: Option<String>,
+}
+
+/// Harvest stock adjustment form cleaned data
+#[derive(Debug, Clone)]
+pub struct HarvestStockChangeData {
+    pub id: ModelID,
+    pub author_id: Option<ModelID>,
+    pub quantity_change: Decimal,
+    pub reason: Option<String>,
+    pub created_at: OffsetDateTime,
+}
+
+impl HarvestStockForm {
+    /// Converts `Self` into `HarvestStockChangeData`
+    #[must_use]
+    pub fn data(self, author_id: ModelID) -> HarvestStockChangeData {
+        HarvestStockChangeData {
+            id: ModelID::new(),
+            author_id: Some(author_id),
+            quantity_change: self.quantity_change,
+            reason: self.reason,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Validates harvest stock form inputs
+    fn validate(&mut self) -> EndpointResult<()> {
+        // Clean the data
+        self.clean_data();
+
+        if self.quantity_change.is_zero() {
+            return Err(EndpointRejection::BadRequest(
+                "Harvest stock quantity change cannot be zero.".into(),
+            ));
+        }
+
+        if let Some(ref reason) = self.reason {
+            reason.validate_len(0, 128, "Stock change reason must be at most 128 characters")?;
+        }
+
+        Ok(())
+    }
+
+    /// Clean form data
+    fn clean_data(&mut self) {
+        self.reason = self.reason.as_ref().map(|reason| reason.clean());
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for HarvestStockForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let user = { FarmerUser::from_parts(&mut parts, state).await? };
+        let harvest_id = { ModelID::from_request_parts(&mut parts, state).await? };
+        let Json(mut stock) =
+            Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        // Validate form fields
+        stock.validate()?;
+
+        // Authorize request
+        check_user_owns_harvest(user.id(), harvest_id, state.database()).await?;
+
+        Ok(stock)
+    }
+}
+
 // ===== Helpers =====
 
 mod helpers {
     use crate::{
-        core::types::price::Price,
+        core::types::price::{Currency, Price, Unit},
         endpoint::{EndpointRejection, EndpointResult},
-        // types::ModelID,
+        server::state::DatabaseConnection,
+        types::ModelID,
     };
+    use rust_decimal::Decimal;
     use time::{Date, OffsetDateTime};
 
     /// Validate harvest `harvest_date` date is not a past date
@@ -269,9 +408,91 @@ mod helpers {
                 "Harvest price cannot be zero.".into(),
             ));
         }
+
+        // Unit quantity must be positive
+        if let Unit::Kg(n) | Unit::Tonne(n) | Unit::Litre(n) = price.unit {
+            if n <= Decimal::ZERO {
+                return Err(EndpointRejection::BadRequest(
+                    "Harvest price unit quantity must be greater than zero.".into(),
+                ));
+            }
+        }
+
+        // Only containers have a declared weight
+        if let Some(weight) = price.weight_kg {
+            if !price.unit.is_container() {
+                return Err(EndpointRejection::BadRequest(
+                    "Only crate, bundle, sack, tray and punnet prices can declare a weight.".into(),
+                ));
+            }
+            if weight <= Decimal::ZERO {
+                return Err(EndpointRejection::BadRequest(
+                    "Harvest price declared weight must be greater than zero.".into(),
+                ));
+            }
+        }
         Ok(())
     }
 
+    /// Validate a stock quantity is not negative
+    pub fn validate_quantity(quantity: Decimal, err_msg: &'static str) -> EndpointResult<()> {
+        if quantity.is_sign_negative() && !quantity.is_zero() {
+            return Err(EndpointRejection::BadRequest(err_msg.into()));
+        }
+        Ok(())
+    }
+
+    /// Fills the harvest price currency with the currency of the location's country,
+    /// validates a currency sent by the user is the location's country currency.
+    pub async fn fill_price_currency(
+        price: &mut Price,
+        location_id: ModelID,
+        db: DatabaseConnection,
+    ) -> EndpointResult<()> {
+        match sqlx::query!(
+            r#"
+                SELECT country.currency
+                FROM services.locations location_
+                LEFT JOIN services.countries country
+                    ON location_.country_id = country.id
+                WHERE location_.id = $1
+            "#,
+            location_id.0
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(rec) => {
+                let currency = Currency::from_row(&rec.currency);
+                match price.currency {
+                    None => {
+                        price.currency = Some(currency);
+                        Ok(())
+                    }
+                    Some(price_currency) if price_currency == currency => Ok(()),
+                    Some(price_currency) => {
+                        tracing::error!(
+                            "Validation error: price currency {} does not match location currency {}",
+                            price_currency,
+                            currency
+                        );
+                        Err(EndpointRejection::BadRequest(
+                            format!("Harvest price currency must be {currency}.").into(),
+                        ))
+                    }
+                }
+            }
+            Err(err) => {
+                if matches!(err, sqlx::Error::RowNotFound) {
+                    Err(EndpointRejection::BadRequest("Location not found".into()))
+                } else {
+                    tracing::error!("Database error: {}", err);
+                    Err(EndpointRejection::internal_server_error())
+                }
+            }
+        }
+    }
+
     // /// Validates harvest `id` exists
     // #[allow(dead_code)]
     // pub async fn validate_harvest_id(id: ModelID, db: DatabaseConnection) -> EndpointResult<()> {
@@ -6,42 +6,49 @@ use axum::{
 };
 
 use crate::{
-    auth::FarmerUser,
+    auth::{CurrentUser, FarmerUser},
     endpoint::{EndpointRejection, EndpointResult},
+    features::harvest_wishlist::{mark_saved, mark_saved_harvest},
     files,
     server::state::DatabaseConnection,
     settings::HARVEST_UPLOAD_DIR,
-    types::{ModelID, Pagination},
+    types::{geo::GeoQuery, ModelID, Pagination},
 };
 
 use super::{
-    forms::{HarvestCreateForm, HarvestUpdateForm},
-    models::{Harvest, HarvestList},
+    forms::{HarvestCreateForm, HarvestStockForm, HarvestUpdateForm},
+    models::{Harvest, HarvestList, HarvestStock},
     permissions::HarvestOwnershipPermission,
     utils::delete_harvest_photos,
 };
 
 /// Handles the `GET /harvests` route.
-#[tracing::instrument(skip(db))]
+#[tracing::instrument(skip(db, user))]
 pub async fn harvest_list(
+    user: Option<CurrentUser>,
     pg: Option<Query<Pagination>>,
+    Query(geo): Query<GeoQuery>,
     State(db): State<DatabaseConnection>,
 ) -> EndpointResult<Json<HarvestList>> {
     let pagination = pg.unwrap_or_default().0;
-    let harvests = Harvest::records(pagination, db).await?;
+    let geo = geo.filter()?;
+    let mut harvests = Harvest::records(pagination, &geo, db.clone()).await?;
+    mark_saved(user.as_ref(), &mut harvests, db).await?;
     Ok(Json(harvests))
 }
 
 /// Handles the `GET /harvests/:harvest_id` route.
-#[tracing::instrument(skip(db))]
+#[tracing::instrument(skip(db, user))]
 pub async fn harvest_detail(
+    user: Option<CurrentUser>,
     harvest_id: ModelID,
     State(db): State<DatabaseConnection>,
 ) -> EndpointResult<Json<Harvest>> {
-    Harvest::find(harvest_id, db).await?.map_or_else(
-        || Err(EndpointRejection::NotFound("Harvest not found.".into())),
-        |harvest| Ok(Json(harvest)),
-    )
+    let Some(mut harvest) = Harvest::find(harvest_id, db.clone()).await? else {
+        return Err(EndpointRejection::NotFound("Harvest not found.".into()));
+    };
+    mark_saved_harvest(user.as_ref(), &mut harvest, db).await?;
+    Ok(Json(harvest))
 }
 
 /// Handles the `POST /harvests` route.
@@ -96,7 +103,7 @@ pub async fn harvest_image_uploads(
     while let Some(file) = uploads.files().await {
         // Save an image to the file system
         paths.push(format!("{}.jpg", file.id));
-        file.save_image(HARVEST_UPLOAD_DIR).await?; // spawn ??
+        file.queue_image(HARVEST_UPLOAD_DIR, db.clone()).await?;
     }
 
     // Save image path to the database
@@ -120,3 +127,38 @@ pub async fn harvest_image_delete(
     Harvest::delete_photos(harvest_id, db).await?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Handles the `GET /harvests/:harvest_id/stock` route.
+#[tracing::instrument(skip(db))]
+pub async fn harvest_stock(
+    _: HarvestOwnershipPermission,
+    harvest_id: ModelID,
+    pg: Option<Query<Pagination>>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<HarvestStock>> {
+    let pagination = pg.map(|pg| pg.0);
+    HarvestStock::find(harvest_id, pagination, db)
+        .await?
+        .map_or_else(
+            || Err(EndpointRejection::NotFound("Harvest not found.".into())),
+            |stock| Ok(Json(stock)),
+        )
+}
+
+/// Handles the `POST /harvests/:harvest_id/stock` route.
+///
+/// Adjusts the harvest available quantity, the harvest is
+/// marked finished when there is no more stock available.
+#[tracing::instrument(skip(db, form))]
+pub async fn harvest_stock_adjust(
+    user: FarmerUser,
+    harvest_id: ModelID,
+    State(db): State<DatabaseConnection>,
+    form: HarvestStockForm,
+) -> EndpointResult<Json<HarvestStock>> {
+    HarvestStock::adjust(harvest_id, form.data(user.id()), db.clone()).await?;
+    HarvestStock::find(harvest_id, None, db).await?.map_or_else(
+        || Err(EndpointRejection::NotFound("Harvest not found.".into())),
+        |stock| Ok(Json(stock)),
+    )
+}
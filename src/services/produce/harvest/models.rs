@@ -2,11 +2,14 @@
 #![allow(clippy::missing_const_for_fn)]
 
 use geo::Point;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use time::{Date, OffsetDateTime};
 
 use crate::{
-    core::types::{price::Price, ModelID},
+    core::types::{
+        price::{Price, QuantityUnit},
+        ModelID,
+    },
     core::{accounts::user::models::UserIndex, types::ModelIdentifier},
     services::farmers::location,
 };
@@ -34,6 +37,12 @@ pub struct Harvest {
     pub harvest_date: Date,
     pub created_at: Date,
     pub location: HarvestLocation,
+    /// `None` if the farmer does not track the harvest stock
+    pub stock: Option<HarvestStockLevel>,
+    /// True if the harvest is on the current user's wishlist,
+    /// not set for anonymous users
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved: Option<bool>,
 }
 
 impl Harvest {
@@ -93,12 +102,14 @@ impl Harvest {
             harvest_date,
             created_at: created_at.date(),
             location: HarvestLocation::from_row(location_id, place_name, region, country, coords),
+            stock: None,
+            saved: None,
         }
     }
 }
 
 /// A type returned by `harvest_list` handler.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HarvestIndex {
     pub id: ModelID,
@@ -107,6 +118,12 @@ pub struct HarvestIndex {
     pub farm_name: String,
     pub farm_logo: Option<String>,
     pub price: Price,
+    /// Price normalised per kg, if the price unit weight is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_per_kg: Option<rust_decimal::Decimal>,
+    /// Price normalised per litre, if the price unit is a volume
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_per_litre: Option<rust_decimal::Decimal>,
     pub images: Option<Vec<String>>,
     pub cultivar_image: Option<String>,
     pub harvest_date: Date,
@@ -114,10 +131,19 @@ pub struct HarvestIndex {
     pub region: Option<String>,
     pub country: String,
     pub coords: Option<Point>,
+    /// True if the harvest available quantity is running out
+    pub low_stock: bool,
+    /// Distance in kilometres from the searched `near` point
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_km: Option<f64>,
+    /// True if the harvest is on the current user's wishlist,
+    /// not set for anonymous users
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved: Option<bool>,
 
     // This field is for internal use only; it is not sent to the users.
     // it is used for ordering
-    #[serde(skip_serializing)]
+    #[serde(skip)]
     pub boost_amount: rust_decimal::Decimal,
 }
 
@@ -139,15 +165,19 @@ impl HarvestIndex {
         coords: Option<serde_json::Value>,
         farm_name: String,
         farm_logo: Option<String>,
+        low_stock: bool,
         boost_amount: rust_decimal::Decimal,
     ) -> Self {
+        let price = Price::from_row(price);
         Self {
             id,
             category: cultivar_category,
             name: cultivar_name,
             farm_name,
             farm_logo,
-            price: Price::from_row(price),
+            price_per_kg: price.price_per_kg(),
+            price_per_litre: price.price_per_litre(),
+            price,
             images,
             cultivar_image,
             harvest_date,
@@ -155,6 +185,9 @@ impl HarvestIndex {
             region,
             place_name,
             coords: location::try_into_point(coords),
+            low_stock,
+            distance_km: None,
+            saved: None,
             boost_amount,
         }
     }
@@ -221,3 +254,82 @@ impl HarvestFarm {
         }
     }
 }
+
+/// Harvest stock level
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarvestStockLevel {
+    pub available_quantity: rust_decimal::Decimal,
+    /// Part of the available quantity held for buyers
+    pub reserved_quantity: rust_decimal::Decimal,
+    pub quantity_unit: Option<QuantityUnit>,
+    pub low_stock: bool,
+}
+
+impl HarvestStockLevel {
+    /// Creates a new `HarvestStockLevel` from the database row
+    #[must_use]
+    pub fn from_row(
+        available_quantity: rust_decimal::Decimal,
+        reserved_quantity: rust_decimal::Decimal,
+        quantity_unit: Option<&str>,
+        low_stock: bool,
+    ) -> Self {
+        Self {
+            available_quantity,
+            reserved_quantity,
+            quantity_unit: quantity_unit.and_then(QuantityUnit::from_row),
+            low_stock,
+        }
+    }
+}
+
+/// A type returned by `harvest_stock` handler.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarvestStock {
+    pub harvest_id: ModelID,
+    /// `None` if the farmer does not track the harvest stock
+    pub stock: Option<HarvestStockLevel>,
+    pub low_stock_threshold: Option<rust_decimal::Decimal>,
+    pub changes: Vec<HarvestStockChange>,
+}
+
+/// A harvest stock adjustment
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarvestStockChange {
+    pub id: ModelID,
+    pub quantity_change: rust_decimal::Decimal,
+    /// Available quantity after the change
+    pub available_quantity: rust_decimal::Decimal,
+    /// Reserved quantity after the change
+    pub reserved_quantity: rust_decimal::Decimal,
+    pub reason: Option<String>,
+    pub author_id: Option<ModelID>,
+    pub created_at: OffsetDateTime,
+}
+
+impl HarvestStockChange {
+    /// Creates a new `HarvestStockChange` from the database row
+    #[must_use]
+    pub fn from_row(
+        id: ModelID,
+        quantity_change: rust_decimal::Decimal,
+        available_quantity: rust_decimal::Decimal,
+        reserved_quantity: rust_decimal::Decimal,
+        reason: Option<String>,
+        author_id: Option<ModelID>,
+        created_at: OffsetDateTime,
+    ) -> Self {
+        Self {
+            id,
+            quantity_change,
+            available_quantity,
+            reserved_quantity,
+            reason,
+            author_id,
+            created_at,
+        }
+    }
+}
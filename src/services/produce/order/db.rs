@@ -0,0 +1,740 @@
+//! Order database impl
+
+use rust_decimal::Decimal;
+
+use crate::{
+    core::accounts::user::models::UserIndex,
+    endpoint::EndpointRejection,
+    error::{ServerError, ServerResult},
+    server::state::DatabaseConnection,
+    services::produce::harvest::models::HarvestStockLevel,
+    types::{ModelID, Pagination},
+};
+
+use super::{
+    forms::{OrderCounterData, OrderFilter, OrderInsertData, OrderRole, OrderStatusUpdateData},
+    models::{Order, OrderCounter, OrderHarvest, OrderList, OrderStatus},
+    utils::{update_harvest_stock, HarvestStockUpdate},
+};
+
+impl Order {
+    /// Fetches the user's orders, as a buyer and as a farmer, from the database
+    #[tracing::instrument(name = "Fetch OrderList", skip(db))]
+    pub async fn records(
+        user_id: ModelID,
+        filter: OrderFilter,
+        pg: Pagination,
+        db: DatabaseConnection,
+    ) -> ServerResult<OrderList> {
+        let (offset, limit) = pg.offset_limit();
+        match sqlx::query!(
+            r#"
+                SELECT order_.id AS order_id,
+                    order_.quantity AS order_quantity,
+                    order_.quantity_unit AS order_quantity_unit,
+                    order_.pickup_date AS order_pickup_date,
+                    order_.note AS order_note,
+                    order_.status AS order_status,
+                    order_.counter_quantity AS order_counter_quantity,
+                    order_.counter_pickup_date AS order_counter_pickup_date,
+                    order_.counter_note AS order_counter_note,
+                    order_.status_reason AS order_status_reason,
+                    order_.created_at AS order_created_at,
+                    order_.updated_at AS order_updated_at,
+                    harvest.id AS harvest_id,
+                    cultivar.name AS cultivar_name,
+                    farm.id AS farm_id,
+                    farm.name AS farm_name,
+                    farmer.id AS farmer_id,
+                    farmer.first_name AS farmer_first_name,
+                    farmer.last_name AS farmer_last_name,
+                    farmer_profile.photo AS "farmer_photo?",
+                    buyer.id AS buyer_id,
+                    buyer.first_name AS buyer_first_name,
+                    buyer.last_name AS buyer_last_name,
+                    buyer_profile.photo AS "buyer_photo?"
+                FROM services.orders order_
+                INNER JOIN services.harvests harvest
+                    ON order_.harvest_id = harvest.id
+                INNER JOIN services.cultivars cultivar
+                    ON harvest.cultivar_id = cultivar.id
+                INNER JOIN services.locations location_
+                    ON harvest.location_id = location_.id
+                INNER JOIN services.farms farm
+                    ON location_.farm_id = farm.id
+                INNER JOIN accounts.users farmer
+                    ON farm.owner_id = farmer.id
+                LEFT JOIN accounts.user_profiles farmer_profile
+                    ON farmer.id = farmer_profile.user_id
+                INNER JOIN accounts.users buyer
+                    ON order_.buyer_id = buyer.id
+                LEFT JOIN accounts.user_profiles buyer_profile
+                    ON buyer.id = buyer_profile.user_id
+
+                WHERE (
+                    (order_.buyer_id = $1 AND ($2::text IS NULL OR $2 = 'buyer'))
+                    OR (farm.owner_id = $1 AND ($2::text IS NULL OR $2 = 'farmer'))
+                )
+                    AND ($3::text IS NULL OR order_.status = $3)
+                ORDER BY order_.created_at DESC
+                LIMIT $4
+                OFFSET $5;
+            "#,
+            user_id.0,
+            filter.role.map(OrderRole::as_str),
+            filter.status.map(OrderStatus::as_str),
+            limit,
+            offset,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let orders = records
+                    .into_iter()
+                    .map(|rec| {
+                        let counter = rec
+                            .order_counter_quantity
+                            .zip(rec.order_counter_pickup_date)
+                            .map(|(quantity, pickup_date)| {
+                                OrderCounter::from_row(
+                                    quantity,
+                                    pickup_date,
+                                    rec.order_counter_note,
+                                )
+                            });
+                        Order::from_row(
+                            rec.order_id.into(),
+                            rec.order_quantity,
+                            rec.order_quantity_unit.as_deref(),
+                            rec.order_pickup_date,
+                            rec.order_note,
+                            &rec.order_status,
+                            counter,
+                            rec.order_status_reason,
+                            rec.order_created_at,
+                            rec.order_updated_at,
+                            rec.harvest_id.into(),
+                            rec.cultivar_name,
+                            rec.farm_id.into(),
+                            rec.farm_name,
+                            UserIndex::from_row(
+                                rec.farmer_id.into(),
+                                rec.farmer_first_name,
+                                rec.farmer_last_name,
+                                rec.farmer_photo,
+                            ),
+                            UserIndex::from_row(
+                                rec.buyer_id.into(),
+                                rec.buyer_first_name,
+                                rec.buyer_last_name,
+                                rec.buyer_photo,
+                            ),
+                        )
+                    })
+                    .collect();
+                Ok(orders)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch orders: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Fetches order detail from the database
+    #[tracing::instrument(name = "Find Order", skip(db))]
+    pub async fn find(order_id: ModelID, db: DatabaseConnection) -> ServerResult<Option<Self>> {
+        match sqlx::query!(
+            r#"
+                SELECT order_.id AS order_id,
+                    order_.quantity AS order_quantity,
+                    order_.quantity_unit AS order_quantity_unit,
+                    order_.pickup_date AS order_pickup_date,
+                    order_.note AS order_note,
+                    order_.status AS order_status,
+                    order_.counter_quantity AS order_counter_quantity,
+                    order_.counter_pickup_date AS order_counter_pickup_date,
+                    order_.counter_note AS order_counter_note,
+                    order_.status_reason AS order_status_reason,
+                    order_.created_at AS order_created_at,
+                    order_.updated_at AS order_updated_at,
+                    harvest.id AS harvest_id,
+                    cultivar.name AS cultivar_name,
+                    farm.id AS farm_id,
+                    farm.name AS farm_name,
+                    farmer.id AS farmer_id,
+                    farmer.first_name AS farmer_first_name,
+                    farmer.last_name AS farmer_last_name,
+                    farmer_profile.photo AS "farmer_photo?",
+                    buyer.id AS buyer_id,
+                    buyer.first_name AS buyer_first_name,
+                    buyer.last_name AS buyer_last_name,
+                    buyer_profile.photo AS "buyer_photo?"
+                FROM services.orders order_
+                INNER JOIN services.harvests harvest
+                    ON order_.harvest_id = harvest.id
+                INNER JOIN services.cultivars cultivar
+                    ON harvest.cultivar_id = cultivar.id
+                INNER JOIN services.locations location_
+                    ON harvest.location_id = location_.id
+                INNER JOIN services.farms farm
+                    ON location_.farm_id = farm.id
+                INNER JOIN accounts.users farmer
+                    ON farm.owner_id = farmer.id
+                LEFT JOIN accounts.user_profiles farmer_profile
+                    ON farmer.id = farmer_profile.user_id
+                INNER JOIN accounts.users buyer
+                    ON order_.buyer_id = buyer.id
+                LEFT JOIN accounts.user_profiles buyer_profile
+                    ON buyer.id = buyer_profile.user_id
+
+                WHERE order_.id = $1;
+            "#,
+            order_id.0,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(Some(rec)) => {
+                let counter = rec
+                    .order_counter_quantity
+                    .zip(rec.order_counter_pickup_date)
+                    .map(|(quantity, pickup_date)| {
+                        OrderCounter::from_row(quantity, pickup_date, rec.order_counter_note)
+                    });
+                let order = Self::from_row(
+                    rec.order_id.into(),
+                    rec.order_quantity,
+                    rec.order_quantity_unit.as_deref(),
+                    rec.order_pickup_date,
+                    rec.order_note,
+                    &rec.order_status,
+                    counter,
+                    rec.order_status_reason,
+                    rec.order_created_at,
+                    rec.order_updated_at,
+                    rec.harvest_id.into(),
+                    rec.cultivar_name,
+                    rec.farm_id.into(),
+                    rec.farm_name,
+                    UserIndex::from_row(
+                        rec.farmer_id.into(),
+                        rec.farmer_first_name,
+                        rec.farmer_last_name,
+                        rec.farmer_photo,
+                    ),
+                    UserIndex::from_row(
+                        rec.buyer_id.into(),
+                        rec.buyer_first_name,
+                        rec.buyer_last_name,
+                        rec.buyer_photo,
+                    ),
+                );
+                Ok(Some(order))
+            }
+            Ok(None) => Ok(None),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch order: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Inserts order into the database
+    #[tracing::instrument(name = "Insert Order", skip(db, order))]
+    pub async fn insert(order: OrderInsertData, db: DatabaseConnection) -> ServerResult<ModelID> {
+        match sqlx::query!(
+            r#"
+                INSERT INTO services.orders(
+                    id,
+                    harvest_id,
+                    buyer_id,
+                    quantity,
+                    quantity_unit,
+                    pickup_date,
+                    note,
+                    status,
+                    created_at
+                )
+                SELECT $1, harvest.id, $3, $4,
+                    COALESCE(harvest.quantity_unit, services.price_unit(harvest.price)),
+                    $5, $6, 'pending', $7
+                FROM services.active_harvests harvest
+                WHERE harvest.id = $2;
+            "#,
+            order.id.0,
+            order.harvest_id.0,
+            order.buyer_id.0,
+            order.quantity,
+            order.pickup_date,
+            order.note,
+            order.created_at,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(ServerError::rejection(
+                EndpointRejection::BadRequest("The harvest is no longer available.".into()),
+            )),
+            Ok(result) => {
+                tracing::debug!("Order inserted successfully: {:?}", result);
+                Ok(order.id)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to insert order: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Moves the order to a new status in the database
+    ///
+    /// Accepting an order reserves its quantity from the harvest stock,
+    /// collecting it removes the quantity from the stock and cancelling
+    /// an accepted order releases the reserved quantity.
+    #[tracing::instrument(name = "Update Order status", skip(db, status))]
+    pub async fn update_status(
+        order_id: ModelID,
+        status: OrderStatusUpdateData,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        match status.status {
+            OrderStatus::Accepted => Self::accept(order_id, false, status, db).await,
+            OrderStatus::Collected | OrderStatus::Cancelled => {
+                Self::close(order_id, status, db).await
+            }
+            OrderStatus::Pending | OrderStatus::Ready | OrderStatus::Declined => {
+                Self::set_status(order_id, status, db).await
+            }
+        }
+    }
+
+    /// Closes the pending and accepted orders of archived harvests,
+    /// pending orders are declined and accepted orders are cancelled
+    /// releasing their reserved harvest stock.
+    ///
+    /// Return the closed orders with their new status.
+    pub async fn close_for_archived_harvests(
+        harvest_ids: &[ModelID],
+        closed_at: time::OffsetDateTime,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> ServerResult<Vec<(ModelID, OrderStatus)>> {
+        let harvest_ids: Vec<_> = harvest_ids.iter().map(|id| id.0).collect();
+        let orders = match sqlx::query!(
+            r#"
+                UPDATE services.orders order_
+                SET status = CASE WHEN order_.status = 'pending'
+                        THEN 'declined'
+                        ELSE 'cancelled'
+                    END,
+                    status_reason = 'The harvest listing has expired.',
+                    updated_at = $2
+                WHERE order_.harvest_id = ANY($1)
+                    AND order_.status IN ('pending', 'accepted')
+                RETURNING order_.id,
+                    order_.harvest_id,
+                    order_.quantity,
+                    order_.stock_reserved,
+                    order_.status;
+            "#,
+            &harvest_ids[..],
+            closed_at,
+        )
+        .fetch_all(&mut **tx)
+        .await
+        {
+            Ok(orders) => orders,
+            Err(err) => {
+                tracing::error!(
+                    "Database error, failed to close archived harvests orders: {}",
+                    err
+                );
+                return Err(err.into());
+            }
+        };
+
+        let mut closed = Vec::with_capacity(orders.len());
+        for order in orders {
+            if order.stock_reserved {
+                let update = HarvestStockUpdate::new(
+                    Decimal::ZERO,
+                    -order.quantity,
+                    None,
+                    "Order cancelled",
+                    closed_at,
+                );
+                update_harvest_stock(order.harvest_id.into(), update, tx).await?;
+            }
+            closed.push((order.id.into(), OrderStatus::from_row(&order.status)));
+        }
+        Ok(closed)
+    }
+
+    /// Accepts the farmer's counter offer in the database
+    #[tracing::instrument(name = "Accept Order counter", skip(db))]
+    pub async fn accept_counter(
+        order_id: ModelID,
+        buyer_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        let status = OrderStatusUpdateData {
+            status: OrderStatus::Accepted,
+            reason: None,
+            author_id: buyer_id,
+            updated_at: time::OffsetDateTime::now_utc(),
+        };
+        Self::accept(order_id, true, status, db).await
+    }
+
+    /// Updates order with the farmer's counter offer in the database
+    #[tracing::instrument(name = "Counter Order", skip(db, counter))]
+    pub async fn counter(
+        order_id: ModelID,
+        counter: OrderCounterData,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        match sqlx::query!(
+            r#"
+                UPDATE services.orders order_
+                SET counter_quantity = $2,
+                    counter_pickup_date = $3,
+                    counter_note = $4,
+                    updated_at = $5
+                WHERE order_.id = $1
+                    AND order_.status = 'pending';
+            "#,
+            order_id.0,
+            counter.quantity,
+            counter.pickup_date,
+            counter.note,
+            counter.updated_at,
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(status_changed_error()),
+            Ok(result) => {
+                tracing::debug!("Order countered successfully: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to counter order: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Returns true if the two users are the buyer and the farmer of the order
+    #[tracing::instrument(name = "Order is between users", skip(db))]
+    pub async fn is_between(
+        order_id: ModelID,
+        user_id: ModelID,
+        other_id: ModelID,
+        db: DatabaseConnection,
+    ) -> ServerResult<bool> {
+        match sqlx::query!(
+            r#"
+                SELECT EXISTS(
+                    SELECT 1
+                    FROM services.orders order_
+                    INNER JOIN services.harvests harvest
+                        ON order_.harvest_id = harvest.id
+                    INNER JOIN services.locations location_
+                        ON harvest.location_id = location_.id
+                    INNER JOIN services.farms farm
+                        ON location_.farm_id = farm.id
+                    WHERE order_.id = $1
+                        AND (
+                            (order_.buyer_id = $2 AND farm.owner_id = $3)
+                            OR (order_.buyer_id = $3 AND farm.owner_id = $2)
+                        )
+                ) AS "exists!";
+            "#,
+            order_id.0,
+            user_id.0,
+            other_id.0,
+        )
+        .fetch_one(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.exists),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch order parties: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Moves a pending order to `accepted`, reserving the order quantity
+    /// if the farmer tracks the harvest stock.
+    async fn accept(
+        order_id: ModelID,
+        use_counter: bool,
+        status: OrderStatusUpdateData,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        let mut tx = db.pool.begin().await?; // init transaction
+
+        let order = match sqlx::query!(
+            r#"
+                UPDATE services.orders order_
+                SET status = 'accepted',
+                    quantity = CASE WHEN $2 THEN order_.counter_quantity ELSE order_.quantity END,
+                    pickup_date = CASE WHEN $2
+                        THEN order_.counter_pickup_date
+                        ELSE order_.pickup_date
+                    END,
+                    updated_at = $3
+                WHERE order_.id = $1
+                    AND order_.status = 'pending'
+                    AND (NOT $2 OR order_.counter_quantity IS NOT NULL)
+                RETURNING order_.harvest_id, order_.quantity AS "quantity!";
+            "#,
+            order_id.0,
+            use_counter,
+            status.updated_at,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        {
+            Ok(Some(order)) => order,
+            Ok(None) => return Err(status_changed_error()),
+            Err(err) => {
+                tracing::error!("Database error, failed to accept order: {}", err);
+                return Err(err.into());
+            }
+        };
+
+        // Lock the harvest stock until the transaction is committed
+        let harvest = match sqlx::query!(
+            r#"
+                SELECT harvest.finished,
+                    harvest.available_quantity,
+                    harvest.reserved_quantity
+                FROM services.harvests harvest
+                WHERE harvest.id = $1
+                FOR UPDATE;
+            "#,
+            order.harvest_id,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        {
+            Ok(harvest) => harvest,
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch order harvest: {}", err);
+                return Err(err.into());
+            }
+        };
+
+        if harvest.finished {
+            return Err(ServerError::rejection(EndpointRejection::BadRequest(
+                "The harvest is no longer available.".into(),
+            )));
+        }
+
+        if let Some(available_quantity) = harvest.available_quantity {
+            if available_quantity - harvest.reserved_quantity < order.quantity {
+                return Err(ServerError::rejection(EndpointRejection::BadRequest(
+                    "There is not enough harvest stock to accept this order.".into(),
+                )));
+            }
+
+            let update = HarvestStockUpdate::new(
+                Decimal::ZERO,
+                order.quantity,
+                Some(status.author_id),
+                "Order accepted",
+                status.updated_at,
+            );
+            update_harvest_stock(order.harvest_id.into(), update, &mut tx).await?;
+
+            if let Err(err) = sqlx::query!(
+                r#"
+                    UPDATE services.orders order_
+                    SET stock_reserved = true
+                    WHERE order_.id = $1;
+                "#,
+                order_id.0,
+            )
+            .execute(&mut *tx)
+            .await
+            {
+                tracing::error!("Database error, failed to reserve order stock: {}", err);
+                return Err(err.into());
+            }
+        }
+
+        tx.commit().await?; // Commit transaction
+        tracing::debug!("Order accepted successfully.");
+        Ok(())
+    }
+
+    /// Moves the order to `collected` or `cancelled`,
+    /// updating the reserved harvest stock.
+    async fn close(
+        order_id: ModelID,
+        status: OrderStatusUpdateData,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        let previous: Vec<_> = status
+            .status
+            .previous()
+            .iter()
+            .map(|status| status.as_str().to_owned())
+            .collect();
+
+        let mut tx = db.pool.begin().await?; // init transaction
+
+        let order = match sqlx::query!(
+            r#"
+                UPDATE services.orders order_
+                SET status = $2,
+                    status_reason = COALESCE($3, order_.status_reason),
+                    updated_at = $4
+                WHERE order_.id = $1
+                    AND order_.status = ANY($5)
+                RETURNING order_.harvest_id, order_.quantity, order_.stock_reserved;
+            "#,
+            order_id.0,
+            status.status.as_str(),
+            status.reason,
+            status.updated_at,
+            &previous[..],
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        {
+            Ok(Some(order)) => order,
+            Ok(None) => return Err(status_changed_error()),
+            Err(err) => {
+                tracing::error!("Database error, failed to update order status: {}", err);
+                return Err(err.into());
+            }
+        };
+
+        if order.stock_reserved {
+            // A collected order leaves the stock, a cancelled one is no longer held
+            let update = if status.status == OrderStatus::Collected {
+                HarvestStockUpdate::new(
+                    -order.quantity,
+                    -order.quantity,
+                    Some(status.author_id),
+                    "Order collected",
+                    status.updated_at,
+                )
+            } else {
+                HarvestStockUpdate::new(
+                    Decimal::ZERO,
+                    -order.quantity,
+                    Some(status.author_id),
+                    "Order cancelled",
+                    status.updated_at,
+                )
+            };
+            update_harvest_stock(order.harvest_id.into(), update, &mut tx).await?;
+        }
+
+        tx.commit().await?; // Commit transaction
+        tracing::debug!("Order status updated successfully.");
+        Ok(())
+    }
+
+    /// Moves the order to a status that does not affect the harvest stock
+    async fn set_status(
+        order_id: ModelID,
+        status: OrderStatusUpdateData,
+        db: DatabaseConnection,
+    ) -> ServerResult<()> {
+        let previous: Vec<_> = status
+            .status
+            .previous()
+            .iter()
+            .map(|status| status.as_str().to_owned())
+            .collect();
+
+        match sqlx::query!(
+            r#"
+                UPDATE services.orders order_
+                SET status = $2,
+                    status_reason = COALESCE($3, order_.status_reason),
+                    updated_at = $4
+                WHERE order_.id = $1
+                    AND order_.status = ANY($5);
+            "#,
+            order_id.0,
+            status.status.as_str(),
+            status.reason,
+            status.updated_at,
+            &previous[..],
+        )
+        .execute(&db.pool)
+        .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(status_changed_error()),
+            Ok(result) => {
+                tracing::debug!("Order status updated successfully: {:?}", result);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to update order status: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+impl OrderHarvest {
+    /// Fetches the active harvest an order is placed for from the database
+    #[tracing::instrument(name = "Find OrderHarvest", skip(db))]
+    pub async fn find(harvest_id: ModelID, db: DatabaseConnection) -> ServerResult<Option<Self>> {
+        match sqlx::query!(
+            r#"
+                SELECT farm.owner_id AS "farmer_id!",
+                    harvest.available_quantity,
+                    harvest.reserved_quantity AS "reserved_quantity!",
+                    harvest.quantity_unit,
+                    services.harvest_low_stock(
+                        harvest.available_quantity,
+                        harvest.reserved_quantity,
+                        harvest.low_stock_threshold
+                    ) AS "low_stock!"
+                FROM services.active_harvests harvest
+                INNER JOIN services.active_locations location_
+                    ON harvest.location_id = location_.id
+                INNER JOIN services.active_farms farm
+                    ON location_.farm_id = farm.id
+
+                WHERE harvest.id = $1;
+            "#,
+            harvest_id.0,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.map(|rec| Self {
+                farmer_id: rec.farmer_id.into(),
+                stock: rec.available_quantity.map(|available_quantity| {
+                    HarvestStockLevel::from_row(
+                        available_quantity,
+                        rec.reserved_quantity,
+                        rec.quantity_unit.as_deref(),
+                        rec.low_stock,
+                    )
+                }),
+            })),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch order harvest: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Returned when the order was updated by the other party in the meantime
+fn status_changed_error() -> ServerError {
+    ServerError::rejection(EndpointRejection::BadRequest(
+        "The order status has changed, please reload the order.".into(),
+    ))
+}
@@ -0,0 +1,376 @@
+//! Order forms impls
+
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Json, Request},
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use time::{Date, OffsetDateTime};
+
+use crate::{
+    auth::{CurrentUser, FarmerUser},
+    endpoint::{
+        validators::{TransformString, ValidateString},
+        EndpointRejection, EndpointResult,
+    },
+    server::state::ServerState,
+    types::ModelID,
+};
+
+use super::{
+    models::{OrderHarvest, OrderParties, OrderStatus},
+    permissions::find_order_parties,
+};
+
+use helpers::{validate_note, validate_pickup_date, validate_quantity};
+
+/// Order create form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderCreateForm {
+    pub harvest_id: String,
+    pub quantity: Decimal,
+    pub pickup_date: Date,
+    pub note: Option<String>,
+}
+
+/// Order create form cleaned data
+#[derive(Debug, Clone)]
+pub struct OrderInsertData {
+    pub id: ModelID,
+    pub harvest_id: ModelID,
+    pub buyer_id: ModelID,
+    pub quantity: Decimal,
+    pub pickup_date: Date,
+    pub note: Option<String>,
+    pub created_at: OffsetDateTime,
+}
+
+impl OrderCreateForm {
+    /// Converts `Self` into `OrderInsertData`
+    #[must_use]
+    pub fn data(self, buyer_id: ModelID) -> OrderInsertData {
+        OrderInsertData {
+            id: ModelID::new(),
+            harvest_id: ModelID::from_str_unchecked(self.harvest_id),
+            buyer_id,
+            quantity: self.quantity,
+            pickup_date: self.pickup_date,
+            note: self.note,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Validates order form inputs
+    fn validate(&mut self) -> EndpointResult<()> {
+        // Clean the data
+        self.clean_data();
+
+        self.harvest_id.validate_id("Invalid harvest id")?;
+        validate_quantity(self.quantity)?;
+        validate_pickup_date(self.pickup_date)?;
+        validate_note(self.note.as_deref())?;
+
+        Ok(())
+    }
+
+    /// Clean form data
+    fn clean_data(&mut self) {
+        self.note = self.note.as_ref().map(|note| note.clean());
+    }
+
+    /// Authorize the user can order the harvest
+    fn authorize(&self, user_id: ModelID, harvest: &OrderHarvest) -> EndpointResult<()> {
+        if harvest.farmer_id == user_id {
+            return Err(EndpointRejection::BadRequest(
+                "You cannot order your own harvest.".into(),
+            ));
+        }
+        if let Some(free_quantity) = harvest.free_quantity() {
+            if self.quantity > free_quantity {
+                return Err(EndpointRejection::BadRequest(
+                    format!(
+                        "Only {} of this harvest is available.",
+                        free_quantity.normalize()
+                    )
+                    .into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for OrderCreateForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let user = { CurrentUser::from_parts(&mut parts, state).await? };
+        let Json(mut order) =
+            Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        // Validate form fields
+        order.validate()?;
+
+        // Authorize request
+        let harvest_id = ModelID::from_str_unchecked(&order.harvest_id);
+        let Some(harvest) = OrderHarvest::find(harvest_id, state.database()).await? else {
+            return Err(EndpointRejection::NotFound("Harvest not found.".into()));
+        };
+        order.authorize(user.id, &harvest)?;
+
+        Ok(order)
+    }
+}
+
+// ===== Status form impls =====
+
+/// Order status update form
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderStatusForm {
+    pub status: OrderStatus,
+    /// The decline or cancel reason
+    pub reason: Option<String>,
+}
+
+/// Order status update form cleaned data
+#[derive(Debug, Clone)]
+pub struct OrderStatusUpdateData {
+    pub status: OrderStatus,
+    pub reason: Option<String>,
+    pub author_id: ModelID,
+    pub updated_at: OffsetDateTime,
+}
+
+impl OrderStatusForm {
+    /// Converts `Self` into `OrderStatusUpdateData`
+    #[must_use]
+    pub fn data(self, author_id: ModelID) -> OrderStatusUpdateData {
+        OrderStatusUpdateData {
+            status: self.status,
+            reason: self.reason,
+            author_id,
+            updated_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Validates order status form inputs
+    fn validate(&mut self) -> EndpointResult<()> {
+        // Clean the data
+        self.reason = self.reason.as_ref().map(|reason| reason.clean());
+
+        if self.status == OrderStatus::Pending {
+            return Err(EndpointRejection::BadRequest(
+                "An order cannot be moved back to pending.".into(),
+            ));
+        }
+        if let Some(ref reason) = self.reason {
+            reason.validate_len(0, 512, "Reason must be at most 512 characters")?;
+        }
+        Ok(())
+    }
+
+    /// Authorize the user can move the order to the new status
+    fn authorize(&self, user_id: ModelID, parties: &OrderParties) -> EndpointResult<()> {
+        if !parties.is_party(user_id) {
+            return Err(EndpointRejection::forbidden());
+        }
+        if self.status.is_farmer_only() && parties.farmer_id != user_id {
+            return Err(EndpointRejection::forbidden());
+        }
+        if !self.status.can_move_from(parties.status) {
+            return Err(EndpointRejection::BadRequest(
+                format!(
+                    "A {} order cannot be marked as {}.",
+                    parties.status, self.status
+                )
+                .into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for OrderStatusForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let user = { CurrentUser::from_parts(&mut parts, state).await? };
+        let order_id = { ModelID::from_request_parts(&mut parts, state).await? };
+        let Json(mut status) =
+            Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        // Validate form fields
+        status.validate()?;
+
+        // Authorize request
+        let parties = find_order_parties(order_id, state.database()).await?;
+        status.authorize(user.id, &parties)?;
+
+        Ok(status)
+    }
+}
+
+// ===== Counter form impls =====
+
+/// Order counter offer form, sent by the farmer
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderCounterForm {
+    pub quantity: Decimal,
+    pub pickup_date: Date,
+    pub note: Option<String>,
+}
+
+/// Order counter offer form cleaned data
+#[derive(Debug, Clone)]
+pub struct OrderCounterData {
+    pub quantity: Decimal,
+    pub pickup_date: Date,
+    pub note: Option<String>,
+    pub updated_at: OffsetDateTime,
+}
+
+impl From<OrderCounterForm> for OrderCounterData {
+    fn from(form: OrderCounterForm) -> Self {
+        Self {
+            quantity: form.quantity,
+            pickup_date: form.pickup_date,
+            note: form.note,
+            updated_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+impl OrderCounterForm {
+    /// Validates order counter form inputs
+    fn validate(&mut self) -> EndpointResult<()> {
+        // Clean the data
+        self.note = self.note.as_ref().map(|note| note.clean());
+
+        validate_quantity(self.quantity)?;
+        validate_pickup_date(self.pickup_date)?;
+        validate_note(self.note.as_deref())?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequest<ServerState> for OrderCounterForm
+where
+    Json<Self>: FromRequest<ServerState, Rejection = JsonRejection>,
+{
+    type Rejection = EndpointRejection;
+
+    async fn from_request(req: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        // Extract data
+        let (mut parts, body) = req.into_parts();
+        let user = { FarmerUser::from_parts(&mut parts, state).await? };
+        let order_id = { ModelID::from_request_parts(&mut parts, state).await? };
+        let Json(mut counter) =
+            Json::<Self>::from_request(Request::from_parts(parts, body), state).await?;
+
+        // Validate form fields
+        counter.validate()?;
+
+        // Authorize request
+        let parties = find_order_parties(order_id, state.database()).await?;
+        if parties.farmer_id != user.id() {
+            return Err(EndpointRejection::forbidden());
+        }
+        if parties.status != OrderStatus::Pending {
+            return Err(EndpointRejection::BadRequest(
+                "Only pending orders can be countered.".into(),
+            ));
+        }
+
+        Ok(counter)
+    }
+}
+
+// ===== Filter impls =====
+
+/// `orders` query parameters.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderFilter {
+    /// filters for orders the user placed or received
+    #[serde(default)]
+    pub role: Option<OrderRole>,
+    /// filters for order status
+    #[serde(default)]
+    pub status: Option<OrderStatus>,
+}
+
+/// The user's role in an order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderRole {
+    /// Orders the user placed
+    Buyer,
+    /// Orders placed for the user's harvests
+    Farmer,
+}
+
+impl OrderRole {
+    /// Returns the role as used in the orders query
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Buyer => "buyer",
+            Self::Farmer => "farmer",
+        }
+    }
+}
+
+// ===== Helpers =====
+
+mod helpers {
+    use rust_decimal::Decimal;
+    use time::{Date, OffsetDateTime};
+
+    use crate::endpoint::{validators::ValidateString, EndpointRejection, EndpointResult};
+
+    /// Validate order quantity is greater than zero
+    pub fn validate_quantity(quantity: Decimal) -> EndpointResult<()> {
+        if quantity <= Decimal::ZERO {
+            return Err(EndpointRejection::BadRequest(
+                "Order quantity must be greater than zero.".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate order `pickup_date` is not a past date
+    pub fn validate_pickup_date(date: Date) -> EndpointResult<()> {
+        if date < OffsetDateTime::now_utc().date() {
+            return Err(EndpointRejection::BadRequest(
+                "Pickup date cannot be a past date.".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate order note length
+    pub fn validate_note(note: Option<&str>) -> EndpointResult<()> {
+        if let Some(note) = note {
+            note.validate_len(0, 512, "Order note must be at most 512 characters")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+//! Order http handlers impls
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    auth::{CurrentUser, FarmerUser},
+    endpoint::{EndpointRejection, EndpointResult},
+    mail::Mail,
+    server::state::DatabaseConnection,
+    types::{ModelID, Pagination},
+};
+
+use super::{
+    forms::{OrderCounterForm, OrderCreateForm, OrderFilter, OrderStatusForm},
+    models::{Order, OrderList},
+    permissions::{OrderBuyerPermission, OrderPartyPermission},
+    utils::{notify_order_parties, OrderEvent},
+};
+
+/// Handles the `GET /orders` route.
+///
+/// Returns the orders the user placed and the orders placed for their harvests.
+#[tracing::instrument(skip(db))]
+pub async fn order_list(
+    user: CurrentUser,
+    filter: Option<Query<OrderFilter>>,
+    pg: Option<Query<Pagination>>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<OrderList>> {
+    let filter = filter.unwrap_or_default().0;
+    let pagination = pg.unwrap_or_default().0;
+    let orders = Order::records(user.id, filter, pagination, db).await?;
+    Ok(Json(orders))
+}
+
+/// Handles the `GET /orders/:order_id` route.
+#[tracing::instrument(skip(db))]
+pub async fn order_detail(
+    _: OrderPartyPermission,
+    order_id: ModelID,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<Order>> {
+    Order::find(order_id, db).await?.map_or_else(
+        || Err(EndpointRejection::NotFound("Order not found.".into())),
+        |order| Ok(Json(order)),
+    )
+}
+
+/// Handles the `POST /orders` route.
+#[tracing::instrument(skip(db, mail, form))]
+pub async fn order_create(
+    user: CurrentUser,
+    State(db): State<DatabaseConnection>,
+    State(mail): State<Mail>,
+    form: OrderCreateForm,
+) -> EndpointResult<(StatusCode, Json<ModelID>)> {
+    let order_id = Order::insert(form.data(user.id), db.clone()).await?;
+
+    tokio::spawn(notify_order_parties(order_id, OrderEvent::Placed, db, mail));
+
+    Ok((StatusCode::CREATED, Json(order_id)))
+}
+
+/// Handles the `PUT /orders/:order_id/status` route.
+#[tracing::instrument(skip(db, mail, form))]
+pub async fn order_status_update(
+    user: CurrentUser,
+    order_id: ModelID,
+    State(db): State<DatabaseConnection>,
+    State(mail): State<Mail>,
+    form: OrderStatusForm,
+) -> EndpointResult<StatusCode> {
+    let event = OrderEvent::from(form.status);
+    Order::update_status(order_id, form.data(user.id), db.clone()).await?;
+
+    tokio::spawn(notify_order_parties(order_id, event, db, mail));
+
+    Ok(StatusCode::OK)
+}
+
+/// Handles the `POST /orders/:order_id/counter` route.
+#[tracing::instrument(skip(db, mail, form))]
+pub async fn order_counter(
+    _: FarmerUser,
+    order_id: ModelID,
+    State(db): State<DatabaseConnection>,
+    State(mail): State<Mail>,
+    form: OrderCounterForm,
+) -> EndpointResult<StatusCode> {
+    Order::counter(order_id, form.into(), db.clone()).await?;
+
+    tokio::spawn(notify_order_parties(
+        order_id,
+        OrderEvent::Countered,
+        db,
+        mail,
+    ));
+
+    Ok(StatusCode::OK)
+}
+
+/// Handles the `POST /orders/:order_id/counter/accept` route.
+#[tracing::instrument(skip(db, mail))]
+pub async fn order_counter_accept(
+    _: OrderBuyerPermission,
+    user: CurrentUser,
+    order_id: ModelID,
+    State(db): State<DatabaseConnection>,
+    State(mail): State<Mail>,
+) -> EndpointResult<StatusCode> {
+    Order::accept_counter(order_id, user.id, db.clone()).await?;
+
+    tokio::spawn(notify_order_parties(
+        order_id,
+        OrderEvent::CounterAccepted,
+        db,
+        mail,
+    ));
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,10 @@
+//! Order impls
+
+pub mod db;
+pub mod forms;
+pub mod handlers;
+pub mod models;
+pub mod permissions;
+mod utils;
+
+pub use utils::{notify_order_parties, OrderEvent};
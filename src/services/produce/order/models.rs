@@ -0,0 +1,224 @@
+//! Order model impls
+
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime};
+
+use crate::{
+    core::{
+        accounts::user::models::UserIndex,
+        types::{price::QuantityUnit, ModelID, ModelIdentifier},
+    },
+    services::produce::harvest::models::HarvestStockLevel,
+};
+
+/// A `Vec` of orders
+pub type OrderList = Vec<Order>;
+
+/// The model representing a row in the `orders` database table.
+///
+/// Returned by `order_detail` and `order_list` handlers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub id: ModelID,
+    pub harvest: ModelIdentifier,
+    pub farm: ModelIdentifier,
+    pub farmer: UserIndex,
+    pub buyer: UserIndex,
+    pub quantity: Decimal,
+    pub quantity_unit: Option<QuantityUnit>,
+    pub pickup_date: Date,
+    pub note: Option<String>,
+    pub status: OrderStatus,
+    /// The farmer's counter offer, if the order was countered
+    pub counter: Option<OrderCounter>,
+    /// The decline or cancel reason
+    pub status_reason: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub updated_at: Option<OffsetDateTime>,
+}
+
+impl Order {
+    /// Creates a new `Order` from the database row
+    #[allow(clippy::too_many_arguments)]
+    #[must_use]
+    pub fn from_row(
+        id: ModelID,
+        quantity: Decimal,
+        quantity_unit: Option<&str>,
+        pickup_date: Date,
+        note: Option<String>,
+        status: &str,
+        counter: Option<OrderCounter>,
+        status_reason: Option<String>,
+        created_at: OffsetDateTime,
+        updated_at: Option<OffsetDateTime>,
+        harvest_id: ModelID,
+        cultivar_name: String,
+        farm_id: ModelID,
+        farm_name: String,
+        farmer: UserIndex,
+        buyer: UserIndex,
+    ) -> Self {
+        Self {
+            id,
+            harvest: ModelIdentifier::from_row(harvest_id, cultivar_name),
+            farm: ModelIdentifier::from_row(farm_id, farm_name),
+            farmer,
+            buyer,
+            quantity,
+            quantity_unit: quantity_unit.and_then(QuantityUnit::from_row),
+            pickup_date,
+            note,
+            status: OrderStatus::from_row(status),
+            counter,
+            status_reason,
+            created_at,
+            updated_at,
+        }
+    }
+}
+
+/// The farmer's counter offer to an order
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderCounter {
+    pub quantity: Decimal,
+    pub pickup_date: Date,
+    pub note: Option<String>,
+}
+
+impl OrderCounter {
+    /// Creates a new `OrderCounter` from the database row
+    #[must_use]
+    pub fn from_row(quantity: Decimal, pickup_date: Date, note: Option<String>) -> Self {
+        Self {
+            quantity,
+            pickup_date,
+            note,
+        }
+    }
+}
+
+/// Order status
+///
+/// An order moves from `pending` to `accepted`, `ready` and `collected`,
+/// it can be `declined` by the farmer while pending
+/// or `cancelled` by both parties until it is collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    Pending,
+    Accepted,
+    Ready,
+    Collected,
+    Declined,
+    Cancelled,
+}
+
+impl OrderStatus {
+    /// Returns the status as stored in the database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Accepted => "accepted",
+            Self::Ready => "ready",
+            Self::Collected => "collected",
+            Self::Declined => "declined",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Creates a new `OrderStatus` from the database column
+    #[must_use]
+    pub fn from_row(status: &str) -> Self {
+        match status {
+            "pending" => Self::Pending,
+            "accepted" => Self::Accepted,
+            "ready" => Self::Ready,
+            "collected" => Self::Collected,
+            "declined" => Self::Declined,
+            // Statuses are checked by the database constraint
+            _ => Self::Cancelled,
+        }
+    }
+
+    /// Returns the statuses an order can move to this status from
+    #[must_use]
+    pub const fn previous(self) -> &'static [Self] {
+        match self {
+            Self::Pending => &[],
+            Self::Accepted | Self::Declined => &[Self::Pending],
+            Self::Ready => &[Self::Accepted],
+            Self::Collected => &[Self::Accepted, Self::Ready],
+            Self::Cancelled => &[Self::Pending, Self::Accepted, Self::Ready],
+        }
+    }
+
+    /// Returns true if the order can move from `current` status to this status
+    #[must_use]
+    pub fn can_move_from(self, current: Self) -> bool {
+        self.previous().contains(&current)
+    }
+
+    /// Returns true if the status can only be set by the farmer
+    #[must_use]
+    pub const fn is_farmer_only(self) -> bool {
+        !matches!(self, Self::Cancelled)
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Order buyer and farmer, used for authorizing order updates
+#[derive(Debug, Clone)]
+pub struct OrderParties {
+    pub buyer_id: ModelID,
+    pub farmer_id: ModelID,
+    pub status: OrderStatus,
+}
+
+impl OrderParties {
+    /// Creates a new `OrderParties` from the database row
+    #[must_use]
+    pub fn from_row(buyer_id: ModelID, farmer_id: ModelID, status: &str) -> Self {
+        Self {
+            buyer_id,
+            farmer_id,
+            status: OrderStatus::from_row(status),
+        }
+    }
+
+    /// Returns true if the user is the buyer or the farmer
+    #[must_use]
+    pub fn is_party(&self, user_id: ModelID) -> bool {
+        self.buyer_id == user_id || self.farmer_id == user_id
+    }
+}
+
+/// A harvest an order is placed for
+#[derive(Debug, Clone)]
+pub struct OrderHarvest {
+    pub farmer_id: ModelID,
+    /// `None` if the farmer does not track the harvest stock
+    pub stock: Option<HarvestStockLevel>,
+}
+
+impl OrderHarvest {
+    /// Returns the quantity not reserved for other buyers,
+    /// `None` if the harvest stock is not tracked.
+    #[must_use]
+    pub fn free_quantity(&self) -> Option<Decimal> {
+        self.stock
+            .as_ref()
+            .map(|stock| stock.available_quantity - stock.reserved_quantity)
+    }
+}
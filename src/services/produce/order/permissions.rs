@@ -0,0 +1,112 @@
+//! Order permission impls
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+
+use crate::{
+    auth::CurrentUser,
+    endpoint::{EndpointRejection, EndpointResult},
+    server::state::{DatabaseConnection, ServerState},
+    types::ModelID,
+};
+
+use super::models::{OrderParties, OrderStatus};
+
+/// Checks if user is the order buyer or the harvest farmer
+#[derive(Debug, Clone)]
+pub struct OrderPartyPermission;
+
+#[async_trait]
+impl FromRequestParts<ServerState> for OrderPartyPermission {
+    type Rejection = EndpointRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &ServerState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = CurrentUser::from_parts(parts, state).await?;
+        let order_id = ModelID::from_request_parts(parts, state).await?;
+
+        let parties = find_order_parties(order_id, state.database()).await?;
+        if !parties.is_party(user.id) {
+            return Err(EndpointRejection::forbidden());
+        }
+
+        Ok(Self)
+    }
+}
+
+/// Checks if user is the buyer of a pending order
+#[derive(Debug, Clone)]
+pub struct OrderBuyerPermission;
+
+#[async_trait]
+impl FromRequestParts<ServerState> for OrderBuyerPermission {
+    type Rejection = EndpointRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &ServerState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = CurrentUser::from_parts(parts, state).await?;
+        let order_id = ModelID::from_request_parts(parts, state).await?;
+
+        let parties = find_order_parties(order_id, state.database()).await?;
+        if parties.buyer_id != user.id {
+            return Err(EndpointRejection::forbidden());
+        }
+        if parties.status != OrderStatus::Pending {
+            return Err(EndpointRejection::BadRequest(
+                "The order is no longer pending.".into(),
+            ));
+        }
+
+        Ok(Self)
+    }
+}
+
+/// Fetches the order buyer, farmer and status
+///
+/// # Errors
+///
+/// Return an error if the order cannot be found.
+pub async fn find_order_parties(
+    order_id: ModelID,
+    db: DatabaseConnection,
+) -> EndpointResult<OrderParties> {
+    match sqlx::query!(
+        r#"
+            SELECT order_.buyer_id,
+                order_.status,
+                farm.owner_id AS "farmer_id?"
+            FROM services.orders order_
+            LEFT JOIN services.harvests harvest
+                ON order_.harvest_id = harvest.id
+            LEFT JOIN services.locations location_
+                ON harvest.location_id = location_.id
+            LEFT JOIN services.farms farm
+                ON location_.farm_id = farm.id
+            WHERE order_.id = $1
+            "#,
+        order_id.0
+    )
+    .fetch_optional(&db.pool)
+    .await
+    {
+        Ok(Some(rec)) => {
+            // The farm owner is null if the farm is archived
+            let Some(farmer_id) = rec.farmer_id else {
+                return Err(EndpointRejection::NotFound("Order not found.".into()));
+            };
+            Ok(OrderParties::from_row(
+                rec.buyer_id.into(),
+                farmer_id.into(),
+                &rec.status,
+            ))
+        }
+        Ok(None) => Err(EndpointRejection::NotFound("Order not found.".into())),
+        Err(err) => {
+            tracing::error!("Database error: {}", err);
+            Err(EndpointRejection::internal_server_error())
+        }
+    }
+}
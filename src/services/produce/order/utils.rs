@@ -0,0 +1,312 @@
+//! Order helpers impls
+
+use rust_decimal::Decimal;
+use time::{Date, OffsetDateTime};
+
+use crate::{
+    error::ServerResult,
+    mail::{Mail, OrderRecipient, OrderUpdate},
+    queue::Task,
+    server::state::DatabaseConnection,
+    services::produce::harvest::db::handle_harvest_database_error,
+    types::{price::QuantityUnit, Language, ModelID},
+    SERVER_DOMAIN_NAME,
+};
+
+use super::models::OrderStatus;
+
+/// Harvest stock change made by an order
+#[derive(Debug, Clone)]
+pub struct HarvestStockUpdate {
+    pub id: ModelID,
+    pub available_change: Decimal,
+    pub reserved_change: Decimal,
+    /// `None` for changes made by the maintenance
+    pub author_id: Option<ModelID>,
+    pub reason: &'static str,
+    pub created_at: OffsetDateTime,
+}
+
+impl HarvestStockUpdate {
+    /// Creates a new `HarvestStockUpdate`
+    #[must_use]
+    pub fn new(
+        available_change: Decimal,
+        reserved_change: Decimal,
+        author_id: Option<ModelID>,
+        reason: &'static str,
+        created_at: OffsetDateTime,
+    ) -> Self {
+        Self {
+            id: ModelID::new(),
+            available_change,
+            reserved_change,
+            author_id,
+            reason,
+            created_at,
+        }
+    }
+}
+
+/// Updates harvest available and reserved quantities and records the change,
+/// the harvest is marked `finished` when its available quantity reaches zero.
+pub async fn update_harvest_stock(
+    harvest_id: ModelID,
+    update: HarvestStockUpdate,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> ServerResult<()> {
+    match sqlx::query!(
+        r#"
+            WITH harvest_ AS (
+                UPDATE services.harvests harvest
+                SET available_quantity = harvest.available_quantity + $2,
+                    reserved_quantity = harvest.reserved_quantity + $3,
+                    finished = harvest.finished OR harvest.available_quantity + $2 = 0,
+                    finished_at = CASE
+                        WHEN NOT harvest.finished AND harvest.available_quantity + $2 = 0
+                            THEN $8::date
+                        ELSE harvest.finished_at
+                    END,
+                    updated_at = $7
+                WHERE harvest.id = $1
+                    AND harvest.available_quantity IS NOT NULL
+                RETURNING harvest.id, harvest.available_quantity, harvest.reserved_quantity
+            )
+            INSERT INTO services.harvest_stock_changes(
+                id,
+                harvest_id,
+                author_id,
+                quantity_change,
+                available_quantity,
+                reserved_quantity,
+                reason,
+                created_at
+            )
+            SELECT $4, harvest_.id, $5, $2,
+                harvest_.available_quantity, harvest_.reserved_quantity, $6, $7
+            FROM harvest_;
+        "#,
+        harvest_id.0,
+        update.available_change,
+        update.reserved_change,
+        update.id.0,
+        update.author_id.map(|id| id.0),
+        update.reason,
+        update.created_at,
+        update.created_at.date(),
+    )
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(result) => {
+            tracing::trace!(
+                "Harvest stock updated, but transaction not committed: {:?}",
+                result
+            );
+            Ok(())
+        }
+        Err(err) => {
+            // Handle database constraint error
+            handle_harvest_database_error(&err)?;
+
+            tracing::error!("Database error, failed to update harvest stock: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+// ===== Order notifications =====
+
+/// Order changes both the buyer and the farmer are notified about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderEvent {
+    Placed,
+    Accepted,
+    Declined,
+    Countered,
+    CounterAccepted,
+    Ready,
+    Collected,
+    Cancelled,
+}
+
+impl From<OrderStatus> for OrderEvent {
+    fn from(status: OrderStatus) -> Self {
+        match status {
+            OrderStatus::Pending => Self::Placed,
+            OrderStatus::Accepted => Self::Accepted,
+            OrderStatus::Declined => Self::Declined,
+            OrderStatus::Ready => Self::Ready,
+            OrderStatus::Collected => Self::Collected,
+            OrderStatus::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+impl OrderEvent {
+    /// Returns the order event email catalogue key
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::Placed => "placed",
+            Self::Accepted => "accepted",
+            Self::Declined => "declined",
+            Self::Countered => "countered",
+            Self::CounterAccepted => "counter_accepted",
+            Self::Ready => "ready",
+            Self::Collected => "collected",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Order details used in the order notification emails
+#[derive(Debug, Clone)]
+pub struct OrderContacts {
+    pub order_id: ModelID,
+    pub cultivar_name: String,
+    pub farm_name: String,
+    pub quantity: Decimal,
+    pub quantity_unit: Option<QuantityUnit>,
+    pub pickup_date: Date,
+    pub status: String,
+    pub buyer_first_name: String,
+    pub buyer_email: String,
+    pub buyer_language: Language,
+    pub farmer_first_name: String,
+    pub farmer_email: String,
+    pub farmer_language: Language,
+}
+
+impl OrderContacts {
+    /// Returns the order quantity with its unit
+    #[must_use]
+    pub fn quantity(&self) -> String {
+        let quantity = self.quantity.normalize();
+        self.quantity_unit
+            .map_or_else(|| quantity.to_string(), |unit| format!("{quantity} {unit}"))
+    }
+
+    /// Fetches the order details and the buyer and farmer emails from the database
+    pub async fn find(order_id: ModelID, db: DatabaseConnection) -> ServerResult<Option<Self>> {
+        match sqlx::query!(
+            r#"
+                SELECT order_.id AS order_id,
+                    order_.quantity,
+                    order_.quantity_unit,
+                    order_.pickup_date,
+                    order_.status,
+                    cultivar.name AS cultivar_name,
+                    farm.name AS farm_name,
+                    buyer.first_name AS buyer_first_name,
+                    buyer_email.email AS buyer_email,
+                    buyer.language AS buyer_language,
+                    farmer.first_name AS farmer_first_name,
+                    farmer_email.email AS farmer_email,
+                    farmer.language AS farmer_language
+                FROM services.orders order_
+                INNER JOIN services.harvests harvest
+                    ON order_.harvest_id = harvest.id
+                INNER JOIN services.cultivars cultivar
+                    ON harvest.cultivar_id = cultivar.id
+                INNER JOIN services.locations location_
+                    ON harvest.location_id = location_.id
+                INNER JOIN services.farms farm
+                    ON location_.farm_id = farm.id
+                INNER JOIN accounts.users buyer
+                    ON order_.buyer_id = buyer.id
+                INNER JOIN accounts.emails buyer_email
+                    ON buyer.id = buyer_email.user_id
+                INNER JOIN accounts.users farmer
+                    ON farm.owner_id = farmer.id
+                INNER JOIN accounts.emails farmer_email
+                    ON farmer.id = farmer_email.user_id
+
+                WHERE order_.id = $1;
+            "#,
+            order_id.0,
+        )
+        .fetch_optional(&db.pool)
+        .await
+        {
+            Ok(rec) => Ok(rec.map(|rec| Self {
+                order_id: rec.order_id.into(),
+                cultivar_name: rec.cultivar_name,
+                farm_name: rec.farm_name,
+                quantity: rec.quantity,
+                quantity_unit: rec
+                    .quantity_unit
+                    .as_deref()
+                    .and_then(QuantityUnit::from_row),
+                pickup_date: rec.pickup_date,
+                status: rec.status,
+                buyer_first_name: rec.buyer_first_name,
+                buyer_email: rec.buyer_email,
+                buyer_language: Language::from_row(&rec.buyer_language),
+                farmer_first_name: rec.farmer_first_name,
+                farmer_email: rec.farmer_email,
+                farmer_language: Language::from_row(&rec.farmer_language),
+            })),
+            Err(err) => {
+                tracing::error!("Database error, failed to fetch order contacts: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Emails the order buyer and farmer about the order change
+///
+/// Errors are logged only, the order change has already been saved.
+pub async fn notify_order_parties(
+    order_id: ModelID,
+    event: OrderEvent,
+    db: DatabaseConnection,
+    mail: Mail,
+) {
+    let order = match OrderContacts::find(order_id, db.clone()).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return,
+        Err(err) => {
+            tracing::error!("Failed to send order notifications: {}", err);
+            return;
+        }
+    };
+
+    let domain = SERVER_DOMAIN_NAME.get().unwrap();
+    let update = OrderUpdate {
+        event: event.key(),
+        cultivar_name: order.cultivar_name.clone(),
+        farm_name: order.farm_name.clone(),
+        buyer_first_name: order.buyer_first_name.clone(),
+        quantity: order.quantity(),
+        pickup_date: order.pickup_date.to_string(),
+        status: order.status.clone(),
+        link: format!("{domain}/orders/{}", order.order_id),
+    };
+
+    let recipients = [
+        (
+            &order.buyer_first_name,
+            &order.buyer_email,
+            order.buyer_language,
+            OrderRecipient::Buyer,
+        ),
+        (
+            &order.farmer_first_name,
+            &order.farmer_email,
+            order.farmer_language,
+            OrderRecipient::Farmer,
+        ),
+    ];
+    for (first_name, email_address, language, recipient) in recipients {
+        let email = mail.order_update(first_name, email_address, language, &update, recipient);
+        let queued = match email.and_then(Task::send_email) {
+            Ok(task) => task.enqueue(db.clone()).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = queued {
+            tracing::error!("Failed to enqueue order notification email: {}", err);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Search database impl
+
+use crate::{error::ServerResult, server::state::DatabaseConnection};
+
+use super::models::{SearchHit, SearchHitList};
+
+/// `ts_headline` options, matching terms are wrapped in `<mark></mark>`,
+/// the text is HTML escaped before the terms are marked.
+const HIGHLIGHT_OPTIONS: &str =
+    "StartSel=<mark>, StopSel=</mark>, MaxWords=24, MinWords=8, MaxFragments=2";
+
+impl SearchHit {
+    /// Searches harvests, cultivars and farms in the database
+    ///
+    /// Hits are ranked by full text search rank on the records
+    /// search documents and by trigram similarity of their names,
+    /// so misspelled names still match.
+    #[tracing::instrument(name = "Search", skip(db))]
+    pub async fn search(
+        query: &str,
+        limit: u32,
+        db: DatabaseConnection,
+    ) -> ServerResult<SearchHitList> {
+        //NB! Don't forget to select records from services.active_* views
+        match sqlx::query!(
+            r#"
+                WITH search AS (
+                    SELECT websearch_to_tsquery('english', $1) AS query
+                ),
+                hits AS (
+                    SELECT 'harvest' AS kind,
+                        harvest.id,
+                        cultivar.name AS title,
+                        ts_headline(
+                            'english',
+                            services.html_escape(concat_ws(' · ', cultivar.name, harvest.type,
+                                harvest.description, farm.name, location_.place_name)),
+                            search.query,
+                            $3
+                        ) AS highlight,
+                        harvest.images[1] AS image,
+                        ts_rank(document.value, search.query)
+                            + word_similarity($1, cultivar.name) AS rank
+                    FROM services.active_harvests harvest
+                    INNER JOIN services.cultivars cultivar
+                        ON harvest.cultivar_id = cultivar.id
+                    INNER JOIN services.active_locations location_
+                        ON harvest.location_id = location_.id
+                    INNER JOIN services.active_farms farm
+                        ON location_.farm_id = farm.id
+                    CROSS JOIN search
+                    CROSS JOIN LATERAL (
+                        SELECT harvest.search_document
+                            || cultivar.search_document
+                            || farm.search_document
+                            || location_.search_document AS value
+                    ) document
+                    WHERE document.value @@ search.query
+                        OR cultivar.name %> $1
+
+                    UNION ALL
+
+                    SELECT 'cultivar' AS kind,
+                        cultivar.id,
+                        cultivar.name AS title,
+                        ts_headline(
+                            'english',
+                            services.html_escape(concat_ws(' · ', cultivar.name, category.name)),
+                            search.query,
+                            $3
+                        ) AS highlight,
+                        cultivar.image AS image,
+                        ts_rank(cultivar.search_document, search.query)
+                            + word_similarity($1, cultivar.name) AS rank
+                    FROM services.cultivars cultivar
+                    LEFT JOIN services.cultivar_categories category
+                        ON cultivar.category_id = category.id
+                    CROSS JOIN search
+                    WHERE cultivar.search_document @@ search.query
+                        OR cultivar.name %> $1
+
+                    UNION ALL
+
+                    SELECT 'farm' AS kind,
+                        farm.id,
+                        farm.name AS title,
+                        ts_headline(
+                            'english',
+                            services.html_escape(concat_ws(' · ', farm.name, location_.place_name)),
+                            search.query,
+                            $3
+                        ) AS highlight,
+                        farm.logo AS image,
+                        ts_rank(
+                            farm.search_document || COALESCE(location_.search_document, ''::tsvector),
+                            search.query
+                        )
+                            + greatest(
+                                word_similarity($1, farm.name),
+                                word_similarity($1, location_.place_name)
+                            ) AS rank
+                    FROM services.active_farms farm
+                    CROSS JOIN search
+                    -- The farm location best matching the search,
+                    -- farms without an active location are still searched by name
+                    LEFT JOIN LATERAL (
+                        SELECT location_.place_name, location_.search_document
+                        FROM services.active_locations location_
+                        WHERE location_.farm_id = farm.id
+                        ORDER BY ts_rank(location_.search_document, search.query)
+                            + word_similarity($1, location_.place_name) DESC
+                        LIMIT 1
+                    ) location_ ON true
+                    WHERE (
+                            farm.search_document
+                                || COALESCE(location_.search_document, ''::tsvector)
+                        ) @@ search.query
+                        OR farm.name %> $1
+                        OR location_.place_name %> $1
+                )
+                SELECT hits.kind AS "kind!",
+                    hits.id AS "id!",
+                    hits.title AS "title!",
+                    hits.highlight AS "highlight!",
+                    hits.image,
+                    hits.rank AS "rank!"
+                FROM hits
+                ORDER BY hits.rank DESC, hits.id
+                LIMIT $2;
+            "#,
+            query,
+            i64::from(limit),
+            HIGHLIGHT_OPTIONS,
+        )
+        .fetch_all(&db.pool)
+        .await
+        {
+            Ok(records) => {
+                let hits = records
+                    .into_iter()
+                    .map(|rec| {
+                        SearchHit::from_row(
+                            &rec.kind,
+                            rec.id.into(),
+                            rec.title,
+                            rec.highlight,
+                            rec.image,
+                            rec.rank,
+                        )
+                    })
+                    .collect();
+
+                Ok(hits)
+            }
+            Err(err) => {
+                tracing::error!("Database error, failed to search: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Search forms impls
+
+use serde::Deserialize;
+
+use crate::endpoint::{
+    validators::{TransformString, ValidateString},
+    EndpointResult,
+};
+
+/// `search` query parameters.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchQuery {
+    /// Search terms
+    #[serde(default)]
+    pub q: String,
+    /// maximum number of hits should be returned
+    #[serde(default = "default_search_hits_limit")]
+    pub limit: u32,
+}
+
+/// Default maximum number of search hits returned `20`
+const fn default_search_hits_limit() -> u32 {
+    20
+}
+
+/// Maximum number of search hits that can be requested
+const SEARCH_HITS_MAX_LIMIT: u32 = 50;
+
+impl SearchQuery {
+    /// Validates search query inputs
+    pub fn validate(&mut self) -> EndpointResult<()> {
+        // Clean the data
+        self.clean_data();
+
+        self.q
+            .validate_len(1, 128, "Search query must be between 1 and 128 characters")?;
+
+        Ok(())
+    }
+
+    /// Clean query data
+    fn clean_data(&mut self) {
+        self.q = self.q.clean();
+        self.limit = self.limit.clamp(1, SEARCH_HITS_MAX_LIMIT);
+    }
+}
@@ -0,0 +1,21 @@
+//! Search http handlers impls
+
+use axum::extract::{Json, Query, State};
+
+use crate::{endpoint::EndpointResult, server::state::DatabaseConnection};
+
+use super::{
+    forms::SearchQuery,
+    models::{SearchHit, SearchHitList},
+};
+
+/// Handles the `GET /search` route.
+#[tracing::instrument(skip(db))]
+pub async fn search(
+    Query(mut query): Query<SearchQuery>,
+    State(db): State<DatabaseConnection>,
+) -> EndpointResult<Json<SearchHitList>> {
+    query.validate()?;
+    let hits = SearchHit::search(&query.q, query.limit, db).await?;
+    Ok(Json(hits))
+}
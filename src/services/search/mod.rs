@@ -0,0 +1,6 @@
+//! Search service
+
+pub mod db;
+pub mod forms;
+pub mod handlers;
+pub mod models;
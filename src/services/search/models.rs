@@ -0,0 +1,64 @@
+//! Search model impls
+
+use serde::Serialize;
+
+use crate::types::ModelID;
+
+/// A `Vec` of search hits
+pub type SearchHitList = Vec<SearchHit>;
+
+/// The kind of record a search hit refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchHitKind {
+    Harvest,
+    Cultivar,
+    Farm,
+}
+
+impl SearchHitKind {
+    /// Creates a new `SearchHitKind` from the database row
+    #[must_use]
+    pub fn from_row(kind: &str) -> Self {
+        match kind {
+            "harvest" => Self::Harvest,
+            "cultivar" => Self::Cultivar,
+            _ => Self::Farm,
+        }
+    }
+}
+
+/// A type returned by `search` handler.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub kind: SearchHitKind,
+    pub id: ModelID,
+    pub title: String,
+    /// HTML escaped matched text, matching terms are wrapped in `<mark></mark>`
+    pub highlight: String,
+    pub image: Option<String>,
+    pub rank: f32,
+}
+
+impl SearchHit {
+    /// Creates a new `SearchHit` from the database row
+    #[must_use]
+    pub fn from_row(
+        kind: &str,
+        id: ModelID,
+        title: String,
+        highlight: String,
+        image: Option<String>,
+        rank: f32,
+    ) -> Self {
+        Self {
+            kind: SearchHitKind::from_row(kind),
+            id,
+            title,
+            highlight,
+            image,
+            rank,
+        }
+    }
+}
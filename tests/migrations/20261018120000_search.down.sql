@@ -0,0 +1,38 @@
+-- Add down migration script here
+
+DROP VIEW IF EXISTS services.active_harvests;
+DROP VIEW IF EXISTS services.active_farms;
+DROP VIEW IF EXISTS services.active_locations;
+
+DROP INDEX IF EXISTS services.locations_place_name_trgm_idx;
+DROP INDEX IF EXISTS services.farms_name_trgm_idx;
+DROP INDEX IF EXISTS services.cultivars_name_trgm_idx;
+
+ALTER TABLE services.locations DROP COLUMN IF EXISTS search_document;
+ALTER TABLE services.farms DROP COLUMN IF EXISTS search_document;
+ALTER TABLE services.harvests DROP COLUMN IF EXISTS search_document;
+ALTER TABLE services.cultivars DROP COLUMN IF EXISTS search_document;
+
+DROP FUNCTION IF EXISTS services.location_search_document(text, text);
+DROP FUNCTION IF EXISTS services.farm_search_document(text);
+DROP FUNCTION IF EXISTS services.harvest_search_document(text, text);
+DROP FUNCTION IF EXISTS services.cultivar_search_document(text, text);
+
+CREATE VIEW services.active_locations AS (
+	SELECT *
+	FROM services.locations location_
+	WHERE location_.deleted = false
+);
+
+CREATE VIEW services.active_farms AS (
+	SELECT *
+	FROM services.farms farm
+	WHERE farm.deleted = false
+    	AND farm.owner_id IS NOT NULL
+);
+
+CREATE VIEW services.active_harvests AS (
+	SELECT *
+	FROM services.harvests harvest
+	WHERE harvest.finished = false
+);
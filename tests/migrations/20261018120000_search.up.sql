@@ -0,0 +1,97 @@
+-- Add up migration script here
+
+-- Full text search over harvests, cultivars and farms.
+-- The search documents are kept up to date by the insert and update queries.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE OR REPLACE FUNCTION services.cultivar_search_document(name text, category text)
+RETURNS tsvector
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT setweight(to_tsvector('english', COALESCE(name, '')), 'A')
+        || setweight(to_tsvector('english', COALESCE(category, '')), 'B');
+$$;
+
+CREATE OR REPLACE FUNCTION services.harvest_search_document(type text, description text)
+RETURNS tsvector
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT setweight(to_tsvector('english', COALESCE(type, '')), 'B')
+        || setweight(to_tsvector('english', COALESCE(description, '')), 'D');
+$$;
+
+CREATE OR REPLACE FUNCTION services.farm_search_document(name text)
+RETURNS tsvector
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT setweight(to_tsvector('english', COALESCE(name, '')), 'A');
+$$;
+
+CREATE OR REPLACE FUNCTION services.location_search_document(place_name text, description text)
+RETURNS tsvector
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT setweight(to_tsvector('english', COALESCE(place_name, '')), 'C')
+        || setweight(to_tsvector('english', COALESCE(description, '')), 'D');
+$$;
+
+ALTER TABLE services.cultivars
+    ADD COLUMN IF NOT EXISTS search_document tsvector NOT NULL DEFAULT ''::tsvector;
+ALTER TABLE services.harvests
+    ADD COLUMN IF NOT EXISTS search_document tsvector NOT NULL DEFAULT ''::tsvector;
+ALTER TABLE services.farms
+    ADD COLUMN IF NOT EXISTS search_document tsvector NOT NULL DEFAULT ''::tsvector;
+ALTER TABLE services.locations
+    ADD COLUMN IF NOT EXISTS search_document tsvector NOT NULL DEFAULT ''::tsvector;
+
+-- Backfill the existing rows
+UPDATE services.cultivars cultivar
+SET search_document = services.cultivar_search_document(cultivar.name, category.name)
+FROM services.cultivar_categories category
+WHERE cultivar.category_id = category.id;
+
+UPDATE services.harvests harvest
+SET search_document = services.harvest_search_document(harvest.type, harvest.description);
+
+UPDATE services.farms farm
+SET search_document = services.farm_search_document(farm.name);
+
+UPDATE services.locations location_
+SET search_document = services.location_search_document(location_.place_name, location_.description);
+
+CREATE INDEX IF NOT EXISTS cultivars_search_document_idx
+    ON services.cultivars USING gin (search_document);
+CREATE INDEX IF NOT EXISTS harvests_search_document_idx
+    ON services.harvests USING gin (search_document);
+CREATE INDEX IF NOT EXISTS farms_search_document_idx
+    ON services.farms USING gin (search_document);
+CREATE INDEX IF NOT EXISTS locations_search_document_idx
+    ON services.locations USING gin (search_document);
+
+-- Trigram indexes for typo tolerant matching
+CREATE INDEX IF NOT EXISTS cultivars_name_trgm_idx
+    ON services.cultivars USING gin (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS farms_name_trgm_idx
+    ON services.farms USING gin (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS locations_place_name_trgm_idx
+    ON services.locations USING gin (place_name gin_trgm_ops);
+
+-- Recreate the views so they include the new columns
+CREATE OR REPLACE VIEW services.active_locations AS (
+	SELECT *
+	FROM services.locations location_
+	WHERE location_.deleted = false
+);
+
+CREATE OR REPLACE VIEW services.active_farms AS (
+	SELECT *
+	FROM services.farms farm
+	WHERE farm.deleted = false
+    	AND farm.owner_id IS NOT NULL
+);
+
+CREATE OR REPLACE VIEW services.active_harvests AS (
+	SELECT *
+	FROM services.harvests harvest
+	WHERE harvest.finished = false
+);
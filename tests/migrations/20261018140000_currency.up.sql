@@ -0,0 +1,15 @@
+-- Add up migration script here
+
+-- ISO-4217 currency harvests listed in the country are priced in
+ALTER TABLE services.countries
+    ADD COLUMN IF NOT EXISTS currency text NOT NULL DEFAULT 'NAD'
+        CHECK (currency ~ '^[A-Z]{3}$');
+
+-- Prices without a currency default to the currency of the harvest location's country
+UPDATE services.harvests harvest
+SET price = harvest.price || jsonb_build_object('currency', country.currency)
+FROM services.locations location_
+INNER JOIN services.countries country
+    ON location_.country_id = country.id
+WHERE harvest.location_id = location_.id
+    AND NOT harvest.price ? 'currency';
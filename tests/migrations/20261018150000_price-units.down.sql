@@ -0,0 +1,19 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS services.harvests_active_price_per_unit_idx;
+
+CREATE OR REPLACE FUNCTION services.price_per_unit(price jsonb)
+RETURNS numeric
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT CASE jsonb_typeof(price->'unit')
+        WHEN 'object' THEN (price->>'amount')::numeric / NULLIF((price->'unit'->>'Kg')::numeric, 0)
+        ELSE (price->>'amount')::numeric
+    END;
+$$;
+
+CREATE INDEX IF NOT EXISTS harvests_active_price_per_unit_idx
+    ON services.harvests (services.price_per_unit(price)) WHERE finished = false;
+
+DROP FUNCTION IF EXISTS services.price_per_litre(jsonb);
+DROP FUNCTION IF EXISTS services.price_per_kg(jsonb);
@@ -0,0 +1,19 @@
+-- Add down migration script here
+
+DROP VIEW IF EXISTS services.active_harvests;
+
+DROP FUNCTION IF EXISTS services.harvest_low_stock(numeric, numeric, numeric);
+
+DROP TABLE IF EXISTS services.harvest_stock_changes;
+
+ALTER TABLE services.harvests
+    DROP COLUMN IF EXISTS low_stock_threshold,
+    DROP COLUMN IF EXISTS quantity_unit,
+    DROP COLUMN IF EXISTS reserved_quantity,
+    DROP COLUMN IF EXISTS available_quantity;
+
+CREATE VIEW services.active_harvests AS (
+	SELECT *
+	FROM services.harvests harvest
+	WHERE harvest.finished = false
+);
@@ -0,0 +1,51 @@
+-- Add up migration script here
+
+-- Harvest stock, quantities are counted in `quantity_unit`.
+-- `available_quantity` is NULL when the farmer does not track the harvest stock,
+-- `reserved_quantity` is part of the available quantity held for buyers.
+ALTER TABLE services.harvests
+    ADD COLUMN IF NOT EXISTS available_quantity numeric
+        CONSTRAINT harvests_available_quantity_check CHECK (available_quantity >= 0),
+    ADD COLUMN IF NOT EXISTS reserved_quantity numeric NOT NULL DEFAULT 0
+        CONSTRAINT harvests_reserved_quantity_check
+            CHECK (reserved_quantity >= 0 AND reserved_quantity <= COALESCE(available_quantity, 0)),
+    ADD COLUMN IF NOT EXISTS quantity_unit text,
+    ADD COLUMN IF NOT EXISTS low_stock_threshold numeric
+        CONSTRAINT harvests_low_stock_threshold_check CHECK (low_stock_threshold >= 0);
+
+-- Harvest stock adjustments history
+CREATE TABLE IF NOT EXISTS services.harvest_stock_changes(
+    id uuid PRIMARY KEY,
+    harvest_id uuid REFERENCES services.harvests (id) ON DELETE CASCADE NOT NULL,
+    author_id uuid REFERENCES accounts.users (id) ON DELETE SET NULL,
+    quantity_change numeric NOT NULL,
+    -- Quantities after the change
+    available_quantity numeric NOT NULL,
+    reserved_quantity numeric NOT NULL,
+    reason text,
+    created_at timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS harvest_stock_changes_harvest_id_idx
+    ON services.harvest_stock_changes (harvest_id, created_at);
+
+-- Returns true if the harvest stock is tracked and the quantity not
+-- reserved is at or below the low stock threshold
+CREATE OR REPLACE FUNCTION services.harvest_low_stock(
+    available_quantity numeric,
+    reserved_quantity numeric,
+    low_stock_threshold numeric
+)
+RETURNS boolean
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT available_quantity IS NOT NULL
+        AND available_quantity - reserved_quantity <= COALESCE(low_stock_threshold, 0);
+$$;
+
+-- Recreate the view so it includes the new columns
+CREATE OR REPLACE VIEW services.active_harvests AS (
+	SELECT *
+	FROM services.harvests harvest
+	WHERE harvest.finished = false
+);
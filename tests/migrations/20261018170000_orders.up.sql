@@ -0,0 +1,32 @@
+-- Add up migration script here
+
+-- Buyer reservations against harvest listings.
+-- `quantity` is counted in `quantity_unit` of the harvest.
+CREATE TABLE IF NOT EXISTS services.orders(
+    id uuid PRIMARY KEY,
+    harvest_id uuid REFERENCES services.harvests (id) ON DELETE CASCADE NOT NULL,
+    buyer_id uuid REFERENCES accounts.users (id) ON DELETE CASCADE NOT NULL,
+    quantity numeric NOT NULL CHECK (quantity > 0),
+    quantity_unit text,
+    pickup_date date NOT NULL,
+    note text,
+    status text NOT NULL
+        CHECK (status IN ('pending', 'accepted', 'ready', 'collected', 'declined', 'cancelled')),
+    -- Set when the farmer counters the buyer's order
+    counter_quantity numeric CHECK (counter_quantity > 0),
+    counter_pickup_date date,
+    counter_note text,
+    -- The decline or cancel reason
+    status_reason text,
+    -- True if the order quantity was reserved from the harvest stock on accept
+    stock_reserved boolean NOT NULL DEFAULT false,
+    created_at timestamptz NOT NULL,
+    updated_at timestamptz
+);
+
+CREATE INDEX IF NOT EXISTS orders_harvest_id_idx ON services.orders (harvest_id);
+CREATE INDEX IF NOT EXISTS orders_buyer_id_idx ON services.orders (buyer_id);
+
+-- Direct messages sent about an order
+ALTER TABLE features.direct_messages
+    ADD COLUMN IF NOT EXISTS order_id uuid REFERENCES services.orders (id) ON DELETE SET NULL;
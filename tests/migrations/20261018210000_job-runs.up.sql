@@ -0,0 +1,16 @@
+-- Add up migration script here
+
+-- Server maintenance job runs history
+CREATE TABLE IF NOT EXISTS maintenance.job_runs(
+    id uuid PRIMARY KEY,
+    job text NOT NULL,
+    trigger text NOT NULL CHECK (trigger IN ('schedule', 'manual')),
+    outcome text NOT NULL CHECK (outcome IN ('running', 'succeeded', 'failed')),
+    rows_affected bigint,
+    error text,
+    started_at timestamptz NOT NULL,
+    finished_at timestamptz
+);
+
+CREATE INDEX IF NOT EXISTS job_runs_job_started_at_idx
+    ON maintenance.job_runs (job, started_at DESC);
@@ -0,0 +1,28 @@
+-- Add up migration script here
+
+-- Background jobs waiting to be run by the queue workers
+CREATE TABLE IF NOT EXISTS maintenance.queued_jobs(
+    id uuid PRIMARY KEY,
+    kind text NOT NULL,
+    payload jsonb NOT NULL,
+    attempts integer NOT NULL DEFAULT 0,
+    max_attempts integer NOT NULL,
+    run_at timestamptz NOT NULL,
+    locked_at timestamptz,
+    last_error text,
+    created_at timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS queued_jobs_run_at_idx
+    ON maintenance.queued_jobs (run_at);
+
+-- Background jobs that failed all their attempts
+CREATE TABLE IF NOT EXISTS maintenance.dead_letter_jobs(
+    id uuid PRIMARY KEY,
+    kind text NOT NULL,
+    payload jsonb NOT NULL,
+    attempts integer NOT NULL,
+    last_error text,
+    created_at timestamptz NOT NULL,
+    failed_at timestamptz NOT NULL
+);
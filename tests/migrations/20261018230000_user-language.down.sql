@@ -0,0 +1,3 @@
+-- Add down migration script here
+
+ALTER TABLE accounts.users DROP COLUMN IF EXISTS language;
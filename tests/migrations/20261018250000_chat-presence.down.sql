@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS features.chat_presence;
+DROP TABLE IF EXISTS features.chat_instances;
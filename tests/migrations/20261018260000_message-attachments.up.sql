@@ -0,0 +1,24 @@
+-- Add up migration script here
+
+-- Files and listings attached to direct messages.
+-- Images are uploaded before the message is sent, until the message claims
+-- them `message_id` is not set; unclaimed images are removed by the
+-- orphaned files cleanup.
+CREATE TABLE IF NOT EXISTS features.message_attachments(
+    id uuid PRIMARY KEY,
+    message_id uuid REFERENCES features.direct_messages (id) ON DELETE CASCADE,
+    uploader_id uuid REFERENCES accounts.users (id) ON DELETE CASCADE NOT NULL,
+    kind text NOT NULL CHECK (kind IN ('image', 'harvest')),
+    image text,
+    harvest_id uuid REFERENCES services.harvests (id) ON DELETE SET NULL,
+    created_at timestamptz NOT NULL,
+    CHECK ((kind = 'image') = (image IS NOT NULL)),
+    CHECK (kind = 'image' OR message_id IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS message_attachments_message_id_idx
+    ON features.message_attachments (message_id);
+
+CREATE INDEX IF NOT EXISTS message_attachments_unclaimed_idx
+    ON features.message_attachments (created_at)
+    WHERE message_id IS NULL;
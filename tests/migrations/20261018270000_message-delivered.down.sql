@@ -0,0 +1,3 @@
+-- Add down migration script here
+
+ALTER TABLE features.message_status DROP COLUMN IF EXISTS delivered_at;
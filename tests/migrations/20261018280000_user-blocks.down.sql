@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS features.conversation_reports;
+DROP TABLE IF EXISTS accounts.user_blocks;
@@ -0,0 +1,34 @@
+-- Add up migration script here
+
+-- Users blocked by the user, blocked users can not
+-- message the user or see when the user is online.
+CREATE TABLE IF NOT EXISTS accounts.user_blocks(
+    user_id uuid REFERENCES accounts.users (id) ON DELETE CASCADE,
+    blocked_user_id uuid REFERENCES accounts.users (id) ON DELETE CASCADE,
+    created_at timestamptz NOT NULL,
+    PRIMARY KEY(user_id, blocked_user_id),
+    CHECK (user_id <> blocked_user_id)
+);
+
+CREATE INDEX IF NOT EXISTS user_blocks_blocked_user_id_idx
+    ON accounts.user_blocks (blocked_user_id);
+
+-- Conversations reported by users, reviewed by staff.
+-- `messages` is a snapshot of the conversation when it was reported,
+-- it is kept when the messages are deleted.
+CREATE TABLE IF NOT EXISTS features.conversation_reports(
+    id uuid PRIMARY KEY,
+    reporter_id uuid REFERENCES accounts.users (id) ON DELETE CASCADE NOT NULL,
+    reported_user_id uuid REFERENCES accounts.users (id) ON DELETE CASCADE NOT NULL,
+    reason text NOT NULL,
+    messages jsonb NOT NULL,
+    status text NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'dismissed', 'actioned')),
+    reviewed_by uuid REFERENCES accounts.users (id) ON DELETE SET NULL,
+    reviewed_at timestamptz,
+    created_at timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS conversation_reports_open_idx
+    ON features.conversation_reports (created_at)
+    WHERE status = 'open';
@@ -0,0 +1,10 @@
+-- Add up migration script here
+
+-- Escapes the HTML special characters of the text,
+-- search highlights are built from user entered text.
+CREATE OR REPLACE FUNCTION services.html_escape(text text)
+RETURNS text
+LANGUAGE sql IMMUTABLE
+AS $$
+    SELECT replace(replace(replace(text, '&', '&amp;'), '<', '&lt;'), '>', '&gt;');
+$$;
@@ -0,0 +1,13 @@
+-- Add up migration script here
+
+-- Only the currencies the server supports are accepted,
+-- prices are never shown in another currency.
+ALTER TABLE services.countries
+    DROP CONSTRAINT IF EXISTS countries_currency_check,
+    ADD CONSTRAINT countries_currency_check CHECK (currency IN (
+        'NAD', 'ZAR', 'BWP', 'ZMW', 'AOA', 'MZN', 'LSL', 'SZL', 'MWK',
+        'KES', 'UGX', 'RWF', 'NGN', 'GHS', 'USD', 'EUR', 'GBP'
+    ));
+
+CREATE INDEX IF NOT EXISTS harvests_active_currency_idx
+    ON services.harvests ((price->>'currency')) WHERE finished = false;